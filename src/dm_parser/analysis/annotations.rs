@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+
+use crate::dm_parser::{
+    ast::{declaration::ProcDeclaration, statement::StatementKind},
+    diagnostics::Diagnostic,
+    object_tree::{procs::ProcRef, ObjectTree},
+};
+
+use super::{
+    call_graph::{CallGraph, Cause, Propagation},
+    sleep,
+};
+
+pub const SHOULD_CALL_PARENT: &str = "should_call_parent";
+pub const SHOULD_NOT_OVERRIDE: &str = "should_not_override";
+pub const PRIVATE_PROC: &str = "private_proc";
+pub const PROTECTED_PROC: &str = "protected_proc";
+pub const SHOULD_NOT_SLEEP: &str = "should_not_sleep";
+pub const SHOULD_BE_PURE: &str = "should_be_pure";
+
+/// The `set SpacemanDMM_*` settings that put requirements on a proc and its overrides. Codebases
+/// usually set them through macros such as `SHOULD_CALL_PARENT(TRUE)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Annotation {
+    /// Overrides must call `..()`.
+    ShouldCallParent,
+    /// Subtypes must not override the proc.
+    ShouldNotOverride,
+    /// Only procs of the same type may call it.
+    PrivateProc,
+    /// Only procs of the same type and its subtypes may call it.
+    ProtectedProc,
+    /// Neither the proc nor anything it waits for may sleep.
+    ShouldNotSleep,
+    /// The proc may only change its own locals.
+    ShouldBePure,
+}
+
+impl Annotation {
+    pub fn setting(self) -> &'static str {
+        match self {
+            Annotation::ShouldCallParent => "SpacemanDMM_should_call_parent",
+            Annotation::ShouldNotOverride => "SpacemanDMM_should_not_override",
+            Annotation::PrivateProc => "SpacemanDMM_private_proc",
+            Annotation::ProtectedProc => "SpacemanDMM_protected_proc",
+            Annotation::ShouldNotSleep => "SpacemanDMM_should_not_sleep",
+            Annotation::ShouldBePure => "SpacemanDMM_should_be_pure",
+        }
+    }
+}
+
+/// Whether `set name = value` in the body of `definition` sets a true value, `None` if the body
+/// does not set it. A value that is not constant counts as false, the last `set` wins.
+pub(super) fn setting(tree: &ObjectTree, definition: &ProcDeclaration, name: &str) -> Option<bool> {
+    let value = definition
+        .body
+        .iter()
+        .rev()
+        .find_map(|statement| match &statement.kind {
+            StatementKind::Set {
+                name: setting,
+                value,
+                is_in: false,
+            } if *setting == name => Some(value),
+            _ => None,
+        })?;
+    Some(
+        tree.evaluate_constant(value, &definition.owner)
+            .is_ok_and(|value| value.is_truthy()),
+    )
+}
+
+/// The definition that puts `annotation` on `proc`, the closest one in its override chain that
+/// sets it. `None` if that definition sets it to false or nothing sets it.
+pub fn annotated_by(tree: &ObjectTree, proc: &ProcRef, annotation: Annotation) -> Option<ProcRef> {
+    tree.override_chain(proc)
+        .find_map(|proc| {
+            let definition = tree.proc_definition(&proc)?;
+            setting(tree, definition, annotation.setting()).map(|set| set.then_some(proc))
+        })
+        .flatten()
+}
+
+/// Reports the procs that break the annotations on them or on the procs they override or call.
+pub fn check(tree: &ObjectTree, graph: &CallGraph) -> Vec<Diagnostic> {
+    let sleeping = sleep::analyze(tree, graph);
+    let impure = impure_procs(tree, graph);
+    let mut diagnostics = vec![];
+    for proc in tree.proc_refs() {
+        let Some(definition) = tree.proc_definition(&proc) else {
+            continue;
+        };
+        let overridden = tree.overridden(&proc);
+
+        // the definition that sets the annotation asks it of its overrides
+        if let Some(annotated) = annotated_by(tree, &proc, Annotation::ShouldCallParent)
+            .filter(|annotated| *annotated != proc)
+        {
+            if overridden.is_some() && tree.parent_calls(&proc).is_empty() {
+                let diagnostic = Diagnostic::error(
+                    SHOULD_CALL_PARENT,
+                    &definition.span,
+                    format!("`{proc}` does not call `..()`"),
+                )
+                .with_help("call `..()` in the override");
+                diagnostics.push(annotation_note(tree, diagnostic, &annotated, "requires it"));
+            }
+        }
+
+        if let Some(annotated) = overridden
+            .and_then(|overridden| annotated_by(tree, &overridden, Annotation::ShouldNotOverride))
+            .filter(|annotated| annotated.owner != proc.owner)
+        {
+            let diagnostic = Diagnostic::error(
+                SHOULD_NOT_OVERRIDE,
+                &definition.span,
+                format!("`{proc}` overrides `{annotated}`, which should not be overridden"),
+            );
+            diagnostics.push(annotation_note(tree, diagnostic, &annotated, "forbids it"));
+        }
+
+        if sleeping.can_sleep(&proc) {
+            if let Some(annotated) = annotated_by(tree, &proc, Annotation::ShouldNotSleep) {
+                let diagnostic = Diagnostic::error(
+                    SHOULD_NOT_SLEEP,
+                    &definition.span,
+                    format!("`{proc}` must not sleep but can"),
+                );
+                let diagnostic = chain_notes(diagnostic, sleeping.sleep_chain(&proc), "can sleep");
+                diagnostics.push(annotation_note(tree, diagnostic, &annotated, "forbids it"));
+            }
+        }
+
+        if impure.contains(&proc) {
+            if let Some(annotated) = annotated_by(tree, &proc, Annotation::ShouldBePure) {
+                let diagnostic = Diagnostic::error(
+                    SHOULD_BE_PURE,
+                    &definition.span,
+                    format!("`{proc}` should be pure but changes state"),
+                );
+                let diagnostic = chain_notes(diagnostic, impure.chain(&proc), "is impure");
+                diagnostics.push(annotation_note(tree, diagnostic, &annotated, "requires it"));
+            }
+        }
+
+        diagnostics.extend(check_access(tree, graph, &proc));
+    }
+    diagnostics
+}
+
+/// Calls from `caller` to private and protected procs of other types.
+fn check_access(tree: &ObjectTree, graph: &CallGraph, caller: &ProcRef) -> Vec<Diagnostic> {
+    let Some(facts) = graph.facts(caller) else {
+        return vec![];
+    };
+    let mut diagnostics = vec![];
+    for call in facts.calls.iter().filter(|call| call.direct) {
+        let callee = &call.callee;
+        let (code, annotated) = if let Some(annotated) =
+            annotated_by(tree, callee, Annotation::PrivateProc)
+                .filter(|annotated| annotated.owner != caller.owner)
+        {
+            (PRIVATE_PROC, annotated)
+        } else if let Some(annotated) = annotated_by(tree, callee, Annotation::ProtectedProc)
+            .filter(|annotated| !tree.is_subtype_of(&caller.owner, &annotated.owner))
+        {
+            (PROTECTED_PROC, annotated)
+        } else {
+            continue;
+        };
+        let message = if code == PRIVATE_PROC {
+            format!("`{callee}` is private to `{}`", annotated.owner)
+        } else {
+            format!(
+                "`{callee}` is protected, only `{}` and its subtypes can call it",
+                annotated.owner
+            )
+        };
+        let diagnostic = Diagnostic::error(code, &call.span, message);
+        diagnostics.push(annotation_note(
+            tree,
+            diagnostic,
+            &annotated,
+            "restricts it",
+        ));
+    }
+    diagnostics
+}
+
+/// The procs that change vars other than their own locals, directly or through the procs they
+/// call.
+fn impure_procs(tree: &ObjectTree, graph: &CallGraph) -> Propagation {
+    let mut direct = HashMap::new();
+    for proc in tree.proc_refs() {
+        let Some(write) = graph.facts(&proc).and_then(|facts| facts.writes.first()) else {
+            continue;
+        };
+        let target = write.text().unwrap_or("a var");
+        let cause = Cause::Direct {
+            span: write.clone(),
+            description: format!("assigns to `{target}`"),
+        };
+        direct.insert(proc, cause);
+    }
+    graph.propagate(direct, |_| true)
+}
+
+/// Adds a note for every step of `chain`, from the call in the reported proc down to the code
+/// that causes it.
+fn chain_notes(
+    mut diagnostic: Diagnostic,
+    chain: Vec<(ProcRef, &Cause)>,
+    property: &str,
+) -> Diagnostic {
+    for (_, cause) in chain {
+        let note = match cause {
+            Cause::Direct { description, .. } => description.clone(),
+            Cause::Call { callee, .. } => format!("calls `{callee}`, which {property}"),
+        };
+        diagnostic = diagnostic.with_note(cause.span(), note);
+    }
+    diagnostic
+}
+
+/// Points to the definition whose `set` the diagnostic is about.
+fn annotation_note(
+    tree: &ObjectTree,
+    diagnostic: Diagnostic,
+    annotated: &ProcRef,
+    verb: &str,
+) -> Diagnostic {
+    match tree.proc_definition(annotated) {
+        Some(definition) => diagnostic.with_note(
+            &definition.span,
+            format!("the annotation on `{annotated}` {verb}"),
+        ),
+        None => diagnostic,
+    }
+}
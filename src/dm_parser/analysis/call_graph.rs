@@ -0,0 +1,344 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    dm_parser::{
+        ast::{
+            expression::{Expression, ExpressionKind, FieldAccess, UnaryOp},
+            statement::{Statement, StatementKind},
+            visit::{self, Visitor},
+        },
+        object_tree::{procs::ProcRef, resolve::ResolvedPath, ObjectTree},
+        parsing::type_path::DmTypePath,
+    },
+    tokens::span::Span,
+    util::symbol::Symbol,
+};
+
+/// A call from one proc to another.
+#[derive(Debug, Clone)]
+pub struct Call {
+    pub callee: ProcRef,
+    pub span: Span,
+    /// False when `callee` is an override on a subtype of the type the proc is called on, which
+    /// only runs for instances of that subtype.
+    pub direct: bool,
+    /// The call is in a `spawn()` block, the proc does not wait for it.
+    pub spawned: bool,
+}
+
+/// What the body of a proc does, as far as the analyses over the call graph care.
+#[derive(Debug, Clone, Default)]
+pub struct ProcFacts {
+    pub calls: Vec<Call>,
+    /// `input()` expressions, with whether they are in a `spawn()` block.
+    pub inputs: Vec<(Span, bool)>,
+    /// Assignments to anything but the locals of the proc and `.`.
+    pub writes: Vec<Span>,
+}
+
+/// The procs every proc definition in the tree calls. Calls are resolved through the declared
+/// types of vars, a call may run the implementation of any subtype of the receiver.
+#[derive(Debug, Default)]
+pub struct CallGraph {
+    facts: HashMap<ProcRef, ProcFacts>,
+    callers: HashMap<ProcRef, Vec<(ProcRef, Call)>>,
+}
+
+impl CallGraph {
+    pub fn build(tree: &ObjectTree) -> Self {
+        let mut overrides: HashMap<Symbol, Vec<ProcRef>> = HashMap::new();
+        for node in tree.iter() {
+            for proc in node.procs() {
+                if let Some(implementation) = tree.implementation(node.path(), proc.name()) {
+                    overrides
+                        .entry(proc.name())
+                        .or_default()
+                        .push(implementation);
+                }
+            }
+        }
+
+        let mut graph = Self::default();
+        for proc in tree.proc_refs() {
+            let Some(definition) = tree.proc_definition(&proc) else {
+                continue;
+            };
+            let mut collector = CallCollector {
+                tree,
+                overrides: &overrides,
+                proc: &proc,
+                locals: definition
+                    .params
+                    .iter()
+                    .map(|param| (param.name, param.var_type.clone()))
+                    .collect(),
+                spawned: false,
+                facts: ProcFacts::default(),
+            };
+            visit::walk_proc(&mut collector, definition);
+            let facts = collector.facts;
+            for call in &facts.calls {
+                graph
+                    .callers
+                    .entry(call.callee.clone())
+                    .or_default()
+                    .push((proc.clone(), call.clone()));
+            }
+            graph.facts.insert(proc, facts);
+        }
+        graph
+    }
+
+    pub fn facts(&self, proc: &ProcRef) -> Option<&ProcFacts> {
+        self.facts.get(proc)
+    }
+
+    /// The calls to `proc` and the procs they are in.
+    pub fn callers(&self, proc: &ProcRef) -> &[(ProcRef, Call)] {
+        self.callers.get(proc).map_or(&[], Vec::as_slice)
+    }
+
+    /// Spreads a property of procs such as sleeping to every proc that calls one that has it,
+    /// through the calls `follow` accepts. Every proc keeps the shortest call chain to a proc in
+    /// `direct`.
+    pub fn propagate(
+        &self,
+        direct: HashMap<ProcRef, Cause>,
+        follow: impl Fn(&Call) -> bool,
+    ) -> Propagation {
+        let mut queue: VecDeque<ProcRef> = direct.keys().cloned().collect();
+        let mut causes = direct;
+        while let Some(callee) = queue.pop_front() {
+            for (caller, call) in self.callers(&callee) {
+                if causes.contains_key(caller) || !follow(call) {
+                    continue;
+                }
+                let cause = Cause::Call {
+                    span: call.span.clone(),
+                    callee: callee.clone(),
+                };
+                causes.insert(caller.clone(), cause);
+                queue.push_back(caller.clone());
+            }
+        }
+        Propagation { causes }
+    }
+}
+
+/// Why a proc has a property that spreads through calls.
+#[derive(Debug, Clone)]
+pub enum Cause {
+    /// Code in the proc itself, such as a call to `sleep()`.
+    Direct { span: Span, description: String },
+    /// A call to a proc that has the property.
+    Call { span: Span, callee: ProcRef },
+}
+
+impl Cause {
+    pub fn span(&self) -> &Span {
+        match self {
+            Cause::Direct { span, .. } | Cause::Call { span, .. } => span,
+        }
+    }
+}
+
+/// The procs that have a property, with the reason for each.
+#[derive(Debug, Default)]
+pub struct Propagation {
+    causes: HashMap<ProcRef, Cause>,
+}
+
+impl Propagation {
+    pub fn contains(&self, proc: &ProcRef) -> bool {
+        self.causes.contains_key(proc)
+    }
+
+    pub fn cause(&self, proc: &ProcRef) -> Option<&Cause> {
+        self.causes.get(proc)
+    }
+
+    /// The calls from `proc` down to the code that gives it the property, each with the proc it
+    /// is in. Empty if `proc` does not have it.
+    pub fn chain(&self, proc: &ProcRef) -> Vec<(ProcRef, &Cause)> {
+        let mut chain = vec![];
+        let mut current = proc.clone();
+        while let Some(cause) = self.causes.get(&current) {
+            chain.push((current, cause));
+            match cause {
+                Cause::Direct { .. } => break,
+                Cause::Call { callee, .. } => current = callee.clone(),
+            }
+        }
+        chain
+    }
+}
+
+struct CallCollector<'a> {
+    tree: &'a ObjectTree,
+    /// Every proc name with the implementations of the types that define it.
+    overrides: &'a HashMap<Symbol, Vec<ProcRef>>,
+    proc: &'a ProcRef,
+    /// The params and local vars of the proc with their declared types.
+    locals: HashMap<Symbol, Option<DmTypePath>>,
+    /// Inside a `spawn()` block.
+    spawned: bool,
+    facts: ProcFacts,
+}
+
+impl CallCollector<'_> {
+    /// The type an expression is declared as, for the receivers of `a.foo()`.
+    fn expression_type(&self, expression: &Expression) -> Option<DmTypePath> {
+        match &expression.kind {
+            ExpressionKind::Ident(name) => {
+                if let Some(var_type) = self.locals.get(name) {
+                    return var_type.clone();
+                }
+                match name.as_str() {
+                    "src" => Some(self.proc.owner.clone()),
+                    "usr" => Some(DmTypePath::from("/mob")),
+                    "world" => Some(DmTypePath::from("/world")),
+                    _ => self.var_type(&self.proc.owner, *name),
+                }
+            }
+            ExpressionKind::Field {
+                object,
+                access: FieldAccess::Dot | FieldAccess::SafeDot,
+                name,
+            } => self.var_type(&self.expression_type(object)?, *name),
+            _ => None,
+        }
+    }
+
+    fn var_type(&self, path: &DmTypePath, name: Symbol) -> Option<DmTypePath> {
+        let (_, declaration) = self.tree.find_var(path, name)?;
+        declaration.var_type.clone()
+    }
+
+    /// Records a call to `name` on an instance of `receiver`, which runs the implementation of
+    /// `receiver` or an override on any of its subtypes.
+    fn call(&mut self, receiver: &DmTypePath, name: Symbol, span: &Span) {
+        let Some(implementation) = self.tree.implementation(receiver, name) else {
+            return;
+        };
+        let overrides = self.overrides.get(&name).into_iter().flatten();
+        for proc in overrides {
+            if proc.owner != implementation.owner && self.tree.is_subtype_of(&proc.owner, receiver)
+            {
+                self.add_call(proc.clone(), span, false);
+            }
+        }
+        self.add_call(implementation, span, true);
+    }
+
+    fn add_call(&mut self, callee: ProcRef, span: &Span, direct: bool) {
+        self.facts.calls.push(Call {
+            callee,
+            span: span.clone(),
+            direct,
+            spawned: self.spawned,
+        });
+    }
+
+    /// Records an assignment unless it is to a local var or the return value.
+    fn write(&mut self, target: &Expression) {
+        let is_local = match &target.kind {
+            ExpressionKind::Dot => true,
+            ExpressionKind::Ident(name) => self.locals.contains_key(name),
+            // changing a list held in a local
+            ExpressionKind::Index { object, .. } => {
+                matches!(&object.kind, ExpressionKind::Ident(name) if self.locals.contains_key(name))
+            }
+            _ => false,
+        };
+        if !is_local {
+            self.facts.writes.push(target.span.clone());
+        }
+    }
+}
+
+impl Visitor for CallCollector<'_> {
+    fn visit_statement(&mut self, statement: &Statement) {
+        match &statement.kind {
+            // the body runs after the proc returns or sleeps, only the delay is waited for
+            StatementKind::Spawn { delay, body } => {
+                if let Some(delay) = delay {
+                    self.visit_expression(delay);
+                }
+                let spawned = std::mem::replace(&mut self.spawned, true);
+                visit::walk_block(self, body);
+                self.spawned = spawned;
+                return;
+            }
+            StatementKind::Var(vars) => {
+                for var in vars {
+                    self.locals.insert(var.name, var.var_type.clone());
+                }
+            }
+            StatementKind::ForIn { var, .. } | StatementKind::ForRange { var, .. }
+                if var.declared =>
+            {
+                self.locals.insert(var.name, var.var_type.clone());
+            }
+            _ => {}
+        }
+        visit::walk_statement(self, statement);
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        match &expression.kind {
+            ExpressionKind::Call { callee, .. } => match &callee.kind {
+                ExpressionKind::ParentProc => {
+                    if let Some(parent) = self.tree.overridden(self.proc) {
+                        self.add_call(parent, &expression.span, true);
+                    }
+                }
+                ExpressionKind::Ident(name) => {
+                    let receiver = if self.tree.find_proc(&self.proc.owner, *name).is_some() {
+                        self.proc.owner.clone()
+                    } else {
+                        DmTypePath::default()
+                    };
+                    self.call(&receiver, *name, &expression.span);
+                }
+                ExpressionKind::Field {
+                    object,
+                    access: FieldAccess::Dot | FieldAccess::SafeDot,
+                    name,
+                } => {
+                    if let Some(receiver) = self.expression_type(object) {
+                        self.call(&receiver, *name, &expression.span);
+                    }
+                }
+                _ => {}
+            },
+            ExpressionKind::New {
+                new_type: Some(new_type),
+                ..
+            } => {
+                if let ExpressionKind::Path { path, .. } = &new_type.kind {
+                    if let Ok(ResolvedPath::Type(path)) =
+                        self.tree.resolve_path(path, &self.proc.owner)
+                    {
+                        self.call(&path, Symbol::from("New"), &expression.span);
+                    }
+                }
+            }
+            ExpressionKind::Input { .. } => {
+                self.facts
+                    .inputs
+                    .push((expression.span.clone(), self.spawned));
+            }
+            ExpressionKind::Assign { target, .. } => self.write(target),
+            ExpressionKind::Unary {
+                op:
+                    UnaryOp::PreIncrement
+                    | UnaryOp::PreDecrement
+                    | UnaryOp::PostIncrement
+                    | UnaryOp::PostDecrement,
+                operand,
+            } => self.write(operand),
+            _ => {}
+        }
+        visit::walk_expression(self, expression);
+    }
+}
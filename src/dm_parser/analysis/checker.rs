@@ -0,0 +1,449 @@
+use std::collections::HashMap;
+
+use crate::{
+    dm_parser::{
+        ast::{
+            declaration::{Declaration, ProcDeclaration, VarDeclaration},
+            expression::{Argument, Expression, ExpressionKind, FieldAccess, UnaryOp},
+            statement::{Statement, StatementKind},
+            visit::{self, Visitor},
+            DmAst,
+        },
+        diagnostics::Diagnostic,
+        object_tree::ObjectTree,
+        parsing::type_path::DmTypePath,
+    },
+    tokens::span::Span,
+    util::symbol::Symbol,
+};
+
+pub const UNDEFINED_VAR: &str = "undefined_var";
+pub const UNDEFINED_PROC: &str = "undefined_proc";
+pub const UNDEFINED_TYPE: &str = "undefined_type";
+pub const CONST_ASSIGNMENT: &str = "const_assignment";
+pub const TOO_MANY_ARGUMENTS: &str = "too_many_arguments";
+pub const UNKNOWN_ARGUMENT: &str = "unknown_argument";
+
+/// Names every proc can use without declaring them.
+const IMPLICIT_VARS: &[&str] = &[
+    "src",
+    "usr",
+    "world",
+    "args",
+    "global",
+    "callee",
+    "caller",
+    "__FILE__",
+    "__LINE__",
+    "__PROC__",
+    "__TYPE__",
+    "__IMPLIED_TYPE__",
+];
+
+/// Reports the errors DreamMaker reports for names that do not exist: undefined vars, procs and
+/// types, assignments to `const` vars and calls with arguments the proc does not take.
+pub fn check(tree: &ObjectTree, asts: &[DmAst]) -> Vec<Diagnostic> {
+    let mut checker = Checker {
+        tree,
+        context: DmTypePath::default(),
+        scopes: vec![],
+        diagnostics: vec![],
+    };
+    for ast in asts {
+        visit::walk_ast(&mut checker, ast);
+    }
+    let mut diagnostics = checker.diagnostics;
+    // the object tree walks path literals with the same context as the checker
+    diagnostics.extend(tree.unresolved_paths(asts).into_iter().map(|unresolved| {
+        Diagnostic::error(
+            UNDEFINED_TYPE,
+            &unresolved.span,
+            unresolved.error.to_string(),
+        )
+    }));
+    diagnostics
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Local {
+    pub(super) var_type: Option<DmTypePath>,
+    pub(super) is_const: bool,
+    pub(super) span: Span,
+}
+
+/// What a name used in code refers to.
+pub(super) enum Variable<'a> {
+    Implicit,
+    Local(&'a Local),
+    Declared(&'a VarDeclaration),
+}
+
+pub(super) struct Checker<'a> {
+    pub(super) tree: &'a ObjectTree,
+    /// The type the code being checked belongs to.
+    pub(super) context: DmTypePath,
+    /// The locals of the proc being checked, the innermost block last. Empty outside of procs.
+    /// A local may also narrow the type of a var inside a block, see `narrow`.
+    pub(super) scopes: Vec<HashMap<Symbol, Local>>,
+    pub(super) diagnostics: Vec<Diagnostic>,
+}
+
+impl Visitor for Checker<'_> {
+    fn visit_declaration(&mut self, declaration: &Declaration) {
+        if let Declaration::Var(var) = declaration {
+            self.context = var.owner.clone();
+            self.scopes.clear();
+            if !var.is_declaration {
+                self.check_override(var);
+            }
+        }
+        visit::walk_declaration(self, declaration);
+    }
+
+    fn visit_proc(&mut self, proc: &ProcDeclaration) {
+        self.context = proc.owner.clone();
+        let params = proc
+            .params
+            .iter()
+            .map(|param| {
+                let local = Local {
+                    var_type: param.var_type.clone(),
+                    is_const: false,
+                    span: param.span.clone(),
+                };
+                (param.name, local)
+            })
+            .collect();
+        self.scopes = vec![params];
+        visit::walk_proc(self, proc);
+        self.scopes.clear();
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        match &statement.kind {
+            StatementKind::Var(vars) => {
+                for var in vars {
+                    visit::walk_local_var(self, var);
+                    let local = Local {
+                        var_type: var.var_type.clone(),
+                        is_const: var.modifiers.is_const,
+                        span: var.span.clone(),
+                    };
+                    self.declare(var.name, local);
+                }
+            }
+            StatementKind::ForIn { var, .. } | StatementKind::ForRange { var, .. } => {
+                self.scopes.push(HashMap::new());
+                if var.declared {
+                    let local = Local {
+                        var_type: var.var_type.clone(),
+                        is_const: false,
+                        span: var.span.clone(),
+                    };
+                    self.declare(var.name, local);
+                } else {
+                    self.check_variable(var.name, &var.span);
+                }
+                visit::walk_statement(self, statement);
+                self.scopes.pop();
+            }
+            StatementKind::Try { catch, .. } => {
+                self.scopes.push(HashMap::new());
+                if let Some(var) = catch.as_ref().and_then(|catch| catch.var.as_ref()) {
+                    let local = Local {
+                        var_type: var.var_type.clone(),
+                        is_const: false,
+                        span: var.span.clone(),
+                    };
+                    self.declare(var.name, local);
+                }
+                visit::walk_statement(self, statement);
+                self.scopes.pop();
+            }
+            StatementKind::If { arms, else_arm } => self.visit_if(arms, else_arm.as_ref()),
+            StatementKind::Expression(_)
+            | StatementKind::Return(_)
+            | StatementKind::Break(_)
+            | StatementKind::Continue(_)
+            | StatementKind::Goto(_)
+            | StatementKind::Del(_)
+            | StatementKind::Throw(_)
+            | StatementKind::Set { .. } => visit::walk_statement(self, statement),
+            // everything else has a block of its own
+            _ => {
+                self.scopes.push(HashMap::new());
+                visit::walk_statement(self, statement);
+                self.scopes.pop();
+            }
+        }
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        match &expression.kind {
+            ExpressionKind::Ident(name) => {
+                self.check_variable(*name, &expression.span);
+            }
+            ExpressionKind::Call { callee, args } => {
+                self.check_call(expression, callee, args);
+            }
+            ExpressionKind::Field {
+                object,
+                access,
+                name,
+            } => {
+                self.check_member_access(expression, object, *access, *name);
+                visit::walk_expression(self, expression);
+            }
+            ExpressionKind::Assign { target, .. } => {
+                self.check_assignment(target);
+                visit::walk_expression(self, expression);
+            }
+            ExpressionKind::Unary {
+                op:
+                    UnaryOp::PreIncrement
+                    | UnaryOp::PreDecrement
+                    | UnaryOp::PostIncrement
+                    | UnaryOp::PostDecrement,
+                operand,
+            } => {
+                self.check_assignment(operand);
+                visit::walk_expression(self, expression);
+            }
+            ExpressionKind::New { new_type, args } => {
+                if let Some(new_type) = new_type {
+                    self.visit_expression(new_type);
+                }
+                self.visit_arguments(args);
+            }
+            ExpressionKind::List { args, .. } => self.visit_arguments(args),
+            ExpressionKind::Locate { args, in_list }
+            | ExpressionKind::Input { args, in_list, .. } => {
+                self.visit_arguments(args);
+                if let Some(in_list) = in_list {
+                    self.visit_expression(in_list);
+                }
+            }
+            ExpressionKind::DynamicCall { target, args, .. } => {
+                self.visit_arguments(target);
+                self.visit_arguments(args);
+            }
+            _ => visit::walk_expression(self, expression),
+        }
+    }
+}
+
+impl Checker<'_> {
+    /// Named arguments and `list(a = 1)` keys are names, not vars.
+    fn visit_arguments(&mut self, args: &[Argument]) {
+        for arg in args {
+            if let Some(key) = &arg.key {
+                if arg.name().is_none() {
+                    self.visit_expression(key);
+                }
+            }
+            self.visit_expression(&arg.value);
+        }
+    }
+
+    pub(super) fn declare(&mut self, name: Symbol, local: Local) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name, local);
+        }
+    }
+
+    pub(super) fn lookup(&self, name: Symbol) -> Option<Variable<'_>> {
+        if let Some(local) = self.scopes.iter().rev().find_map(|scope| scope.get(&name)) {
+            return Some(Variable::Local(local));
+        }
+        if let Some((_, declaration)) = self.tree.find_var(&self.context, name) {
+            return Some(Variable::Declared(declaration));
+        }
+        if let Some(declaration) = self.tree.root().var(name).and_then(|var| var.declaration()) {
+            return Some(Variable::Declared(declaration));
+        }
+        IMPLICIT_VARS
+            .contains(&name.as_str())
+            .then_some(Variable::Implicit)
+    }
+
+    fn check_variable(&mut self, name: Symbol, span: &Span) {
+        if self.lookup(name).is_none() {
+            self.diagnostics.push(Diagnostic::error(
+                UNDEFINED_VAR,
+                span,
+                format!("undefined var `{name}`"),
+            ));
+        }
+    }
+
+    /// `name = value` on a type must change a var declared on the type or a parent.
+    fn check_override(&mut self, var: &VarDeclaration) {
+        match self.tree.find_var(&var.owner, var.name) {
+            None => self.diagnostics.push(Diagnostic::error(
+                UNDEFINED_VAR,
+                &var.span,
+                format!("`{}` has no var `{}` to override", var.owner, var.name),
+            )),
+            Some((_, declaration)) if declaration.modifiers.is_const => {
+                self.diagnostics.push(
+                    Diagnostic::error(
+                        CONST_ASSIGNMENT,
+                        &var.span,
+                        format!("cannot override const var `{}`", var.name),
+                    )
+                    .with_note(&declaration.span, "declared here"),
+                );
+            }
+            Some(_) => {}
+        }
+    }
+
+    fn check_assignment(&mut self, target: &Expression) {
+        let declared_at = match &target.kind {
+            ExpressionKind::Ident(name) => match self.lookup(*name) {
+                Some(Variable::Local(local)) if local.is_const => Some(local.span.clone()),
+                Some(Variable::Declared(declaration)) if declaration.modifiers.is_const => {
+                    Some(declaration.span.clone())
+                }
+                _ => None,
+            },
+            ExpressionKind::Field {
+                object,
+                access: FieldAccess::Dot | FieldAccess::SafeDot,
+                name,
+            } => self
+                .expression_type(object)
+                .and_then(|object_type| self.tree.find_var(&object_type, *name))
+                .filter(|(_, declaration)| declaration.modifiers.is_const)
+                .map(|(_, declaration)| declaration.span.clone()),
+            _ => None,
+        };
+        if let Some(declared_at) = declared_at {
+            let name = target.span.text().unwrap_or("var");
+            self.diagnostics.push(
+                Diagnostic::error(
+                    CONST_ASSIGNMENT,
+                    &target.span,
+                    format!("cannot assign to `{name}`, it is a const var"),
+                )
+                .with_note(&declared_at, "declared here"),
+            );
+        }
+    }
+
+    fn check_call(&mut self, call: &Expression, callee: &Expression, args: &[Argument]) {
+        match &callee.kind {
+            ExpressionKind::Ident(_) => {}
+            ExpressionKind::Field {
+                object,
+                access: FieldAccess::Dot | FieldAccess::SafeDot,
+                ..
+            } => self.visit_expression(object),
+            _ => {
+                self.visit_expression(callee);
+                self.visit_arguments(args);
+                return;
+            }
+        }
+        let Some((receiver, name)) = self.call_receiver(callee) else {
+            return;
+        };
+        self.visit_arguments(args);
+
+        let Some(receiver) = receiver else {
+            return;
+        };
+        if self.tree.find_proc(&receiver, name).is_none() {
+            let message = if receiver.is_root() {
+                format!("undefined proc `{name}`")
+            } else {
+                format!("`{receiver}` has no proc `{name}`")
+            };
+            self.diagnostics
+                .push(Diagnostic::error(UNDEFINED_PROC, &callee.span, message));
+            return;
+        }
+        self.check_arguments(call, &receiver, name, args);
+    }
+
+    /// Checks the arguments against every definition the call could run, any of them may name
+    /// a parameter.
+    fn check_arguments(
+        &mut self,
+        call: &Expression,
+        receiver: &DmTypePath,
+        name: Symbol,
+        args: &[Argument],
+    ) {
+        let spreads_list = args.iter().any(|arg| {
+            matches!(&arg.value.kind, ExpressionKind::Call { callee, .. }
+                if callee.kind == ExpressionKind::Ident(Symbol::intern("arglist")))
+        });
+        let Some(implementation) = self.tree.implementation(receiver, name) else {
+            return;
+        };
+        let definitions: Vec<&ProcDeclaration> = self
+            .tree
+            .override_chain(&implementation)
+            .filter_map(|proc| self.tree.proc_definition(&proc))
+            .collect();
+        if spreads_list
+            || definitions
+                .iter()
+                .any(|definition| definition.variadic || uses_args(definition))
+        {
+            return;
+        }
+
+        let max_params = definitions
+            .iter()
+            .map(|definition| definition.params.len())
+            .max()
+            .unwrap_or(0);
+        let positional = args.iter().filter(|arg| arg.key.is_none()).count();
+        if positional > max_params {
+            self.diagnostics.push(Diagnostic::error(
+                TOO_MANY_ARGUMENTS,
+                &call.span,
+                format!(
+                    "`{name}` takes at most {max_params} argument{} but {positional} were given",
+                    if max_params == 1 { "" } else { "s" }
+                ),
+            ));
+        }
+        for arg in args {
+            let (Some(arg_name), Some(key)) = (arg.name(), &arg.key) else {
+                continue;
+            };
+            let known = definitions
+                .iter()
+                .flat_map(|definition| &definition.params)
+                .any(|param| param.name == arg_name);
+            if !known {
+                self.diagnostics.push(Diagnostic::error(
+                    UNKNOWN_ARGUMENT,
+                    &key.span,
+                    format!("`{name}` has no parameter named `{arg_name}`"),
+                ));
+            }
+        }
+    }
+}
+
+/// Procs that read `args` can take any arguments.
+fn uses_args(proc: &ProcDeclaration) -> bool {
+    struct ArgsFinder {
+        found: bool,
+    }
+    impl Visitor for ArgsFinder {
+        fn visit_expression(&mut self, expression: &Expression) {
+            if expression.kind == ExpressionKind::Ident(Symbol::intern("args")) {
+                self.found = true;
+            }
+            visit::walk_expression(self, expression);
+        }
+    }
+    let mut finder = ArgsFinder { found: false };
+    visit::walk_proc(&mut finder, proc);
+    finder.found
+}
@@ -0,0 +1,133 @@
+use crate::dm_parser::{
+    ast::statement::{Statement, StatementKind},
+    diagnostics::Severity,
+};
+
+use super::{Lint, LintContext, LintInfo, Reporter};
+
+pub struct UnreachableCode;
+
+impl Lint for UnreachableCode {
+    fn info(&self) -> &'static LintInfo {
+        &LintInfo {
+            id: "unreachable_code",
+            description: "Statements after a `return`, `break`, `continue`, `goto` or `throw`.",
+            default_severity: Severity::Warning,
+            config_key: "LIES_LINT_UNREACHABLE_CODE",
+        }
+    }
+
+    fn check(&self, context: &LintContext<'_>, reporter: &mut Reporter<'_>) {
+        for proc in context.procs() {
+            for block in nested_blocks(&proc.body) {
+                check_block(block, reporter);
+            }
+        }
+    }
+}
+
+/// Reports the first statement after one that leaves the block. Labels can be jumped to, so
+/// they end the unreachable code.
+fn check_block(block: &[Statement], reporter: &mut Reporter<'_>) {
+    let Some(exit) = block
+        .iter()
+        .position(|statement| exit_keyword(statement).is_some())
+    else {
+        return;
+    };
+    let Some(unreachable) = block.get(exit + 1) else {
+        return;
+    };
+    if matches!(unreachable.kind, StatementKind::Label { .. }) {
+        return check_block(&block[exit + 1..], reporter);
+    }
+    let keyword = exit_keyword(&block[exit]).unwrap();
+    reporter
+        .report(
+            &unreachable.span,
+            format!("unreachable code after `{keyword}`"),
+        )
+        .notes
+        .push((
+            block[exit].span.clone(),
+            "leaves the block here".to_string(),
+        ));
+}
+
+fn exit_keyword(statement: &Statement) -> Option<&'static str> {
+    match statement.kind {
+        StatementKind::Return(_) => Some("return"),
+        StatementKind::Break(_) => Some("break"),
+        StatementKind::Continue(_) => Some("continue"),
+        StatementKind::Goto(_) => Some("goto"),
+        StatementKind::Throw(_) => Some("throw"),
+        _ => None,
+    }
+}
+
+pub struct EmptyIf;
+
+impl Lint for EmptyIf {
+    fn info(&self) -> &'static LintInfo {
+        &LintInfo {
+            id: "empty_if",
+            description: "An `if` that does nothing when its condition is true.",
+            default_severity: Severity::Warning,
+            config_key: "LIES_LINT_EMPTY_IF",
+        }
+    }
+
+    fn check(&self, context: &LintContext<'_>, reporter: &mut Reporter<'_>) {
+        for proc in context.procs() {
+            for statement in nested_blocks(&proc.body).into_iter().flatten() {
+                if let StatementKind::If { arms, .. } = &statement.kind {
+                    for (condition, body) in arms {
+                        if body.is_empty() {
+                            reporter.report(&condition.span, "empty `if` body");
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// `block` followed by every block nested in it.
+fn nested_blocks(block: &[Statement]) -> Vec<&[Statement]> {
+    let mut blocks = vec![block];
+    let mut next = 0;
+    while let Some(&block) = blocks.get(next) {
+        next += 1;
+        for statement in block {
+            blocks.extend(child_blocks(statement));
+        }
+    }
+    blocks
+}
+
+fn child_blocks(statement: &Statement) -> Vec<&[Statement]> {
+    match &statement.kind {
+        StatementKind::Block(body)
+        | StatementKind::Label { body, .. }
+        | StatementKind::While { body, .. }
+        | StatementKind::DoWhile { body, .. }
+        | StatementKind::For { body, .. }
+        | StatementKind::ForIn { body, .. }
+        | StatementKind::ForRange { body, .. }
+        | StatementKind::Spawn { body, .. } => vec![body],
+        StatementKind::If { arms, else_arm } => arms
+            .iter()
+            .map(|(_, body)| body.as_slice())
+            .chain(else_arm.as_deref())
+            .collect(),
+        StatementKind::Switch { cases, default, .. } => cases
+            .iter()
+            .map(|case| case.body.as_slice())
+            .chain(default.as_deref())
+            .collect(),
+        StatementKind::Try { body, catch } => std::iter::once(body.as_slice())
+            .chain(catch.as_ref().map(|catch| catch.body.as_slice()))
+            .collect(),
+        _ => vec![],
+    }
+}
@@ -0,0 +1,39 @@
+use crate::dm_parser::diagnostics::Severity;
+
+use super::{Lint, LintContext, LintInfo, Reporter};
+
+pub struct DuplicateVar;
+
+impl Lint for DuplicateVar {
+    fn info(&self) -> &'static LintInfo {
+        &LintInfo {
+            id: "duplicate_var",
+            description: "A var declared more than once on the same type.",
+            default_severity: Severity::Error,
+            config_key: "LIES_LINT_DUPLICATE_VAR",
+        }
+    }
+
+    fn check(&self, context: &LintContext<'_>, reporter: &mut Reporter<'_>) {
+        for node in context.tree.iter() {
+            for var in node.vars() {
+                let mut declarations = var
+                    .declarations()
+                    .iter()
+                    .filter(|declaration| declaration.is_declaration);
+                let Some(first) = declarations.next() else {
+                    continue;
+                };
+                for duplicate in declarations {
+                    reporter
+                        .report(
+                            &duplicate.span,
+                            format!("`{}` already declares var `{}`", node.path(), var.name()),
+                        )
+                        .notes
+                        .push((first.span.clone(), "previously declared here".to_string()));
+                }
+            }
+        }
+    }
+}
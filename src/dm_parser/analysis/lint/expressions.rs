@@ -0,0 +1,130 @@
+use crate::{
+    dm_parser::{
+        ast::{
+            expression::{AssignOp, Expression, ExpressionKind},
+            statement::{Statement, StatementKind},
+            visit::{self, Visitor},
+        },
+        diagnostics::Severity,
+    },
+    tokens::span::Span,
+};
+
+use super::{Lint, LintContext, LintInfo, Reporter};
+
+pub struct AssignmentInCondition;
+
+impl Lint for AssignmentInCondition {
+    fn info(&self) -> &'static LintInfo {
+        &LintInfo {
+            id: "assignment_in_condition",
+            description: "An `=` where a condition is expected, usually meant to be `==`.",
+            default_severity: Severity::Warning,
+            config_key: "LIES_LINT_ASSIGNMENT_IN_CONDITION",
+        }
+    }
+
+    fn check(&self, context: &LintContext<'_>, reporter: &mut Reporter<'_>) {
+        let mut finder = ConditionFinder {
+            assignments: vec![],
+        };
+        for proc in context.procs() {
+            visit::walk_proc(&mut finder, proc);
+        }
+        for span in finder.assignments {
+            reporter.report(&span, "assignment in condition, did you mean `==`?");
+        }
+    }
+}
+
+struct ConditionFinder {
+    assignments: Vec<Span>,
+}
+
+impl ConditionFinder {
+    fn condition(&mut self, condition: &Expression) {
+        if let ExpressionKind::Assign {
+            op: AssignOp::Assign,
+            ..
+        } = condition.kind
+        {
+            self.assignments.push(condition.span.clone());
+        }
+    }
+}
+
+impl Visitor for ConditionFinder {
+    fn visit_statement(&mut self, statement: &Statement) {
+        match &statement.kind {
+            StatementKind::If { arms, .. } => {
+                for (condition, _) in arms {
+                    self.condition(condition);
+                }
+            }
+            StatementKind::While { condition, .. } | StatementKind::DoWhile { condition, .. } => {
+                self.condition(condition)
+            }
+            StatementKind::For {
+                condition: Some(condition),
+                ..
+            } => self.condition(condition),
+            _ => {}
+        }
+        visit::walk_statement(self, statement);
+    }
+}
+
+pub struct SelfAssignment;
+
+impl Lint for SelfAssignment {
+    fn info(&self) -> &'static LintInfo {
+        &LintInfo {
+            id: "self_assignment",
+            description: "An assignment of a var to itself, such as `x = x`.",
+            default_severity: Severity::Warning,
+            config_key: "LIES_LINT_SELF_ASSIGNMENT",
+        }
+    }
+
+    fn check(&self, context: &LintContext<'_>, reporter: &mut Reporter<'_>) {
+        let mut finder = SelfAssignmentFinder {
+            assignments: vec![],
+        };
+        for proc in context.procs() {
+            visit::walk_proc(&mut finder, proc);
+        }
+        for (span, target) in finder.assignments {
+            reporter.report(&span, format!("`{target}` is assigned to itself"));
+        }
+    }
+}
+
+struct SelfAssignmentFinder {
+    assignments: Vec<(Span, String)>,
+}
+
+impl Visitor for SelfAssignmentFinder {
+    fn visit_expression(&mut self, expression: &Expression) {
+        if let ExpressionKind::Assign {
+            op: AssignOp::Assign,
+            target,
+            value,
+        } = &expression.kind
+        {
+            // the same var written the same way, `src.x = x` may be a different var
+            let is_var = matches!(
+                target.kind,
+                ExpressionKind::Ident(_) | ExpressionKind::Field { .. }
+            );
+            if let (true, Some(target), Some(value)) =
+                (is_var, target.span.text(), value.span.text())
+            {
+                if target == value {
+                    self.assignments
+                        .push((expression.span.clone(), target.to_string()));
+                }
+            }
+        }
+        visit::walk_expression(self, expression);
+    }
+}
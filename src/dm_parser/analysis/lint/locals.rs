@@ -0,0 +1,298 @@
+use crate::{
+    dm_parser::{
+        ast::{
+            declaration::ProcDeclaration,
+            expression::{Argument, Expression, ExpressionKind},
+            statement::{Statement, StatementKind},
+            visit::{self, Visitor},
+        },
+        diagnostics::Severity,
+        object_tree::{resolve::ResolvedPath, ObjectTree},
+        parsing::type_path::DmTypePath,
+    },
+    tokens::span::Span,
+    util::symbol::Symbol,
+};
+
+use super::{Lint, LintContext, LintInfo, Reporter};
+
+pub struct UnusedVar;
+
+impl Lint for UnusedVar {
+    fn info(&self) -> &'static LintInfo {
+        &LintInfo {
+            id: "unused_var",
+            description: "A local var that is never used.",
+            default_severity: Severity::Warning,
+            config_key: "LIES_LINT_UNUSED_VAR",
+        }
+    }
+
+    fn check(&self, context: &LintContext<'_>, reporter: &mut Reporter<'_>) {
+        for proc in context.procs() {
+            let walker = LocalsWalker::walk(context.tree, proc);
+            for local in &walker.locals {
+                if local.kind == LocalKind::Var && !local.used {
+                    reporter.report(&local.span, format!("unused local var `{}`", local.name));
+                }
+            }
+        }
+    }
+}
+
+pub struct ShadowedLocal;
+
+impl Lint for ShadowedLocal {
+    fn info(&self) -> &'static LintInfo {
+        &LintInfo {
+            id: "shadowed_local",
+            description: "A local var with the name of a local var or param of an outer block.",
+            default_severity: Severity::Warning,
+            config_key: "LIES_LINT_SHADOWED_LOCAL",
+        }
+    }
+
+    fn check(&self, context: &LintContext<'_>, reporter: &mut Reporter<'_>) {
+        for proc in context.procs() {
+            let walker = LocalsWalker::walk(context.tree, proc);
+            for &(local, outer) in &walker.shadows {
+                let local = &walker.locals[local];
+                let outer = &walker.locals[outer];
+                let what = match outer.kind {
+                    LocalKind::Param => "param",
+                    LocalKind::Var | LocalKind::Bound => "local var",
+                };
+                reporter
+                    .report(&local.span, format!("`{}` shadows a {what}", local.name))
+                    .notes
+                    .push((outer.span.clone(), "declared here".to_string()));
+            }
+        }
+    }
+}
+
+pub struct AlwaysTrueIstype;
+
+impl Lint for AlwaysTrueIstype {
+    fn info(&self) -> &'static LintInfo {
+        &LintInfo {
+            id: "always_true_istype",
+            description: "An `istype()` on a var that is declared as that type already.",
+            default_severity: Severity::Warning,
+            config_key: "LIES_LINT_ALWAYS_TRUE_ISTYPE",
+        }
+    }
+
+    fn check(&self, context: &LintContext<'_>, reporter: &mut Reporter<'_>) {
+        for proc in context.procs() {
+            let walker = LocalsWalker::walk(context.tree, proc);
+            for (span, local, checked) in &walker.type_checks {
+                let local = &walker.locals[*local];
+                let Some(var_type) = &local.var_type else {
+                    continue;
+                };
+                if context.tree.contains(var_type) && context.tree.is_subtype_of(var_type, checked)
+                {
+                    reporter.report(
+                        span,
+                        format!(
+                            "`{}` is declared as `{var_type}`, this only checks that it is not null",
+                            local.name
+                        ),
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LocalKind {
+    Param,
+    Var,
+    /// Declared by a `for` or a `catch`, which need a name even when it is not used.
+    Bound,
+}
+
+struct LocalInfo {
+    name: Symbol,
+    kind: LocalKind,
+    var_type: Option<DmTypePath>,
+    span: Span,
+    used: bool,
+}
+
+/// Follows the params and local vars of a proc through its blocks.
+struct LocalsWalker<'a> {
+    tree: &'a ObjectTree,
+    owner: &'a DmTypePath,
+    locals: Vec<LocalInfo>,
+    /// The locals of every block, the innermost last, as indices into `locals`.
+    scopes: Vec<Vec<usize>>,
+    /// The locals declared with the name of a local of an outer block, and that local.
+    shadows: Vec<(usize, usize)>,
+    /// `istype(x, /type)` on a local, with the span of the call and the checked type.
+    type_checks: Vec<(Span, usize, DmTypePath)>,
+}
+
+impl<'a> LocalsWalker<'a> {
+    fn walk(tree: &'a ObjectTree, proc: &'a ProcDeclaration) -> Self {
+        let mut walker = Self {
+            tree,
+            owner: &proc.owner,
+            locals: vec![],
+            scopes: vec![vec![]],
+            shadows: vec![],
+            type_checks: vec![],
+        };
+        for param in &proc.params {
+            walker.declare(param.name, LocalKind::Param, &param.var_type, &param.span);
+        }
+        walker.scopes.push(vec![]);
+        visit::walk_proc(&mut walker, proc);
+        walker
+    }
+
+    fn lookup(&self, name: Symbol) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .flatten()
+            .copied()
+            .find(|&index| self.locals[index].name == name)
+    }
+
+    fn declare(
+        &mut self,
+        name: Symbol,
+        kind: LocalKind,
+        var_type: &Option<DmTypePath>,
+        span: &Span,
+    ) {
+        let outer_scopes = &self.scopes[..self.scopes.len() - 1];
+        let outer = outer_scopes
+            .iter()
+            .rev()
+            .flatten()
+            .copied()
+            .find(|&index| self.locals[index].name == name);
+        let index = self.locals.len();
+        self.locals.push(LocalInfo {
+            name,
+            kind,
+            var_type: var_type.clone(),
+            span: span.clone(),
+            used: false,
+        });
+        self.scopes.last_mut().unwrap().push(index);
+        if let Some(outer) = outer {
+            self.shadows.push((index, outer));
+        }
+    }
+
+    fn walk_scoped(&mut self, statements: &[Statement]) {
+        self.scopes.push(vec![]);
+        visit::walk_block(self, statements);
+        self.scopes.pop();
+    }
+
+    /// Records `istype(x, /type)` where `x` is a local.
+    fn type_check(&mut self, call: &Expression, args: &[Argument]) {
+        let [Argument {
+            key: None, value, ..
+        }, Argument {
+            key: None,
+            value: checked,
+            ..
+        }] = args
+        else {
+            return;
+        };
+        let (ExpressionKind::Ident(name), ExpressionKind::Path { path, .. }) =
+            (&value.kind, &checked.kind)
+        else {
+            return;
+        };
+        let Some(local) = self.lookup(*name) else {
+            return;
+        };
+        if let Ok(ResolvedPath::Type(checked)) = self.tree.resolve_path(path, self.owner) {
+            self.type_checks.push((call.span.clone(), local, checked));
+        }
+    }
+}
+
+impl Visitor for LocalsWalker<'_> {
+    fn visit_statement(&mut self, statement: &Statement) {
+        match &statement.kind {
+            StatementKind::Var(vars) => {
+                // each var is in scope for the values of the ones after it
+                for var in vars {
+                    visit::walk_local_var(self, var);
+                    self.declare(var.name, LocalKind::Var, &var.var_type, &var.span);
+                }
+            }
+            StatementKind::ForIn { var, .. } | StatementKind::ForRange { var, .. } => {
+                self.scopes.push(vec![]);
+                if var.declared {
+                    self.declare(var.name, LocalKind::Bound, &var.var_type, &var.span);
+                } else if let Some(index) = self.lookup(var.name) {
+                    self.locals[index].used = true;
+                }
+                visit::walk_statement(self, statement);
+                self.scopes.pop();
+            }
+            StatementKind::If { arms, else_arm } => {
+                for (condition, body) in arms {
+                    self.visit_expression(condition);
+                    self.walk_scoped(body);
+                }
+                if let Some(else_arm) = else_arm {
+                    self.walk_scoped(else_arm);
+                }
+            }
+            StatementKind::Try { body, catch } => {
+                self.walk_scoped(body);
+                if let Some(catch) = catch {
+                    self.scopes.push(vec![]);
+                    if let Some(var) = &catch.var {
+                        self.declare(var.name, LocalKind::Bound, &var.var_type, &var.span);
+                    }
+                    visit::walk_block(self, &catch.body);
+                    self.scopes.pop();
+                }
+            }
+            StatementKind::Expression(_)
+            | StatementKind::Return(_)
+            | StatementKind::Break(_)
+            | StatementKind::Continue(_)
+            | StatementKind::Goto(_)
+            | StatementKind::Del(_)
+            | StatementKind::Throw(_)
+            | StatementKind::Set { .. } => visit::walk_statement(self, statement),
+            // everything else has a block of its own
+            _ => {
+                self.scopes.push(vec![]);
+                visit::walk_statement(self, statement);
+                self.scopes.pop();
+            }
+        }
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        match &expression.kind {
+            ExpressionKind::Ident(name) => {
+                if let Some(index) = self.lookup(*name) {
+                    self.locals[index].used = true;
+                }
+            }
+            ExpressionKind::Call { callee, args } => {
+                if matches!(callee.kind, ExpressionKind::Ident(name) if name == "istype") {
+                    self.type_check(expression, args);
+                }
+            }
+            _ => {}
+        }
+        visit::walk_expression(self, expression);
+    }
+}
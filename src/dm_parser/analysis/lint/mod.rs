@@ -0,0 +1,162 @@
+use std::env;
+
+use crate::{
+    dm_parser::{
+        ast::{
+            declaration::{Declaration, ProcDeclaration},
+            DmAst,
+        },
+        diagnostics::{Diagnostic, Severity},
+        object_tree::ObjectTree,
+    },
+    tokens::span::Span,
+};
+
+pub mod control_flow;
+pub mod declarations;
+pub mod expressions;
+pub mod locals;
+
+/// What identifies a lint and how it reports by default.
+#[derive(Debug)]
+pub struct LintInfo {
+    /// The code of the diagnostics the lint reports, such as `unused_var`.
+    pub id: &'static str,
+    pub description: &'static str,
+    pub default_severity: Severity,
+    /// The environment variable that changes the severity of the lint, or turns it `off`.
+    pub config_key: &'static str,
+}
+
+/// A check for code that is valid but likely a mistake.
+pub trait Lint {
+    fn info(&self) -> &'static LintInfo;
+
+    fn check(&self, context: &LintContext<'_>, reporter: &mut Reporter<'_>);
+}
+
+/// What a lint can look at.
+pub struct LintContext<'a> {
+    pub tree: &'a ObjectTree,
+    pub asts: &'a [DmAst],
+}
+
+impl LintContext<'_> {
+    /// Every proc definition in the parsed files.
+    pub fn procs(&self) -> impl Iterator<Item = &ProcDeclaration> {
+        self.asts
+            .iter()
+            .flat_map(DmAst::iter_declarations)
+            .filter_map(|declaration| match declaration {
+                Declaration::Proc(proc) => Some(proc.as_ref()),
+                _ => None,
+            })
+    }
+}
+
+/// Collects the findings of a lint as diagnostics with its id and configured severity.
+pub struct Reporter<'a> {
+    info: &'static LintInfo,
+    severity: Severity,
+    diagnostics: &'a mut Vec<Diagnostic>,
+}
+
+impl Reporter<'_> {
+    /// Reports a finding, notes can be added to the returned diagnostic.
+    pub fn report(&mut self, span: &Span, message: impl Into<String>) -> &mut Diagnostic {
+        let diagnostic = Diagnostic::new(self.severity, self.info.id, span, message);
+        self.diagnostics.push(diagnostic);
+        self.diagnostics.last_mut().unwrap()
+    }
+}
+
+/// The registered lints with the severity each reports at, `None` for lints that are off.
+pub struct Lints {
+    lints: Vec<(Box<dyn Lint>, Option<Severity>)>,
+}
+
+impl Default for Lints {
+    fn default() -> Self {
+        let mut lints = Self { lints: vec![] };
+        lints.register(Box::new(locals::UnusedVar));
+        lints.register(Box::new(locals::ShadowedLocal));
+        lints.register(Box::new(locals::AlwaysTrueIstype));
+        lints.register(Box::new(control_flow::UnreachableCode));
+        lints.register(Box::new(control_flow::EmptyIf));
+        lints.register(Box::new(expressions::AssignmentInCondition));
+        lints.register(Box::new(expressions::SelfAssignment));
+        lints.register(Box::new(declarations::DuplicateVar));
+        lints
+    }
+}
+
+impl Lints {
+    /// The builtin lints, configured from the environment. `LIES_LINT_UNUSED_VAR=error` makes
+    /// `unused_var` an error, `off` turns it off.
+    pub fn from_env() -> Self {
+        let mut lints = Self::default();
+        for (lint, severity) in &mut lints.lints {
+            let info = lint.info();
+            if let Ok(value) = env::var(info.config_key) {
+                *severity = parse_level(&value)
+                    .unwrap_or_else(|error| panic!("failed to parse {}: {error}", info.config_key));
+            }
+        }
+        lints
+    }
+
+    /// Adds a lint at its default severity.
+    pub fn register(&mut self, lint: Box<dyn Lint>) {
+        let severity = lint.info().default_severity;
+        self.lints.push((lint, Some(severity)));
+    }
+
+    /// Changes the severity of the lint `id`, `None` turns it off. False if there is no such lint.
+    pub fn configure(&mut self, id: &str, severity: Option<Severity>) -> bool {
+        match self.lints.iter_mut().find(|(lint, _)| lint.info().id == id) {
+            Some((_, configured)) => {
+                *configured = severity;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The ids of the lints that are off.
+    pub fn disabled(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.lints
+            .iter()
+            .filter(|(_, severity)| severity.is_none())
+            .map(|(lint, _)| lint.info().id)
+    }
+
+    pub fn infos(&self) -> impl Iterator<Item = &'static LintInfo> + '_ {
+        self.lints.iter().map(|(lint, _)| lint.info())
+    }
+
+    /// Runs every lint that is on.
+    pub fn run(&self, tree: &ObjectTree, asts: &[DmAst]) -> Vec<Diagnostic> {
+        let context = LintContext { tree, asts };
+        let mut diagnostics = vec![];
+        for (lint, severity) in &self.lints {
+            let Some(severity) = severity else {
+                continue;
+            };
+            let mut reporter = Reporter {
+                info: lint.info(),
+                severity: *severity,
+                diagnostics: &mut diagnostics,
+            };
+            lint.check(&context, &mut reporter);
+        }
+        diagnostics
+    }
+}
+
+/// A severity or `off`.
+fn parse_level(value: &str) -> Result<Option<Severity>, String> {
+    match value.to_lowercase().as_str() {
+        "off" | "none" => Ok(None),
+        _ => value.parse().map(Some),
+    }
+}
@@ -0,0 +1,6 @@
+pub mod annotations;
+pub mod call_graph;
+pub mod checker;
+pub mod lint;
+pub mod sleep;
+pub mod types;
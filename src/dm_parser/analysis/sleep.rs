@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+use crate::dm_parser::{
+    object_tree::{procs::ProcRef, ObjectTree},
+    parsing::type_path::DmTypePath,
+};
+
+use super::{
+    annotations::setting,
+    call_graph::{CallGraph, Cause, Propagation},
+};
+
+/// Builtin procs that sleep, with the type that declares them. `stoplag()` and `UNTIL()` sleep
+/// through the `sleep()` in their definitions.
+const SLEEPING_PROCS: &[(&str, &str)] = &[
+    ("/", "sleep"),
+    ("/", "alert"),
+    ("/", "shell"),
+    ("/", "winexists"),
+    ("/", "winget"),
+    ("/world", "Export"),
+    ("/client", "SoundQuery"),
+];
+
+/// The procs that can sleep, directly or through the procs they call. Code in `spawn()` does
+/// not make its proc sleep, and neither do calls to procs that `set waitfor = FALSE`.
+#[derive(Debug, Default)]
+pub struct SleepAnalysis {
+    sleeping: Propagation,
+}
+
+impl SleepAnalysis {
+    pub fn can_sleep(&self, proc: &ProcRef) -> bool {
+        self.sleeping.contains(proc)
+    }
+
+    pub fn cause(&self, proc: &ProcRef) -> Option<&Cause> {
+        self.sleeping.cause(proc)
+    }
+
+    /// The calls from `proc` down to the builtin that sleeps, each with the proc it is in.
+    /// Empty if `proc` cannot sleep.
+    pub fn sleep_chain(&self, proc: &ProcRef) -> Vec<(ProcRef, &Cause)> {
+        self.sleeping.chain(proc)
+    }
+}
+
+/// Finds every proc in the tree that can sleep. Each proc keeps the shortest call chain that
+/// makes it sleep.
+pub fn analyze(tree: &ObjectTree, graph: &CallGraph) -> SleepAnalysis {
+    let mut direct = HashMap::new();
+    for proc in tree.proc_refs() {
+        let Some(facts) = graph.facts(&proc) else {
+            continue;
+        };
+        let sleeping_calls = facts
+            .calls
+            .iter()
+            .filter(|call| !call.spawned && is_sleeping_builtin(tree, &call.callee))
+            .map(|call| {
+                (
+                    call.span.clone(),
+                    format!("`{}()` sleeps", call.callee.name),
+                )
+            });
+        let inputs = facts
+            .inputs
+            .iter()
+            .filter(|(_, spawned)| !spawned)
+            .map(|(span, _)| (span.clone(), "`input()` sleeps".to_string()));
+        let first = sleeping_calls
+            .chain(inputs)
+            .min_by_key(|(span, _)| span.start());
+        if let Some((span, description)) = first {
+            direct.insert(proc, Cause::Direct { span, description });
+        }
+    }
+
+    let sleeping = graph.propagate(direct, |call| {
+        !call.spawned && waits_for(tree, &call.callee)
+    });
+    SleepAnalysis { sleeping }
+}
+
+fn is_sleeping_builtin(tree: &ObjectTree, proc: &ProcRef) -> bool {
+    let is_builtin = tree
+        .get(&proc.owner)
+        .and_then(|node| node.proc(proc.name))
+        .is_some_and(|builtin| builtin.builtin().is_some());
+    is_builtin
+        && SLEEPING_PROCS
+            .iter()
+            .any(|(owner, name)| proc.name == *name && proc.owner == DmTypePath::from(*owner))
+}
+
+/// False for procs that `set waitfor = FALSE`, their callers continue when they sleep.
+fn waits_for(tree: &ObjectTree, proc: &ProcRef) -> bool {
+    tree.proc_definition(proc)
+        .and_then(|definition| setting(tree, definition, "waitfor"))
+        .unwrap_or(true)
+}
@@ -0,0 +1,269 @@
+use crate::{
+    dm_parser::{
+        ast::{
+            expression::{Argument, BinaryOp, Expression, ExpressionKind, FieldAccess, UnaryOp},
+            statement::{Block, StatementKind},
+            visit::{self, Visitor},
+        },
+        diagnostics::{Diagnostic, Severity},
+        object_tree::resolve::ResolvedPath,
+        parsing::type_path::DmTypePath,
+    },
+    util::symbol::Symbol,
+};
+
+use super::checker::{Checker, Local, Variable, UNDEFINED_VAR};
+
+pub const COLON_ACCESS: &str = "colon_access";
+
+/// Builtin procs that check for a type like `istype(x, /type)` does.
+const TYPE_CHECKS: &[(&str, &str)] = &[
+    ("isarea", "/area"),
+    ("isicon", "/icon"),
+    ("islist", "/list"),
+    ("ismob", "/mob"),
+    ("ismovable", "/atom/movable"),
+    ("isobj", "/obj"),
+    ("isturf", "/turf"),
+];
+
+impl Checker<'_> {
+    /// The type of the object an expression evaluates to, as far as declarations, proc return
+    /// types and `istype()` checks around it tell.
+    pub(super) fn expression_type(&self, expression: &Expression) -> Option<DmTypePath> {
+        match &expression.kind {
+            ExpressionKind::Ident(name) => match (name.as_str(), self.lookup(*name)?) {
+                (_, Variable::Local(local)) => local.var_type.clone(),
+                (_, Variable::Declared(declaration)) => declaration.var_type.clone(),
+                ("src", _) if !self.context.is_root() => Some(self.context.clone()),
+                ("usr", _) => Some(DmTypePath::from("/mob")),
+                ("world", _) => Some(DmTypePath::from("/world")),
+                ("global", _) => Some(DmTypePath::default()),
+                _ => None,
+            },
+            ExpressionKind::Field {
+                object,
+                access: FieldAccess::Dot | FieldAccess::SafeDot,
+                name,
+            } => {
+                let object_type = self.expression_type(object)?;
+                let (_, declaration) = self.tree.find_var(&object_type, *name)?;
+                declaration.var_type.clone()
+            }
+            ExpressionKind::Call { callee, .. } => {
+                let (receiver, name) = self.call_receiver(callee)?;
+                self.return_type(&receiver?, name)
+            }
+            ExpressionKind::New {
+                new_type: Some(new_type),
+                ..
+            } => self.type_literal(new_type),
+            // `locate(/obj/item)` finds an instance of the type
+            ExpressionKind::Locate { args, .. } => match args.as_slice() {
+                [Argument { key: None, value }] => self.type_literal(value),
+                _ => None,
+            },
+            ExpressionKind::Ternary {
+                if_true, if_false, ..
+            } => {
+                let if_true = self.expression_type(if_true)?;
+                let if_false = self.expression_type(if_false)?;
+                (if_true == if_false).then_some(if_true)
+            }
+            _ => None,
+        }
+    }
+
+    /// The type a path literal such as `/obj/item` names.
+    fn type_literal(&self, expression: &Expression) -> Option<DmTypePath> {
+        let ExpressionKind::Path { path, .. } = &expression.kind else {
+            return None;
+        };
+        match self.tree.resolve_path(path, &self.context).ok()? {
+            ResolvedPath::Type(path) => Some(path),
+            ResolvedPath::Proc { .. } => None,
+        }
+    }
+
+    /// The type the proc is called on and its name for `foo()` and `a.foo()`. `None` for other
+    /// calls, the type is `None` when the receiver has no known type.
+    pub(super) fn call_receiver(
+        &self,
+        callee: &Expression,
+    ) -> Option<(Option<DmTypePath>, Symbol)> {
+        match &callee.kind {
+            // `foo()` is a proc of `src` or a global proc
+            ExpressionKind::Ident(name) => {
+                let receiver = if self.tree.find_proc(&self.context, *name).is_some() {
+                    self.context.clone()
+                } else {
+                    DmTypePath::default()
+                };
+                Some((Some(receiver), *name))
+            }
+            ExpressionKind::Field {
+                object,
+                access: FieldAccess::Dot | FieldAccess::SafeDot,
+                name,
+            } => {
+                let receiver = self
+                    .expression_type(object)
+                    .filter(|receiver| self.tree.contains(receiver));
+                Some((receiver, *name))
+            }
+            _ => None,
+        }
+    }
+
+    /// The `as /type` of the proc a call runs, or of the closest definition it overrides.
+    fn return_type(&self, receiver: &DmTypePath, name: Symbol) -> Option<DmTypePath> {
+        let implementation = self.tree.implementation(receiver, name)?;
+        self.tree
+            .override_chain(&implementation)
+            .filter_map(|proc| self.tree.proc_definition(&proc))
+            .find_map(|definition| definition.return_type.clone())
+    }
+
+    /// Checks an `if`, the vars its conditions check with `istype()` have that type in the
+    /// branch. `if(!istype(x, /type)) return` narrows `x` for the rest of the block.
+    pub(super) fn visit_if(&mut self, arms: &[(Expression, Block)], else_arm: Option<&Block>) {
+        for (condition, body) in arms {
+            self.visit_expression(condition);
+            self.scopes.push(Default::default());
+            for (name, narrowed) in self.narrowings(condition) {
+                self.narrow(name, narrowed);
+            }
+            visit::walk_block(self, body);
+            self.scopes.pop();
+        }
+        if let Some(else_arm) = else_arm {
+            self.scopes.push(Default::default());
+            visit::walk_block(self, else_arm);
+            self.scopes.pop();
+        }
+
+        let [(condition, body)] = arms else {
+            return;
+        };
+        let ExpressionKind::Unary {
+            op: UnaryOp::Not,
+            operand,
+        } = &condition.kind
+        else {
+            return;
+        };
+        let exits = body.last().is_some_and(|statement| {
+            matches!(
+                statement.kind,
+                StatementKind::Return(_)
+                    | StatementKind::Break(_)
+                    | StatementKind::Continue(_)
+                    | StatementKind::Goto(_)
+                    | StatementKind::Throw(_)
+            )
+        });
+        if else_arm.is_none() && exits {
+            for (name, narrowed) in self.narrowings(operand) {
+                self.narrow(name, narrowed);
+            }
+        }
+    }
+
+    /// The vars `condition` proves to have a type when it is true.
+    fn narrowings(&self, condition: &Expression) -> Vec<(Symbol, DmTypePath)> {
+        match &condition.kind {
+            ExpressionKind::Binary {
+                op: BinaryOp::And,
+                lhs,
+                rhs,
+            } => {
+                let mut narrowings = self.narrowings(lhs);
+                narrowings.extend(self.narrowings(rhs));
+                narrowings
+            }
+            ExpressionKind::Call { callee, args } => {
+                let ExpressionKind::Ident(check) = callee.kind else {
+                    return vec![];
+                };
+                let checked_type = match (check.as_str(), args.as_slice()) {
+                    ("istype", [_, checked_type]) => self.type_literal(&checked_type.value),
+                    (check, [_]) => TYPE_CHECKS
+                        .iter()
+                        .find(|(name, _)| *name == check)
+                        .map(|(_, path)| DmTypePath::from(*path)),
+                    _ => None,
+                };
+                match (args.first().map(|arg| &arg.value.kind), checked_type) {
+                    (Some(ExpressionKind::Ident(name)), Some(checked_type)) => {
+                        vec![(*name, checked_type)]
+                    }
+                    _ => vec![],
+                }
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Gives the var `name` the type `narrowed` in the current block, unless it already has that
+    /// type or a more specific one.
+    fn narrow(&mut self, name: Symbol, narrowed: DmTypePath) {
+        let local = match self.lookup(name) {
+            Some(Variable::Local(local)) => local.clone(),
+            Some(Variable::Declared(declaration)) => Local {
+                var_type: declaration.var_type.clone(),
+                is_const: declaration.modifiers.is_const,
+                span: declaration.span.clone(),
+            },
+            Some(Variable::Implicit) | None => return,
+        };
+        if let Some(current) = &local.var_type {
+            if self.tree.is_subtype_of(current, &narrowed) {
+                return;
+            }
+        }
+        self.declare(
+            name,
+            Local {
+                var_type: Some(narrowed),
+                ..local
+            },
+        );
+    }
+
+    /// `a.b` must name a var of the type of `a` if it is known. `a:b` skips that check, so the
+    /// type is reported when it is known anyway.
+    pub(super) fn check_member_access(
+        &mut self,
+        expression: &Expression,
+        object: &Expression,
+        access: FieldAccess,
+        name: Symbol,
+    ) {
+        let Some(object_type) = self
+            .expression_type(object)
+            .filter(|object_type| self.tree.contains(object_type))
+        else {
+            return;
+        };
+        let exists = self.tree.find_var(&object_type, name).is_some();
+        let object_text = object.span.text().unwrap_or("the object");
+        let diagnostic = match access {
+            FieldAccess::Dot | FieldAccess::SafeDot if !exists => Diagnostic::error(
+                UNDEFINED_VAR,
+                &expression.span,
+                format!("`{object_type}` has no var `{name}`"),
+            ),
+            FieldAccess::Dot | FieldAccess::SafeDot => return,
+            FieldAccess::Colon => {
+                let has_member = exists || self.tree.find_proc(&object_type, name).is_some();
+                let message = if has_member {
+                    format!("`{object_text}` is a `{object_type}` here, `.{name}` would be checked")
+                } else {
+                    format!("`{object_text}` is a `{object_type}` here, which has no `{name}`")
+                };
+                Diagnostic::new(Severity::Hint, COLON_ACCESS, &expression.span, message)
+            }
+        };
+        self.diagnostics.push(diagnostic);
+    }
+}
@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use crate::{dm_parser::parsing::type_path::DmTypePath, tokens::span::Span, util::symbol::Symbol};
+
+use super::{expression::Expression, statement::Block};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Declaration {
+    /// `/obj/item` and everything in its block.
+    Type(TypeDeclaration),
+    /// `var/x` as well as `x = 1` overriding an inherited var. Shared with the object tree.
+    Var(Arc<VarDeclaration>),
+    /// `parent_type = /obj`
+    ParentType(ParentTypeDeclaration),
+    /// Shared with the object tree.
+    Proc(Arc<ProcDeclaration>),
+}
+
+impl Declaration {
+    pub fn span(&self) -> &Span {
+        match self {
+            Declaration::Type(declaration) => &declaration.span,
+            Declaration::Var(declaration) => &declaration.span,
+            Declaration::ParentType(declaration) => &declaration.span,
+            Declaration::Proc(declaration) => &declaration.span,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeDeclaration {
+    pub path: DmTypePath,
+    pub declarations: Vec<Declaration>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VarModifiers {
+    pub global: bool,
+    pub is_static: bool,
+    pub tmp: bool,
+    pub is_const: bool,
+    pub is_final: bool,
+}
+
+impl VarModifiers {
+    /// Sets the modifier named `name`, returns false if `name` is not a modifier.
+    pub fn set(&mut self, name: &str) -> bool {
+        let modifier = match name {
+            "global" => &mut self.global,
+            "static" => &mut self.is_static,
+            "tmp" => &mut self.tmp,
+            "const" => &mut self.is_const,
+            "final" => &mut self.is_final,
+            _ => return false,
+        };
+        *modifier = true;
+        true
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VarDeclaration {
+    /// The type the var is declared on. Global vars are declared on the root `/`.
+    pub owner: DmTypePath,
+    pub name: Symbol,
+    /// False for `x = 1` which changes the value of a var declared elsewhere.
+    pub is_declaration: bool,
+    pub modifiers: VarModifiers,
+    /// `/obj/item` in `var/obj/item/I`
+    pub var_type: Option<DmTypePath>,
+    /// The sizes of `var/L[10][]`, `None` for an unsized dimension.
+    pub dimensions: Vec<Option<Expression>>,
+    pub input_types: Vec<Symbol>,
+    pub value: Option<Expression>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParentTypeDeclaration {
+    pub owner: DmTypePath,
+    pub parent: DmTypePath,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcKind {
+    Proc,
+    Verb,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcDeclaration {
+    pub owner: DmTypePath,
+    pub name: Symbol,
+    /// `proc` or `verb` when this is a new declaration, `None` for an override.
+    pub declared_kind: Option<ProcKind>,
+    pub params: Vec<ProcParam>,
+    /// `...` in the parameter list, the proc takes any number of arguments.
+    pub variadic: bool,
+    /// `as /obj` after the parameter list.
+    pub return_type: Option<DmTypePath>,
+    pub body: Block,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcParam {
+    pub name: Symbol,
+    pub var_type: Option<DmTypePath>,
+    pub default: Option<Expression>,
+    /// `as num|text`
+    pub input_types: Vec<Symbol>,
+    /// `in view()`
+    pub input_list: Option<Expression>,
+    pub span: Span,
+}
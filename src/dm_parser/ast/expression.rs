@@ -0,0 +1,324 @@
+use std::fmt::Display;
+
+use crate::{dm_parser::parsing::type_path::DmTypePath, tokens::span::Span, util::symbol::Symbol};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub span: Span,
+}
+
+impl Expression {
+    pub fn new(kind: ExpressionKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpressionKind {
+    Null,
+    Number(f32),
+    String(Vec<StringSegment>),
+    /// `'icon.dmi'`
+    Resource(String),
+    Ident(Symbol),
+    /// `/obj/item{name = "thing"}`
+    Path {
+        path: PathLiteral,
+        vars: Vec<(Symbol, Expression)>,
+    },
+    /// `.`, the return value of the current proc.
+    Dot,
+    /// `..`, the parent implementation of the current proc.
+    ParentProc,
+    Unary {
+        op: UnaryOp,
+        operand: Box<Expression>,
+    },
+    Binary {
+        op: BinaryOp,
+        lhs: Box<Expression>,
+        rhs: Box<Expression>,
+    },
+    Assign {
+        op: AssignOp,
+        target: Box<Expression>,
+        value: Box<Expression>,
+    },
+    Ternary {
+        condition: Box<Expression>,
+        if_true: Box<Expression>,
+        if_false: Box<Expression>,
+    },
+    /// `a.b`, `a?.b` and `a:b`
+    Field {
+        object: Box<Expression>,
+        access: FieldAccess,
+        name: Symbol,
+    },
+    /// `a::b`
+    StaticField {
+        object: Box<Expression>,
+        name: Symbol,
+    },
+    /// `a[b]` and `a?[b]`
+    Index {
+        object: Box<Expression>,
+        index: Box<Expression>,
+        safe: bool,
+    },
+    Call {
+        callee: Box<Expression>,
+        args: Vec<Argument>,
+    },
+    /// `new /obj(loc)`, `new(loc)` leaves the type to be inferred.
+    New {
+        new_type: Option<Box<Expression>>,
+        args: Vec<Argument>,
+    },
+    /// `list(...)` and `alist(...)`
+    List {
+        associative_only: bool,
+        args: Vec<Argument>,
+    },
+    /// `locate(...)` with an optional `in container`.
+    Locate {
+        args: Vec<Argument>,
+        in_list: Option<Box<Expression>>,
+    },
+    /// `input(...) as text|null in choices`
+    Input {
+        args: Vec<Argument>,
+        input_types: Vec<Symbol>,
+        in_list: Option<Box<Expression>>,
+    },
+    /// `call(target, proc)(args)` and `call_ext(library, function)(args)`
+    DynamicCall {
+        external: bool,
+        target: Vec<Argument>,
+        args: Vec<Argument>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StringSegment {
+    Text(String),
+    Expression(Expression),
+}
+
+/// A call argument, `key = value` for named arguments and list associations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Argument {
+    pub key: Option<Expression>,
+    pub value: Expression,
+}
+
+impl Argument {
+    /// The name of a named argument, `None` for positional ones and non-identifier keys.
+    pub fn name(&self) -> Option<Symbol> {
+        match self.key.as_ref()?.kind {
+            ExpressionKind::Ident(name) => Some(name),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathOp {
+    /// `/`, a child of the previous part. Leading, it starts from the root.
+    Slash,
+    /// `.`, a relative lookup. Leading, it starts from the current type.
+    Dot,
+    /// `:`, a search downward for a matching subtype.
+    Colon,
+}
+
+impl PathOp {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PathOp::Slash => "/",
+            PathOp::Dot => ".",
+            PathOp::Colon => ":",
+        }
+    }
+}
+
+/// A path as written in code, such as `/obj/item`, `.proc/foo` or `/obj:widget`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathLiteral {
+    pub parts: Vec<(PathOp, Symbol)>,
+}
+
+impl PathLiteral {
+    /// The absolute type path, if the path only uses `/`.
+    pub fn as_type_path(&self) -> Option<DmTypePath> {
+        if self.parts.iter().any(|(op, _)| *op != PathOp::Slash) {
+            return None;
+        }
+        Some(DmTypePath::from_parts(
+            self.parts.iter().map(|(_, part)| *part).collect::<Vec<_>>(),
+        ))
+    }
+}
+
+impl Display for PathLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (op, part) in &self.parts {
+            write!(f, "{}{}", op.as_str(), part)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldAccess {
+    Dot,
+    SafeDot,
+    Colon,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Negate,
+    Not,
+    BitNot,
+    PreIncrement,
+    PreDecrement,
+    PostIncrement,
+    PostDecrement,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Pow,
+    Mul,
+    Div,
+    Mod,
+    FloatMod,
+    Add,
+    Sub,
+    LShift,
+    RShift,
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+    Eq,
+    NotEq,
+    Equiv,
+    NotEquiv,
+    BitAnd,
+    BitXor,
+    BitOr,
+    And,
+    Or,
+    In,
+}
+
+impl BinaryOp {
+    pub fn from_punct(punct: &str) -> Option<Self> {
+        Some(match punct {
+            "**" => BinaryOp::Pow,
+            "*" => BinaryOp::Mul,
+            "/" => BinaryOp::Div,
+            "%" => BinaryOp::Mod,
+            "%%" => BinaryOp::FloatMod,
+            "+" => BinaryOp::Add,
+            "-" => BinaryOp::Sub,
+            "<<" => BinaryOp::LShift,
+            ">>" => BinaryOp::RShift,
+            "<" => BinaryOp::Less,
+            "<=" => BinaryOp::LessEq,
+            ">" => BinaryOp::Greater,
+            ">=" => BinaryOp::GreaterEq,
+            "==" => BinaryOp::Eq,
+            "!=" | "<>" => BinaryOp::NotEq,
+            "~=" => BinaryOp::Equiv,
+            "~!" => BinaryOp::NotEquiv,
+            "&" => BinaryOp::BitAnd,
+            "^" => BinaryOp::BitXor,
+            "|" => BinaryOp::BitOr,
+            "&&" => BinaryOp::And,
+            "||" => BinaryOp::Or,
+            _ => return None,
+        })
+    }
+
+    /// How tightly the operator binds, higher binds tighter.
+    pub fn strength(self) -> u8 {
+        match self {
+            BinaryOp::In => 1,
+            BinaryOp::Or => 2,
+            BinaryOp::And => 3,
+            BinaryOp::BitOr => 4,
+            BinaryOp::BitXor => 5,
+            BinaryOp::BitAnd => 6,
+            BinaryOp::Eq | BinaryOp::NotEq | BinaryOp::Equiv | BinaryOp::NotEquiv => 7,
+            BinaryOp::Less | BinaryOp::LessEq | BinaryOp::Greater | BinaryOp::GreaterEq => 8,
+            BinaryOp::LShift | BinaryOp::RShift => 9,
+            BinaryOp::Add | BinaryOp::Sub => 10,
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod | BinaryOp::FloatMod => 11,
+            BinaryOp::Pow => 12,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BinaryOp::Pow => "**",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Mod => "%",
+            BinaryOp::FloatMod => "%%",
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::LShift => "<<",
+            BinaryOp::RShift => ">>",
+            BinaryOp::Less => "<",
+            BinaryOp::LessEq => "<=",
+            BinaryOp::Greater => ">",
+            BinaryOp::GreaterEq => ">=",
+            BinaryOp::Eq => "==",
+            BinaryOp::NotEq => "!=",
+            BinaryOp::Equiv => "~=",
+            BinaryOp::NotEquiv => "~!",
+            BinaryOp::BitAnd => "&",
+            BinaryOp::BitXor => "^",
+            BinaryOp::BitOr => "|",
+            BinaryOp::And => "&&",
+            BinaryOp::Or => "||",
+            BinaryOp::In => "in",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssignOp {
+    Assign,
+    /// `x += y` and friends.
+    Compound(BinaryOp),
+    /// `:=`
+    AssignInto,
+}
+
+impl AssignOp {
+    pub fn from_punct(punct: &str) -> Option<Self> {
+        Some(match punct {
+            "=" => AssignOp::Assign,
+            ":=" => AssignOp::AssignInto,
+            "+=" => AssignOp::Compound(BinaryOp::Add),
+            "-=" => AssignOp::Compound(BinaryOp::Sub),
+            "*=" => AssignOp::Compound(BinaryOp::Mul),
+            "/=" => AssignOp::Compound(BinaryOp::Div),
+            "%=" => AssignOp::Compound(BinaryOp::Mod),
+            "%%=" => AssignOp::Compound(BinaryOp::FloatMod),
+            "&=" => AssignOp::Compound(BinaryOp::BitAnd),
+            "|=" => AssignOp::Compound(BinaryOp::BitOr),
+            "^=" => AssignOp::Compound(BinaryOp::BitXor),
+            "<<=" => AssignOp::Compound(BinaryOp::LShift),
+            ">>=" => AssignOp::Compound(BinaryOp::RShift),
+            "&&=" => AssignOp::Compound(BinaryOp::And),
+            "||=" => AssignOp::Compound(BinaryOp::Or),
+            _ => return None,
+        })
+    }
+}
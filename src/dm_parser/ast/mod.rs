@@ -0,0 +1,29 @@
+use std::path::PathBuf;
+
+pub mod declaration;
+pub mod expression;
+pub mod statement;
+pub mod visit;
+
+use declaration::Declaration;
+
+/// Everything declared in a single file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DmAst {
+    pub path: PathBuf,
+    pub declarations: Vec<Declaration>,
+}
+
+impl DmAst {
+    /// Every declaration in the file, including the ones nested in type blocks.
+    pub fn iter_declarations(&self) -> impl Iterator<Item = &Declaration> {
+        let mut stack: Vec<&Declaration> = self.declarations.iter().rev().collect();
+        std::iter::from_fn(move || {
+            let declaration = stack.pop()?;
+            if let Declaration::Type(type_declaration) = declaration {
+                stack.extend(type_declaration.declarations.iter().rev());
+            }
+            Some(declaration)
+        })
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{dm_parser::parsing::type_path::DmTypePath, tokens::span::Span, util::symbol::Symbol};
+
+use super::{declaration::VarModifiers, expression::Expression};
+
+pub type Block = Vec<Statement>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatementKind {
+    Expression(Expression),
+    /// A `{}` block on its own.
+    Block(Block),
+    /// `var/a = 1, b = 2` or a `var` block, one local for each declaration in it.
+    Var(Vec<LocalVar>),
+    Return(Option<Expression>),
+    Break(Option<Symbol>),
+    Continue(Option<Symbol>),
+    Goto(Symbol),
+    /// A named block that `break`, `continue` and `goto` can refer to.
+    Label {
+        name: Symbol,
+        body: Block,
+    },
+    Del(Expression),
+    If {
+        arms: Vec<(Expression, Block)>,
+        else_arm: Option<Block>,
+    },
+    While {
+        condition: Expression,
+        body: Block,
+    },
+    DoWhile {
+        body: Block,
+        condition: Expression,
+    },
+    /// `for(init, condition, increment)`, also written with `;`. `for()` loops forever.
+    For {
+        init: Option<Box<Statement>>,
+        condition: Option<Expression>,
+        increment: Option<Box<Statement>>,
+        body: Block,
+    },
+    /// `for(var/obj/O in view())`, without a list it loops over the world.
+    ForIn {
+        var: ForVar,
+        list: Option<Expression>,
+        body: Block,
+    },
+    /// `for(x in 1 to 10 step 2)` and `for(var/x = 1 to 10)`
+    ForRange {
+        var: ForVar,
+        start: Expression,
+        end: Expression,
+        step: Option<Expression>,
+        body: Block,
+    },
+    Switch {
+        value: Expression,
+        cases: Vec<SwitchCase>,
+        default: Option<Block>,
+    },
+    Spawn {
+        delay: Option<Expression>,
+        body: Block,
+    },
+    Try {
+        body: Block,
+        catch: Option<Catch>,
+    },
+    Throw(Expression),
+    /// `set name = "Say"` or `set src in view()`
+    Set {
+        name: Symbol,
+        value: Expression,
+        is_in: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalVar {
+    pub name: Symbol,
+    pub modifiers: VarModifiers,
+    pub var_type: Option<DmTypePath>,
+    pub dimensions: Vec<Option<Expression>>,
+    pub value: Option<Expression>,
+    pub span: Span,
+}
+
+/// The loop variable of a `for`, either declared in the loop or an existing var.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForVar {
+    pub name: Symbol,
+    pub var_type: Option<DmTypePath>,
+    pub declared: bool,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwitchCase {
+    pub values: Vec<CaseValue>,
+    pub body: Block,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CaseValue {
+    Single(Expression),
+    /// `if(1 to 5)`
+    Range(Expression, Expression),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Catch {
+    /// `catch(var/exception/e)`
+    pub var: Option<LocalVar>,
+    pub body: Block,
+}
@@ -0,0 +1,273 @@
+use super::{
+    declaration::{Declaration, ProcDeclaration},
+    expression::{Argument, Expression, ExpressionKind, StringSegment},
+    statement::{CaseValue, LocalVar, Statement, StatementKind},
+    DmAst,
+};
+
+/// Walks an AST. Every method visits the children of its node by default, so an implementation
+/// only overrides the nodes it cares about and calls the matching `walk_` function to continue.
+pub trait Visitor {
+    fn visit_declaration(&mut self, declaration: &Declaration) {
+        walk_declaration(self, declaration);
+    }
+
+    fn visit_proc(&mut self, proc: &ProcDeclaration) {
+        walk_proc(self, proc);
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        walk_statement(self, statement);
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        walk_expression(self, expression);
+    }
+}
+
+pub fn walk_ast<V: Visitor + ?Sized>(visitor: &mut V, ast: &DmAst) {
+    for declaration in &ast.declarations {
+        visitor.visit_declaration(declaration);
+    }
+}
+
+pub fn walk_declaration<V: Visitor + ?Sized>(visitor: &mut V, declaration: &Declaration) {
+    match declaration {
+        Declaration::Type(type_declaration) => {
+            for declaration in &type_declaration.declarations {
+                visitor.visit_declaration(declaration);
+            }
+        }
+        Declaration::Var(var) => {
+            for dimension in var.dimensions.iter().flatten() {
+                visitor.visit_expression(dimension);
+            }
+            if let Some(value) = &var.value {
+                visitor.visit_expression(value);
+            }
+        }
+        Declaration::ParentType(_) => {}
+        Declaration::Proc(proc) => visitor.visit_proc(proc),
+    }
+}
+
+pub fn walk_proc<V: Visitor + ?Sized>(visitor: &mut V, proc: &ProcDeclaration) {
+    for param in &proc.params {
+        if let Some(default) = &param.default {
+            visitor.visit_expression(default);
+        }
+        if let Some(input_list) = &param.input_list {
+            visitor.visit_expression(input_list);
+        }
+    }
+    walk_block(visitor, &proc.body);
+}
+
+pub fn walk_block<V: Visitor + ?Sized>(visitor: &mut V, block: &[Statement]) {
+    for statement in block {
+        visitor.visit_statement(statement);
+    }
+}
+
+pub fn walk_local_var<V: Visitor + ?Sized>(visitor: &mut V, var: &LocalVar) {
+    for dimension in var.dimensions.iter().flatten() {
+        visitor.visit_expression(dimension);
+    }
+    if let Some(value) = &var.value {
+        visitor.visit_expression(value);
+    }
+}
+
+pub fn walk_statement<V: Visitor + ?Sized>(visitor: &mut V, statement: &Statement) {
+    match &statement.kind {
+        StatementKind::Expression(expression)
+        | StatementKind::Del(expression)
+        | StatementKind::Throw(expression)
+        | StatementKind::Set {
+            value: expression, ..
+        } => visitor.visit_expression(expression),
+        StatementKind::Return(value) => {
+            if let Some(value) = value {
+                visitor.visit_expression(value);
+            }
+        }
+        StatementKind::Block(body) | StatementKind::Label { body, .. } => {
+            walk_block(visitor, body);
+        }
+        StatementKind::Var(vars) => {
+            for var in vars {
+                walk_local_var(visitor, var);
+            }
+        }
+        StatementKind::Break(_) | StatementKind::Continue(_) | StatementKind::Goto(_) => {}
+        StatementKind::If { arms, else_arm } => {
+            for (condition, body) in arms {
+                visitor.visit_expression(condition);
+                walk_block(visitor, body);
+            }
+            if let Some(else_arm) = else_arm {
+                walk_block(visitor, else_arm);
+            }
+        }
+        StatementKind::While { condition, body } => {
+            visitor.visit_expression(condition);
+            walk_block(visitor, body);
+        }
+        StatementKind::DoWhile { body, condition } => {
+            walk_block(visitor, body);
+            visitor.visit_expression(condition);
+        }
+        StatementKind::For {
+            init,
+            condition,
+            increment,
+            body,
+        } => {
+            if let Some(init) = init {
+                visitor.visit_statement(init);
+            }
+            if let Some(condition) = condition {
+                visitor.visit_expression(condition);
+            }
+            if let Some(increment) = increment {
+                visitor.visit_statement(increment);
+            }
+            walk_block(visitor, body);
+        }
+        StatementKind::ForIn { list, body, .. } => {
+            if let Some(list) = list {
+                visitor.visit_expression(list);
+            }
+            walk_block(visitor, body);
+        }
+        StatementKind::ForRange {
+            start,
+            end,
+            step,
+            body,
+            ..
+        } => {
+            visitor.visit_expression(start);
+            visitor.visit_expression(end);
+            if let Some(step) = step {
+                visitor.visit_expression(step);
+            }
+            walk_block(visitor, body);
+        }
+        StatementKind::Switch {
+            value,
+            cases,
+            default,
+        } => {
+            visitor.visit_expression(value);
+            for case in cases {
+                for value in &case.values {
+                    match value {
+                        CaseValue::Single(value) => visitor.visit_expression(value),
+                        CaseValue::Range(start, end) => {
+                            visitor.visit_expression(start);
+                            visitor.visit_expression(end);
+                        }
+                    }
+                }
+                walk_block(visitor, &case.body);
+            }
+            if let Some(default) = default {
+                walk_block(visitor, default);
+            }
+        }
+        StatementKind::Spawn { delay, body } => {
+            if let Some(delay) = delay {
+                visitor.visit_expression(delay);
+            }
+            walk_block(visitor, body);
+        }
+        StatementKind::Try { body, catch } => {
+            walk_block(visitor, body);
+            if let Some(catch) = catch {
+                if let Some(var) = &catch.var {
+                    walk_local_var(visitor, var);
+                }
+                walk_block(visitor, &catch.body);
+            }
+        }
+    }
+}
+
+fn walk_arguments<V: Visitor + ?Sized>(visitor: &mut V, args: &[Argument]) {
+    for arg in args {
+        if let Some(key) = &arg.key {
+            visitor.visit_expression(key);
+        }
+        visitor.visit_expression(&arg.value);
+    }
+}
+
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &Expression) {
+    match &expression.kind {
+        ExpressionKind::Null
+        | ExpressionKind::Number(_)
+        | ExpressionKind::Resource(_)
+        | ExpressionKind::Ident(_)
+        | ExpressionKind::Dot
+        | ExpressionKind::ParentProc => {}
+        ExpressionKind::String(segments) => {
+            for segment in segments {
+                if let StringSegment::Expression(expression) = segment {
+                    visitor.visit_expression(expression);
+                }
+            }
+        }
+        ExpressionKind::Path { vars, .. } => {
+            for (_, value) in vars {
+                visitor.visit_expression(value);
+            }
+        }
+        ExpressionKind::Unary { operand, .. } => visitor.visit_expression(operand),
+        ExpressionKind::Binary { lhs, rhs, .. } => {
+            visitor.visit_expression(lhs);
+            visitor.visit_expression(rhs);
+        }
+        ExpressionKind::Assign { target, value, .. } => {
+            visitor.visit_expression(target);
+            visitor.visit_expression(value);
+        }
+        ExpressionKind::Ternary {
+            condition,
+            if_true,
+            if_false,
+        } => {
+            visitor.visit_expression(condition);
+            visitor.visit_expression(if_true);
+            visitor.visit_expression(if_false);
+        }
+        ExpressionKind::Field { object, .. } | ExpressionKind::StaticField { object, .. } => {
+            visitor.visit_expression(object);
+        }
+        ExpressionKind::Index { object, index, .. } => {
+            visitor.visit_expression(object);
+            visitor.visit_expression(index);
+        }
+        ExpressionKind::Call { callee, args } => {
+            visitor.visit_expression(callee);
+            walk_arguments(visitor, args);
+        }
+        ExpressionKind::New { new_type, args } => {
+            if let Some(new_type) = new_type {
+                visitor.visit_expression(new_type);
+            }
+            walk_arguments(visitor, args);
+        }
+        ExpressionKind::List { args, .. } => walk_arguments(visitor, args),
+        ExpressionKind::Locate { args, in_list } | ExpressionKind::Input { args, in_list, .. } => {
+            walk_arguments(visitor, args);
+            if let Some(in_list) = in_list {
+                visitor.visit_expression(in_list);
+            }
+        }
+        ExpressionKind::DynamicCall { target, args, .. } => {
+            walk_arguments(visitor, target);
+            walk_arguments(visitor, args);
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use std::{collections::HashMap, fmt::Display, fs, io, path::Path};
+
+use super::diagnostics::Diagnostic;
+
+const HEADER: &str = "# LiES baseline, regenerate with --update-baseline";
+
+/// Identifies a diagnostic without its line number, so that it stays the same when code above it
+/// moves.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BaselineKey {
+    pub file: String,
+    pub code: String,
+    /// A hash of the message and of the source line, ignoring whitespace. For code expanded from a
+    /// define that is the line the define is used on.
+    pub fingerprint: u64,
+}
+
+impl BaselineKey {
+    pub fn of(diagnostic: &Diagnostic) -> Self {
+        let span = diagnostic.location();
+        let file = span
+            .path()
+            .map(|path| path.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_default();
+        let line = span
+            .source()
+            .zip(span.line())
+            .and_then(|(source, line)| source.line_text(line))
+            .unwrap_or_default();
+        let mut hash = Fnv1a::default();
+        hash.write(diagnostic.code);
+        hash.write(&diagnostic.message);
+        for word in line.split_whitespace() {
+            hash.write(word);
+        }
+        Self {
+            file,
+            code: diagnostic.code.to_string(),
+            fingerprint: hash.finish(),
+        }
+    }
+}
+
+/// The diagnostics a codebase already had when it adopted LiES. Later runs only report the
+/// diagnostics that are not in it.
+///
+/// The file has one `file<TAB>code<TAB>fingerprint` line per diagnostic, a key is repeated when
+/// several diagnostics share it.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Baseline {
+    counts: HashMap<BaselineKey, usize>,
+}
+
+impl Baseline {
+    pub fn from_diagnostics(diagnostics: &[Diagnostic]) -> Self {
+        let mut baseline = Self::default();
+        for diagnostic in diagnostics {
+            *baseline
+                .counts
+                .entry(BaselineKey::of(diagnostic))
+                .or_default() += 1;
+        }
+        baseline
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut baseline = Self::default();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || format!("invalid baseline entry on line {}: `{line}`", index + 1);
+            let mut fields = line.split('\t');
+            let (Some(file), Some(code), Some(fingerprint), None) =
+                (fields.next(), fields.next(), fields.next(), fields.next())
+            else {
+                return Err(invalid());
+            };
+            let key = BaselineKey {
+                file: file.to_string(),
+                code: code.to_string(),
+                fingerprint: u64::from_str_radix(fingerprint, 16).map_err(|_| invalid())?,
+            };
+            *baseline.counts.entry(key).or_default() += 1;
+        }
+        Ok(baseline)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    /// The number of diagnostics in the baseline.
+    pub fn len(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// The diagnostics that are not in the baseline, and the number of baselined diagnostics that
+    /// no longer occur. A key that occurs more often than it was recorded reports the extra ones.
+    pub fn filter(&self, diagnostics: Vec<Diagnostic>) -> (Vec<Diagnostic>, usize) {
+        let mut remaining = self.counts.clone();
+        let new = diagnostics
+            .into_iter()
+            .filter(
+                |diagnostic| match remaining.get_mut(&BaselineKey::of(diagnostic)) {
+                    Some(count) if *count > 0 => {
+                        *count -= 1;
+                        false
+                    }
+                    _ => true,
+                },
+            )
+            .collect();
+        (new, remaining.values().sum())
+    }
+}
+
+impl Display for Baseline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{HEADER}")?;
+        // sorted so that regenerating it gives a readable diff
+        let mut keys: Vec<_> = self.counts.iter().collect();
+        keys.sort();
+        for (key, count) in keys {
+            for _ in 0..*count {
+                writeln!(f, "{}\t{}\t{:016x}", key.file, key.code, key.fingerprint)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// FNV-1a, which unlike the std hashers is guaranteed to give the same hash on every build.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    fn write(&mut self, text: &str) {
+        for byte in text.bytes().chain([0]) {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
@@ -0,0 +1,107 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::tokens::span::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+    Hint,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+            Severity::Hint => "hint",
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "error" => Ok(Severity::Error),
+            "warning" | "warn" => Ok(Severity::Warning),
+            "info" => Ok(Severity::Info),
+            "hint" => Ok(Severity::Hint),
+            _ => Err(format!("Unknown severity `{s}`")),
+        }
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A finding of an analysis pass, pointing at the code it is about.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub span: Span,
+    pub severity: Severity,
+    /// A short name for the kind of finding, such as `undefined_var`.
+    pub code: &'static str,
+    pub message: String,
+    /// Other locations that explain the finding, such as the declaration of a var.
+    pub notes: Vec<(Span, String)>,
+    /// Suggestions on how to fix it.
+    pub help: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(
+        severity: Severity,
+        code: &'static str,
+        span: &Span,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            span: span.clone(),
+            severity,
+            code,
+            message: message.into(),
+            notes: vec![],
+            help: vec![],
+        }
+    }
+
+    pub fn error(code: &'static str, span: &Span, message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, code, span, message)
+    }
+
+    pub fn warning(code: &'static str, span: &Span, message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, code, span, message)
+    }
+
+    pub fn with_note(mut self, span: &Span, note: impl Into<String>) -> Self {
+        self.notes.push((span.clone(), note.into()));
+        self
+    }
+
+    /// Where the diagnostic is shown, the use of the define for code expanded from one.
+    pub fn location(&self) -> &Span {
+        self.span.expansion().unwrap_or(&self.span)
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help.push(help.into());
+        self
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {}: {} [{}]",
+            self.span, self.severity, self.message, self.code
+        )
+    }
+}
@@ -0,0 +1,336 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::tokens::span::Span;
+
+use super::diagnostics::Diagnostic;
+
+/// The lines a change touches in every file, to only report the diagnostics of a pull request.
+/// Lines are numbered in the new version of the files.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DiffScope {
+    /// The changed lines of every file as sorted inclusive ranges, keyed by `/` separated path.
+    files: HashMap<String, Vec<(usize, usize)>>,
+}
+
+impl DiffScope {
+    /// Reads a unified diff such as the output of `git diff`. Added lines count as changed, and
+    /// removed lines mark the line that now follows them.
+    pub fn from_unified_diff(diff: &str) -> Result<Self, String> {
+        let mut scope = Self::default();
+        let mut file: Option<String> = None;
+        // the next line on the new side, and how many lines the hunk has left on each side
+        let mut line = 0;
+        let (mut old_left, mut new_left) = (0, 0);
+        for (index, text) in diff.lines().enumerate() {
+            if old_left > 0 || new_left > 0 {
+                match text.chars().next() {
+                    Some('+') => {
+                        scope.add(&file, line);
+                        line += 1;
+                        new_left -= 1;
+                    }
+                    Some('-') => {
+                        scope.add(&file, line.max(1));
+                        old_left -= 1;
+                    }
+                    Some(' ') | None => {
+                        line += 1;
+                        old_left -= 1;
+                        new_left -= 1;
+                    }
+                    // `\ No newline at end of file`
+                    Some('\\') => {}
+                    Some(_) => return Err(format!("unexpected line {} in hunk", index + 1)),
+                }
+            } else if let Some(path) = text.strip_prefix("+++ ") {
+                let path = path.split('\t').next().unwrap_or_default().trim();
+                file = (path != "/dev/null")
+                    .then(|| normalize(path.strip_prefix("b/").unwrap_or(path)));
+            } else if let Some(header) = text.strip_prefix("@@ ") {
+                let invalid = || format!("invalid hunk header on line {}", index + 1);
+                let (old, new) = header
+                    .split_once(' ')
+                    .and_then(|(old, rest)| Some((old.strip_prefix('-')?, rest.strip_prefix('+')?)))
+                    .ok_or_else(invalid)?;
+                let new = new.split(' ').next().unwrap_or_default();
+                let (_, old_count) = hunk_range(old).ok_or_else(invalid)?;
+                let (new_start, new_count) = hunk_range(new).ok_or_else(invalid)?;
+                // a hunk without new lines starts on the line before the removed ones
+                line = if new_count == 0 {
+                    new_start + 1
+                } else {
+                    new_start
+                };
+                (old_left, new_left) = (old_count, new_count);
+            }
+        }
+        Ok(scope)
+    }
+
+    /// Compares the files of two versions of a tree, `new` being the environment directory that
+    /// is parsed. Files that are only in `new` are changed entirely.
+    pub fn from_trees(old: &Path, new: &Path) -> io::Result<Self> {
+        let mut scope = Self::default();
+        let mut files = vec![];
+        collect_files(new, &mut files)?;
+        for path in files {
+            let relative = path.strip_prefix(new).unwrap_or(&path);
+            let new_text = read_lossy(&path)?;
+            let old_text = match fs::read(old.join(relative)) {
+                Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+                Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+                Err(error) => return Err(error),
+            };
+            if old_text == new_text {
+                continue;
+            }
+            let old_lines: Vec<&str> = old_text.lines().collect();
+            let new_lines: Vec<&str> = new_text.lines().collect();
+            let file = Some(normalize(&relative.to_string_lossy()));
+            for line in changed_lines(&old_lines, &new_lines) {
+                scope.add(&file, line);
+            }
+        }
+        Ok(scope)
+    }
+
+    fn add(&mut self, file: &Option<String>, line: usize) {
+        let Some(file) = file else {
+            return;
+        };
+        let ranges = self.files.entry(file.clone()).or_default();
+        let index = ranges.partition_point(|&(_, end)| end + 1 < line);
+        match ranges.get_mut(index) {
+            Some((start, end)) if *start <= line + 1 => {
+                *start = (*start).min(line);
+                *end = (*end).max(line);
+                // the grown range may now reach the next one
+                if let Some(&(next_start, next_end)) = ranges.get(index + 1) {
+                    if next_start <= ranges[index].1 + 1 {
+                        ranges[index].1 = ranges[index].1.max(next_end);
+                        ranges.remove(index + 1);
+                    }
+                }
+            }
+            _ => ranges.insert(index, (line, line)),
+        }
+    }
+
+    /// The changed lines of `file` as sorted inclusive ranges.
+    pub fn changed_lines(&self, file: &str) -> &[(usize, usize)] {
+        self.files.get(file).map_or(&[], Vec::as_slice)
+    }
+
+    /// Whether the change touches the span. Paths are compared by their end, so that a diff of the
+    /// repository matches an environment directory in a subdirectory of it.
+    pub fn touches(&self, span: &Span) -> bool {
+        let (Some(path), Some(start), Some(end)) = (span.path(), span.line(), span.end_line())
+        else {
+            return false;
+        };
+        let path = normalize(&path.to_string_lossy());
+        self.files.iter().any(|(file, ranges)| {
+            let same_file = file == &path
+                || file
+                    .strip_suffix(path.as_str())
+                    .is_some_and(|prefix| prefix.ends_with('/'));
+            same_file
+                && ranges.iter().any(|&(changed_start, changed_end)| {
+                    changed_start <= end && start <= changed_end
+                })
+        })
+    }
+
+    /// Keeps the diagnostics whose span, or the macro call it was expanded from, the change
+    /// touches.
+    pub fn filter(&self, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        diagnostics
+            .into_iter()
+            .filter(|diagnostic| {
+                self.touches(&diagnostic.span)
+                    || diagnostic
+                        .span
+                        .expansion()
+                        .is_some_and(|call_site| self.touches(call_site))
+            })
+            .collect()
+    }
+}
+
+fn normalize(path: &str) -> String {
+    path.replace('\\', "/").trim_start_matches("./").to_string()
+}
+
+/// `start,count` of a hunk header, a missing count is 1.
+fn hunk_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+fn read_lossy(path: &Path) -> io::Result<String> {
+    Ok(String::from_utf8_lossy(&fs::read(path)?).into_owned())
+}
+
+/// The DM sources under `directory`. Maps and icons are not checked, so they are not diffed.
+fn collect_files(directory: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if path.file_name().is_some_and(|name| name == ".git") {
+                continue;
+            }
+            collect_files(&path, files)?;
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "dm" || extension == "dme")
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// The 1-based lines of `new` that are not in a longest common subsequence with `old`, and the
+/// lines that follow removed ones, found with the linear space variant of Myers' diff.
+fn changed_lines(old: &[&str], new: &[&str]) -> Vec<usize> {
+    let mut edits = Edits::default();
+    diff_lines(old, new, 0, &mut edits);
+    let mut lines = edits.inserted.clone();
+    // a removal next to an inserted line is part of replacing it, otherwise the line that now
+    // follows the removed ones is marked
+    for &gap in &edits.removed {
+        let inserted_around = edits.inserted.contains(&gap) || edits.inserted.contains(&(gap + 1));
+        if !inserted_around {
+            lines.push((gap + 1).min(new.len().max(1)));
+        }
+    }
+    lines.sort_unstable();
+    lines.dedup();
+    lines
+}
+
+#[derive(Default)]
+struct Edits {
+    /// The 1-based lines of `new` that are not in `old`.
+    inserted: Vec<usize>,
+    /// The number of lines of `new` before every run of removed lines.
+    removed: Vec<usize>,
+}
+
+/// Adds the edits from `old` to `new`, where `new` starts after `new_start` lines of the file.
+fn diff_lines(old: &[&str], new: &[&str], new_start: usize, edits: &mut Edits) {
+    // only the part between the common start and end needs diffing
+    let prefix = old
+        .iter()
+        .zip(new)
+        .take_while(|(old, new)| old == new)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let old = &old[prefix..old.len() - suffix];
+    let new = &new[prefix..new.len() - suffix];
+    let new_start = new_start + prefix;
+
+    if new.is_empty() {
+        if !old.is_empty() {
+            edits.removed.push(new_start);
+        }
+    } else if old.is_empty() {
+        edits.inserted.extend(new_start + 1..=new_start + new.len());
+    } else if let Some((x, y)) = middle_snake(old, new) {
+        diff_lines(&old[..x], &new[..y], new_start, edits);
+        diff_lines(&old[x..], &new[y..], new_start + y, edits);
+    } else {
+        // nothing in common, every line is replaced
+        edits.inserted.extend(new_start + 1..=new_start + new.len());
+    }
+}
+
+/// A point on a shortest edit path from `old` to `new` half way through it, found by searching
+/// from both ends at once. Only the furthest reach of every diagonal is kept, so the memory is
+/// linear in the length of the files.
+fn middle_snake(old: &[&str], new: &[&str]) -> Option<(usize, usize)> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max_d = (n + m + 1) / 2;
+    let offset = max_d + 1;
+    let length = 2 * offset as usize + 1;
+    // the furthest x reached on every diagonal k = x - y, from the start and from the end
+    let mut forward = vec![-1isize; length];
+    let mut backward = vec![-1isize; length];
+    forward[offset as usize + 1] = 0;
+    backward[offset as usize + 1] = 0;
+    let delta = n - m;
+    // with an odd delta the paths meet while searching forward, otherwise backward
+    let front = delta % 2 != 0;
+    // diagonals that ran off the edit graph are not searched again
+    let (mut k1_start, mut k1_end, mut k2_start, mut k2_end) = (0, 0, 0, 0);
+    for d in 0..max_d {
+        for k1 in (-d + k1_start..=d - k1_end).step_by(2) {
+            let index = (offset + k1) as usize;
+            let mut x1 = if k1 == -d || (k1 != d && forward[index - 1] < forward[index + 1]) {
+                forward[index + 1]
+            } else {
+                forward[index - 1] + 1
+            };
+            let mut y1 = x1 - k1;
+            while x1 < n && y1 < m && old[x1 as usize] == new[y1 as usize] {
+                x1 += 1;
+                y1 += 1;
+            }
+            forward[index] = x1;
+            if x1 > n {
+                k1_end += 2;
+            } else if y1 > m {
+                k1_start += 2;
+            } else if front {
+                let k2_index = offset + delta - k1;
+                if (0..length as isize).contains(&k2_index) && backward[k2_index as usize] != -1 {
+                    let x2 = n - backward[k2_index as usize];
+                    if x1 >= x2 {
+                        return Some((x1 as usize, y1 as usize));
+                    }
+                }
+            }
+        }
+
+        for k2 in (-d + k2_start..=d - k2_end).step_by(2) {
+            let index = (offset + k2) as usize;
+            let mut x2 = if k2 == -d || (k2 != d && backward[index - 1] < backward[index + 1]) {
+                backward[index + 1]
+            } else {
+                backward[index - 1] + 1
+            };
+            let mut y2 = x2 - k2;
+            while x2 < n && y2 < m && old[(n - x2 - 1) as usize] == new[(m - y2 - 1) as usize] {
+                x2 += 1;
+                y2 += 1;
+            }
+            backward[index] = x2;
+            if x2 > n {
+                k2_end += 2;
+            } else if y2 > m {
+                k2_start += 2;
+            } else if !front {
+                let k1_index = offset + delta - k2;
+                if (0..length as isize).contains(&k1_index) && forward[k1_index as usize] != -1 {
+                    let x1 = forward[k1_index as usize];
+                    let y1 = offset + x1 - k1_index;
+                    if x1 >= n - x2 {
+                        return Some((x1 as usize, y1 as usize));
+                    }
+                }
+            }
+        }
+    }
+    None
+}
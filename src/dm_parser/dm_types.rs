@@ -1 +1,125 @@
+use std::fmt::Display;
 
+use crate::util::symbol::Symbol;
+
+use super::parsing::type_path::DmTypePath;
+
+/// A value known at compile time, with the types BYOND gives them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DmValue {
+    Null,
+    Num(f32),
+    Text(String),
+    /// A type path such as `/obj/item`.
+    Path(DmTypePath),
+    /// A proc path such as `/obj/item/proc/attack`, the proc `name` as seen from `on`.
+    ProcPath {
+        on: DmTypePath,
+        name: Symbol,
+    },
+    /// `list(...)`, each entry with its associated value if it has one.
+    List(Vec<(DmValue, Option<DmValue>)>),
+    /// `'icon.dmi'`
+    Resource(String),
+}
+
+impl DmValue {
+    /// The name of the value's type as BYOND's `istype` family would call it.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            DmValue::Null => "null",
+            DmValue::Num(_) => "num",
+            DmValue::Text(_) => "text",
+            DmValue::Path(_) | DmValue::ProcPath { .. } => "path",
+            DmValue::List(_) => "list",
+            DmValue::Resource(_) => "resource",
+        }
+    }
+
+    /// Whether an `if` would take the value as true.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            DmValue::Null => false,
+            DmValue::Num(num) => *num != 0.0,
+            DmValue::Text(text) => !text.is_empty(),
+            _ => true,
+        }
+    }
+
+    /// The text `"[value]"` embeds in a string.
+    pub fn to_text(&self) -> String {
+        match self {
+            DmValue::Null => String::new(),
+            DmValue::Num(num) => format_num(*num),
+            DmValue::Text(text) | DmValue::Resource(text) => text.clone(),
+            DmValue::Path(path) => path.to_string(),
+            DmValue::ProcPath { .. } => self.to_string(),
+            DmValue::List(_) => "/list".into(),
+        }
+    }
+}
+
+/// Formats a number the way BYOND prints it, whole numbers without a fraction and everything
+/// else with up to 6 significant digits.
+pub fn format_num(num: f32) -> String {
+    if num.is_nan() {
+        return "nan".into();
+    }
+    if num.is_infinite() {
+        return if num > 0.0 { "inf" } else { "-inf" }.into();
+    }
+    if num.fract() == 0.0 && num.abs() < 1e7 {
+        return format!("{num:.0}");
+    }
+    let digits = 6 - (num.abs().log10().floor() as i32 + 1);
+    let formatted = format!("{:.*}", digits.clamp(0, 6) as usize, num);
+    if formatted.contains('.') {
+        formatted
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    } else {
+        formatted
+    }
+}
+
+impl Display for DmValue {
+    /// Writes the value as DM code.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DmValue::Null => write!(f, "null"),
+            DmValue::Num(num) => write!(f, "{}", format_num(*num)),
+            DmValue::Text(text) => write!(f, "{text:?}"),
+            DmValue::Path(path) => write!(f, "{path}"),
+            DmValue::ProcPath { on, name } if on.is_root() => write!(f, "/proc/{name}"),
+            DmValue::ProcPath { on, name } => write!(f, "{on}/proc/{name}"),
+            DmValue::List(entries) => {
+                write!(f, "list(")?;
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    match value {
+                        Some(value) => write!(f, "{key} = {value}")?,
+                        None => write!(f, "{key}")?,
+                    }
+                }
+                write!(f, ")")
+            }
+            DmValue::Resource(resource) => write!(f, "'{resource}'"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_num_like_byond() {
+        assert_eq!(format_num(3.0), "3");
+        assert_eq!(format_num(-0.5), "-0.5");
+        assert_eq!(format_num(1.0 / 3.0), "0.333333");
+        assert_eq!(format_num(1234.5678), "1234.57");
+    }
+}
@@ -7,9 +7,19 @@ use log::{debug, error, info, trace, warn};
 
 use crate::{
     dm_preprocessor::lib::DmPreProcessor,
+    tokens::{dm_token::DmToken, span::Span, tokenize::DmTokenizer},
     util::{dm_file::DmFile, ParseError},
 };
 
+use super::{
+    analysis::{annotations, call_graph::CallGraph, checker, lint::Lints},
+    ast::DmAst,
+    diagnostics::Diagnostic,
+    object_tree::ObjectTree,
+    prefetch::TokenizePrefetcher,
+    suppressions,
+};
+
 enum ParseLogMode {
     None,
     Directory,
@@ -43,6 +53,14 @@ pub struct DmParser {
     parse_log_mode: ParseLogMode,
     parse_last_dir: PathBuf,
     environment_traversal: Vec<PathBuf>,
+    prefetcher: TokenizePrefetcher,
+    /// The parsed files in include order.
+    asts: Vec<DmAst>,
+    /// Every type declared so far, merged across files.
+    object_tree: ObjectTree,
+    lints: Lints,
+    /// The `// lies:` comments of every parsed file.
+    comments: Vec<Span>,
 }
 
 impl Default for DmParser {
@@ -55,6 +73,40 @@ impl DmParser {
     pub fn environment_directory(&self) -> &PathBuf {
         &self.environment_directory
     }
+
+    pub fn asts(&self) -> &[DmAst] {
+        &self.asts
+    }
+
+    pub fn object_tree(&self) -> &ObjectTree {
+        &self.object_tree
+    }
+
+    /// Runs the semantic checks over everything parsed so far.
+    pub fn check(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = checker::check(&self.object_tree, &self.asts);
+        let call_graph = CallGraph::build(&self.object_tree);
+        diagnostics.extend(annotations::check(&self.object_tree, &call_graph));
+        diagnostics
+    }
+
+    /// Runs the lints that are on over everything parsed so far.
+    pub fn lint(&self) -> Vec<Diagnostic> {
+        self.lints.run(&self.object_tree, &self.asts)
+    }
+
+    /// The findings of both the semantic checks and the lints, without the ones suppressed by
+    /// `// lies:ignore` comments.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.check();
+        diagnostics.extend(self.lint());
+        let disabled: Vec<&str> = self.lints.disabled().collect();
+        suppressions::apply(diagnostics, &self.comments, &disabled)
+    }
+
+    pub fn lints_mut(&mut self) -> &mut Lints {
+        &mut self.lints
+    }
 }
 
 impl DmParser {
@@ -64,7 +116,19 @@ impl DmParser {
             "DmParser new with env dir `{}`",
             environment_directory.display()
         );
+        let tokenize_threads = env::var("LIES_TOKENIZE_THREADS")
+            .map(|threads| {
+                threads
+                    .parse()
+                    .expect("failed to parse LIES_TOKENIZE_THREADS")
+            })
+            .unwrap_or_else(|_| {
+                std::thread::available_parallelism()
+                    .map(|threads| threads.get() - 1)
+                    .unwrap_or(0)
+            });
         Self {
+            prefetcher: TokenizePrefetcher::new(&environment_directory, tokenize_threads),
             preprocessor: DmPreProcessor::new(),
             _include_order: vec![],
             environment_directory,
@@ -74,6 +138,10 @@ impl DmParser {
                 .parse()
                 .expect("failed to parse LIES_PARSE_LOG_MODE"),
             parse_last_dir: ".".into(),
+            asts: vec![],
+            object_tree: ObjectTree::new(),
+            lints: Lints::from_env(),
+            comments: vec![],
         }
     }
 
@@ -84,6 +152,19 @@ impl DmParser {
     }
 
     pub fn load_path(&mut self, path: impl Into<PathBuf>) -> Result<(), ParseError> {
+        let actual_path = self.resolve_path(path)?;
+        if let Some(tokens) = self.prefetcher.take(&actual_path) {
+            trace!("using prefetched tokens for {}", actual_path.display());
+            let tokens = tokens
+                .map_err(|err| err.with_file_path(actual_path.to_string_lossy().to_string()))?;
+            return self.load_tokens(&actual_path, tokens.into_iter());
+        }
+        self.load_file(DmFile::new(&self.environment_directory, actual_path)?)
+    }
+
+    /// Resolves a path relative to the file currently being parsed into a path relative to the
+    /// environment directory.
+    fn resolve_path(&self, path: impl Into<PathBuf>) -> Result<PathBuf, ParseError> {
         let current_traversal = self
             .environment_traversal
             .last()
@@ -115,13 +196,18 @@ impl DmParser {
             ParseError::PATH_CANONICALIZE_FAIL.with_file_path(wanted_path_str_fixed)
         })?;
 
-        let actual_path = self.convert_canonical_path_to_relative(&wanted_path);
-        self.load_file(DmFile::new(&self.environment_directory, actual_path)?)
+        Ok(self.convert_canonical_path_to_relative(&wanted_path))
     }
 
     pub fn load_file(&mut self, file: DmFile) -> Result<(), ParseError> {
-        let actual_path = file.path();
+        self.load_tokens(file.path(), DmTokenizer::from_file(&file))
+    }
 
+    fn load_tokens(
+        &mut self,
+        actual_path: &Path,
+        tokens: impl Iterator<Item = DmToken>,
+    ) -> Result<(), ParseError> {
         // announce each directory we enter if the depth is lower than the set depth
         match self.parse_log_mode {
             ParseLogMode::Directory => {
@@ -153,14 +239,21 @@ impl DmParser {
         }
 
         self.preprocessor.add_to_include_order(actual_path);
-        let result = self.parse_file(&file);
+        let result = self.parse_file(actual_path, tokens);
         if result.is_ok() {
             trace!("Successfully loaded file {}", actual_path.display());
         } else {
             trace!("Failed to load file {}", actual_path.display());
         }
 
-        for pending_include in self.preprocessor.take_pending_includes() {
+        let pending_includes = self.preprocessor.take_pending_includes();
+        let prefetch = pending_includes
+            .iter()
+            .filter_map(|include| self.resolve_path(include).ok())
+            .filter(|include| Self::is_parsed_file(include))
+            .collect::<Vec<_>>();
+        self.prefetcher.request(prefetch);
+        for pending_include in pending_includes {
             self.load_path(pending_include)?;
         }
 
@@ -168,22 +261,38 @@ impl DmParser {
             .pop() // not returning an Err here because this SHOULD not be possible
             .expect("failed to pop directory traversal?");
 
-        result.map_err(|err| err.with_file_path(file.path().to_str().unwrap().to_string()))
+        result.map_err(|err| err.with_file_path(actual_path.to_str().unwrap().to_string()))
     }
 
-    fn parse_file(&mut self, file: &DmFile) -> Result<(), ParseError> {
-        let file_extension = file
-            .path()
+    fn is_parsed_file(path: &Path) -> bool {
+        let file_extension = path
             .extension()
             .expect("parsing file without extension!")
             .to_str()
             .expect("failed to convert extension to str");
-        if !matches!(file_extension, "dme" | "dm") {
-            warn!("Skipping File: {}", file.path().display());
+        matches!(file_extension, "dme" | "dm")
+    }
+
+    fn parse_file(
+        &mut self,
+        path: &Path,
+        tokens: impl Iterator<Item = DmToken>,
+    ) -> Result<(), ParseError> {
+        if !Self::is_parsed_file(path) {
+            warn!("Skipping File: {}", path.display());
             return Ok(());
         }
 
-        let tokens = self.preprocessor.preprocess(file)?;
-        self.parse_tokens(tokens)
+        let comments = &mut self.comments;
+        let tokens = tokens.inspect(|token| {
+            if token.is_comment() {
+                comments.push(token.span());
+            }
+        });
+        let tokens = self.preprocessor.preprocess_tokens(tokens);
+        let ast = Self::parse_tokens(path, tokens)?;
+        self.object_tree.add_ast(&ast);
+        self.asts.push(ast);
+        Ok(())
     }
 }
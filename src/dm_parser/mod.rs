@@ -1,3 +1,15 @@
+pub mod analysis;
+pub mod ast;
+pub mod baseline;
+pub mod diagnostics;
+pub mod diff_scope;
 pub mod dm_types;
 pub mod lib;
+pub mod object_tree;
 pub mod parsing;
+pub mod prefetch;
+pub mod render;
+pub mod suppressions;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,152 @@
+use std::{collections::HashMap, sync::Arc};
+
+use log::warn;
+
+use crate::dm_parser::{
+    ast::{
+        declaration::{Declaration, ProcDeclaration, VarDeclaration},
+        DmAst,
+    },
+    parsing::type_path::DmTypePath,
+};
+
+use super::{ObjectTree, TypeNode, TypeProc, TypeVar};
+
+/// The types BYOND provides and their parents, `None` for types directly under the root.
+const BUILTIN_TYPES: &[(&str, Option<&str>)] = &[
+    ("/datum", None),
+    ("/atom", Some("/datum")),
+    ("/atom/movable", Some("/atom")),
+    ("/area", Some("/atom")),
+    ("/turf", Some("/atom")),
+    ("/obj", Some("/atom/movable")),
+    ("/mob", Some("/atom/movable")),
+    ("/client", None),
+    ("/world", None),
+    ("/list", None),
+    ("/savefile", None),
+    ("/image", Some("/datum")),
+    ("/icon", Some("/datum")),
+    ("/sound", Some("/datum")),
+    ("/matrix", Some("/datum")),
+    ("/regex", Some("/datum")),
+    ("/database", Some("/datum")),
+    ("/mutable_appearance", Some("/image")),
+    ("/exception", Some("/datum")),
+    ("/callee", Some("/datum")),
+    ("/particles", Some("/datum")),
+    ("/generator", Some("/datum")),
+];
+
+impl ObjectTree {
+    /// A tree holding only the root and the builtin types.
+    pub fn new() -> Self {
+        let mut tree = Self {
+            types: vec![],
+            index: HashMap::new(),
+        };
+        tree.insert(TypeNode::new(DmTypePath::default(), true));
+        for (path, parent) in BUILTIN_TYPES {
+            let mut node = TypeNode::new(DmTypePath::from(*path), true);
+            node.default_parent = parent.map(DmTypePath::from);
+            tree.insert(node);
+        }
+        tree.add_builtins();
+        tree
+    }
+
+    /// Merges the declarations of a parsed file into the tree.
+    pub fn add_ast(&mut self, ast: &DmAst) {
+        for declaration in ast.iter_declarations() {
+            match declaration {
+                Declaration::Type(type_declaration) => {
+                    self.node_mut(&type_declaration.path)
+                        .locations
+                        .push(type_declaration.span.clone());
+                }
+                Declaration::Var(var) => self.node_mut(&var.owner).add_var(Arc::clone(var)),
+                Declaration::ParentType(parent_type) => {
+                    let node = self.node_mut(&parent_type.owner);
+                    if let Some(previous) = &node.parent_type {
+                        if previous.parent != parent_type.parent {
+                            warn!(
+                                "{}: parent_type of `{}` changed from `{}` to `{}`, declared at {}",
+                                parent_type.span,
+                                node.path,
+                                previous.parent,
+                                parent_type.parent,
+                                previous.span
+                            );
+                        }
+                    }
+                    node.parent_type = Some(parent_type.clone());
+                }
+                Declaration::Proc(proc) => self.node_mut(&proc.owner).add_proc(Arc::clone(proc)),
+            }
+        }
+    }
+
+    fn insert(&mut self, node: TypeNode) -> usize {
+        let index = self.types.len();
+        self.index.insert(node.path.clone(), index);
+        self.types.push(node);
+        index
+    }
+
+    /// The node for `path`, creating it and every type above it that does not exist yet.
+    pub(super) fn node_mut(&mut self, path: &DmTypePath) -> &mut TypeNode {
+        if let Some(&index) = self.index.get(path) {
+            return &mut self.types[index];
+        }
+        if let Some(parent) = path.parent() {
+            self.node_mut(&parent);
+        }
+        let index = self.insert(TypeNode::new(path.clone(), false));
+        &mut self.types[index]
+    }
+}
+
+impl TypeNode {
+    fn new(path: DmTypePath, builtin: bool) -> Self {
+        let default_parent = match path.depth() {
+            0 => None,
+            1 => Some(DmTypePath::from("/datum")),
+            _ => path.parent(),
+        };
+        Self {
+            path,
+            builtin,
+            default_parent,
+            parent_type: None,
+            locations: vec![],
+            vars: vec![],
+            var_index: HashMap::new(),
+            procs: vec![],
+            proc_index: HashMap::new(),
+        }
+    }
+
+    fn add_var(&mut self, var: Arc<VarDeclaration>) {
+        let index = *self.var_index.entry(var.name).or_insert_with(|| {
+            self.vars.push(TypeVar {
+                name: var.name,
+                declarations: vec![],
+                builtin: None,
+            });
+            self.vars.len() - 1
+        });
+        self.vars[index].declarations.push(var);
+    }
+
+    fn add_proc(&mut self, proc: Arc<ProcDeclaration>) {
+        let index = *self.proc_index.entry(proc.name).or_insert_with(|| {
+            self.procs.push(TypeProc {
+                name: proc.name,
+                definitions: vec![],
+                builtin: None,
+            });
+            self.procs.len() - 1
+        });
+        self.procs[index].definitions.push(proc);
+    }
+}
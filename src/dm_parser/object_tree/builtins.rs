@@ -0,0 +1,682 @@
+use std::{fmt::Display, path::Path, str::FromStr};
+
+use crate::{
+    dm_parser::{
+        ast::{declaration::Declaration, DmAst},
+        parsing::{lexer::Lexer, syntax::SyntaxParser},
+    },
+    tokens::{span::Span, tokenize::DmTokenizer},
+    util::ParseError,
+};
+
+use super::ObjectTree;
+
+/// The file name builtin declarations are reported in.
+pub const BUILTINS_PATH: &str = "builtins.dm";
+
+/// The BYOND version the builtin table describes.
+pub const BUILTINS_VERSION: ByondVersion = ByondVersion::new(515, 1647);
+
+/// A BYOND release such as `515.1647`. A build of `0` stands for every build of the major
+/// version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByondVersion {
+    pub major: u16,
+    pub build: u16,
+}
+
+impl ByondVersion {
+    pub const fn new(major: u16, build: u16) -> Self {
+        Self { major, build }
+    }
+}
+
+impl Display for ByondVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.build == 0 {
+            return write!(f, "{}", self.major);
+        }
+        write!(f, "{}.{}", self.major, self.build)
+    }
+}
+
+impl FromStr for ByondVersion {
+    type Err = String;
+
+    /// Parses `515.1647` or `515`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (major, build) = s.split_once('.').unwrap_or((s, "0"));
+        let parse = |part: &str| {
+            part.trim()
+                .parse::<u16>()
+                .map_err(|_| format!("`{s}` is not a BYOND version"))
+        };
+        Ok(Self::new(parse(major)?, parse(build)?))
+    }
+}
+
+/// A var or proc BYOND provides.
+#[derive(Debug)]
+pub struct BuiltinDefinition {
+    /// The declaration as DM code, `/atom/var/icon_state` or `/proc/sleep(Delay)`.
+    pub declaration: &'static str,
+    pub doc: &'static str,
+    /// The first version with the definition, `None` for ones older than any supported version.
+    pub since: Option<ByondVersion>,
+}
+
+impl BuiltinDefinition {
+    const fn new(declaration: &'static str, doc: &'static str) -> Self {
+        Self {
+            declaration,
+            doc,
+            since: None,
+        }
+    }
+
+    const fn since(mut self, major: u16, build: u16) -> Self {
+        self.since = Some(ByondVersion::new(major, build));
+        self
+    }
+
+    pub fn is_available_in(&self, version: ByondVersion) -> bool {
+        match self.since {
+            Some(since) => since <= version,
+            None => true,
+        }
+    }
+
+    /// True if the proc takes any number of arguments after its named parameters.
+    pub fn is_variadic(&self) -> bool {
+        self.declaration.contains("...")
+    }
+}
+
+const fn def(declaration: &'static str, doc: &'static str) -> BuiltinDefinition {
+    BuiltinDefinition::new(declaration, doc)
+}
+
+/// Every builtin var and proc, one declaration each.
+pub static BUILTINS: &[BuiltinDefinition] = &[
+    // /datum
+    def("/datum/var/type", "The type path of the object."),
+    def("/datum/var/parent_type", "The type this type inherits from."),
+    def("/datum/var/tag", "A unique text id that `locate()` can find the object by."),
+    def("/datum/var/vars", "A list of the object's vars and their values."),
+    def("/datum/proc/New()", "Called when the object is created."),
+    def("/datum/proc/Del()", "Called when the object is deleted."),
+    def("/datum/proc/Topic(href, href_list)", "Called when a link to the object is clicked."),
+    def("/datum/proc/Read(F)", "Called when the object is loaded from a savefile."),
+    def("/datum/proc/Write(F)", "Called when the object is saved to a savefile."),
+    // /atom
+    def("/atom/var/name", "The name shown to players."),
+    def("/atom/var/desc", "The description shown when the object is examined."),
+    def("/atom/var/icon", "The icon file the object is drawn from."),
+    def("/atom/var/icon_state", "The state in `icon` the object is drawn with."),
+    def("/atom/var/dir = 2", "The direction the object faces."),
+    def("/atom/var/layer", "The drawing order among objects on the same plane."),
+    def("/atom/var/plane = 0", "The drawing plane, higher planes are drawn on top."),
+    def("/atom/var/alpha = 255", "The opacity, `0` is invisible."),
+    def("/atom/var/color", "A color or color matrix multiplied with the icon."),
+    def("/atom/var/density = 0", "Whether the object blocks movement."),
+    def("/atom/var/opacity = 0", "Whether the object blocks vision."),
+    def("/atom/var/invisibility = 0", "How well the object is hidden from `see_invisible`."),
+    def("/atom/var/mouse_opacity = 1", "How the object reacts to the mouse."),
+    def("/atom/var/loc", "The object containing this one."),
+    def("/atom/var/x", "The x coordinate of the turf the object is on."),
+    def("/atom/var/y", "The y coordinate of the turf the object is on."),
+    def("/atom/var/z", "The z level of the turf the object is on."),
+    def("/atom/var/contents", "The objects inside this one."),
+    def("/atom/var/overlays", "Appearances drawn on top of the object."),
+    def("/atom/var/underlays", "Appearances drawn below the object."),
+    def("/atom/var/verbs", "The verbs the object provides."),
+    def("/atom/var/appearance", "The object's appearance as a single value."),
+    def("/atom/var/appearance_flags = 0", "Flags changing how the appearance is drawn."),
+    def("/atom/var/pixel_x = 0", "The horizontal offset in pixels."),
+    def("/atom/var/pixel_y = 0", "The vertical offset in pixels."),
+    def("/atom/var/maptext", "Text drawn on the map over the object."),
+    def("/atom/var/filters", "Graphical filters applied to the object.").since(512, 0),
+    def("/atom/var/render_target", "Renders the object to a target other objects can draw.")
+        .since(513, 0),
+    def("/atom/var/vis_contents", "Objects drawn as if they were inside this one.")
+        .since(512, 0),
+    def("/atom/var/gender", "`male`, `female`, `neuter` or `plural`, used by text macros."),
+    def("/atom/var/luminosity = 0", "How far the object lights up its surroundings."),
+    def("/atom/var/infra_luminosity = 0", "How far the object can be seen with `see_infrared`."),
+    def("/atom/var/suffix", "Text shown after the name in the statpanel."),
+    def("/atom/var/text", "The character the object is drawn as on text maps."),
+    def("/atom/var/transform", "A matrix transforming how the object is drawn."),
+    def("/atom/var/blend_mode = 0", "How the object is blended with what is below it."),
+    def("/atom/var/maptext_width = 32", "The width of the maptext box."),
+    def("/atom/var/maptext_height = 32", "The height of the maptext box."),
+    def("/atom/var/maptext_x = 0", "The horizontal offset of the maptext."),
+    def("/atom/var/maptext_y = 0", "The vertical offset of the maptext."),
+    def("/atom/var/pixel_w = 0", "The horizontal offset in pixels that ignores the map format."),
+    def("/atom/var/pixel_z = 0", "The vertical offset in pixels that ignores the map format."),
+    def("/atom/var/mouse_over_pointer", "The mouse cursor over the object."),
+    def("/atom/var/mouse_drag_pointer", "The mouse cursor while dragging the object."),
+    def("/atom/var/mouse_drop_pointer", "The mouse cursor while dropping onto the object."),
+    def("/atom/var/mouse_drop_zone = 0", "Whether the object can be dropped onto."),
+    def("/atom/var/override = 0", "Whether an image replaces the object instead of covering it."),
+    def("/atom/var/render_source", "The render target the object draws instead of its icon.").since(513, 0),
+    def("/atom/var/vis_flags = 0", "How the object behaves in `vis_contents`.").since(512, 0),
+    def("/atom/var/vis_locs", "The objects with this one in their `vis_contents`.").since(512, 0),
+    def("/atom/var/particles", "A particle set emitted by the object.").since(514, 0),
+    def("/atom/proc/Click(location, control, params)", "Called when the object is clicked."),
+    def("/atom/proc/DblClick(location, control, params)", "Called on a double click."),
+    def("/atom/proc/MouseDrop(over_object, src_location, over_location, src_control, over_control, params)",
+        "Called when the object is dragged onto something."),
+    def("/atom/proc/Enter(atom/movable/O, atom/oldloc)", "Whether `O` may move into the object."),
+    def("/atom/proc/Entered(atom/movable/Obj, atom/OldLoc)", "Called after `Obj` moved in."),
+    def("/atom/proc/Exit(atom/movable/O, atom/newloc)", "Whether `O` may move out."),
+    def("/atom/proc/Exited(atom/movable/Obj, atom/newloc)", "Called after `Obj` moved out."),
+    def("/atom/proc/Stat()", "Called to update the statpanel."),
+    def("/atom/proc/MouseDown(location, control, params)", "Called when a mouse button is pressed over the object."),
+    def("/atom/proc/MouseUp(location, control, params)", "Called when a mouse button is released over the object."),
+    def("/atom/proc/MouseDrag(over_object, src_location, over_location, src_control, over_control, params)", "Called while the object is dragged."),
+    def("/atom/proc/MouseEntered(location, control, params)", "Called when the mouse moves onto the object."),
+    def("/atom/proc/MouseExited(location, control, params)", "Called when the mouse leaves the object."),
+    def("/atom/proc/MouseMove(location, control, params)", "Called when the mouse moves over the object."),
+    def("/atom/proc/MouseWheel(delta_x, delta_y, location, control, params)", "Called when the mouse wheel turns over the object."),
+    // /atom/movable
+    def("/atom/movable/var/animate_movement = 1", "How moving between turfs is animated."),
+    def("/atom/movable/var/glide_size = 0", "How many pixels a move glides per tick."),
+    def("/atom/movable/var/step_size = 32", "How far a step moves in pixels."),
+    def("/atom/movable/var/screen_loc", "Where the object is drawn on the HUD."),
+    def("/atom/movable/var/locs", "The turfs the object covers."),
+    def("/atom/movable/var/step_x = 0", "The horizontal offset from the turf in pixel movement."),
+    def("/atom/movable/var/step_y = 0", "The vertical offset from the turf in pixel movement."),
+    def("/atom/movable/var/bound_x = 0", "The horizontal offset of the bounding box."),
+    def("/atom/movable/var/bound_y = 0", "The vertical offset of the bounding box."),
+    def("/atom/movable/var/bound_width = 32", "The width of the bounding box."),
+    def("/atom/movable/var/bound_height = 32", "The height of the bounding box."),
+    def("/atom/movable/proc/Move(NewLoc, Dir = 0, step_x = 0, step_y = 0)",
+        "Moves the object, returns true on success."),
+    def("/atom/movable/proc/Bump(atom/Obstacle)", "Called when a move is blocked by `Obstacle`."),
+    def("/atom/movable/proc/Cross(atom/movable/O)", "Whether `O` may overlap the object."),
+    def("/atom/movable/proc/Crossed(atom/movable/O)", "Called after `O` started overlapping."),
+    def("/atom/movable/proc/Uncross(atom/movable/O)", "Whether `O` may stop overlapping."),
+    def("/atom/movable/proc/Uncrossed(atom/movable/O)", "Called after `O` stopped overlapping."),
+    // /mob
+    def("/mob/var/ckey", "The canonical key of the player controlling the mob."),
+    def("/mob/var/key", "The key of the player controlling the mob."),
+    def("/mob/var/client", "The client controlling the mob."),
+    def("/mob/var/see_invisible = 0", "The highest `invisibility` the mob can see."),
+    def("/mob/var/sight = 0", "Flags changing what the mob can see."),
+    def("/mob/var/see_in_dark = 2", "How far the mob can see in the dark."),
+    def("/mob/var/group", "The mobs grouped with this one."),
+    def("/mob/var/see_infrared = 0", "Whether the mob sees `infra_luminosity`."),
+    def("/mob/proc/Login()", "Called when a player takes control of the mob."),
+    def("/mob/proc/Logout()", "Called when the player leaves the mob."),
+    // /client
+    def("/client/var/ckey", "The canonical key of the player."),
+    def("/client/var/key", "The key of the player."),
+    def("/client/var/mob", "The mob the player controls."),
+    def("/client/var/eye", "What the player's view is centered on."),
+    def("/client/var/view", "The size of the map view."),
+    def("/client/var/screen", "Objects drawn on the player's HUD."),
+    def("/client/var/images", "Images only this player can see."),
+    def("/client/var/address", "The IP address of the player."),
+    def("/client/var/computer_id", "An id for the player's computer."),
+    def("/client/var/byond_version", "The BYOND version of the player's client."),
+    def("/client/var/byond_build", "The BYOND build of the player's client.").since(512, 0),
+    def("/client/var/fps = 0", "The client side frame rate."),
+    def("/client/var/verbs", "The verbs the player can use."),
+    def("/client/var/statpanel", "The statpanel currently shown."),
+    def("/client/var/holder", "Commonly used by games for admin data."),
+    def("/client/var/authenticate", "Whether the player's key is checked with the hub."),
+    def("/client/var/bounds", "The map area the player sees, in pixels."),
+    def("/client/var/color", "A color or color matrix applied to the whole view."),
+    def("/client/var/command_text", "Text put in front of commands the player types."),
+    def("/client/var/connection", "How the player is connected, such as `seeker` or `web`."),
+    def("/client/var/control_freak = 0", "Which client features the game disables."),
+    def("/client/var/default_verb_category", "The statpanel verbs without a category go to."),
+    def("/client/var/dir", "Which way is up on the player's map."),
+    def("/client/var/edge_limit", "How far the view can scroll past the map edge."),
+    def("/client/var/gender", "The gender of the player."),
+    def("/client/var/inactivity", "How long the player has been idle, in ticks."),
+    def("/client/var/lazy_eye", "How far the mob may move before the view follows."),
+    def("/client/var/mouse_pointer_icon", "The mouse cursor over the map."),
+    def("/client/var/perspective = 0", "Whether the view follows the mob or the eye."),
+    def("/client/var/pixel_x = 0", "The horizontal offset of the view in pixels."),
+    def("/client/var/pixel_y = 0", "The vertical offset of the view in pixels."),
+    def("/client/var/pixel_w = 0", "The horizontal offset of the view that ignores the map format."),
+    def("/client/var/pixel_z = 0", "The vertical offset of the view that ignores the map format."),
+    def("/client/var/preload_rsc", "Whether resources are sent before the player joins."),
+    def("/client/var/script", "Skin script sent to the player."),
+    def("/client/var/show_map = 1", "Whether the map is shown."),
+    def("/client/var/show_popup_menus = 1", "Whether right clicking opens a menu."),
+    def("/client/var/show_verb_panel = 1", "Whether the verb statpanel is shown."),
+    def("/client/var/statobj", "The object shown in the statpanel."),
+    def("/client/var/tick_lag = 0", "The client side tick length."),
+    def("/client/var/timezone", "The offset of the player's time zone in hours."),
+    def("/client/var/virtual_eye", "The object the view is centered on."),
+    def("/client/proc/New(TopicData)", "Called when the player connects."),
+    def("/client/proc/Del()", "Called when the player disconnects."),
+    def("/client/proc/Topic(href, href_list, hsrc)", "Called when the player clicks a link."),
+    def("/client/proc/Command(command)", "Called for commands that are not verbs."),
+    def("/client/proc/Click(object, location, control, params)", "Called on every click."),
+    def("/client/proc/Move(loc, dir)", "Called when the player moves their mob."),
+    def("/client/proc/Stat()", "Called to update the statpanel."),
+    def("/client/proc/MeasureText(text, style, width = 0)", "Measures maptext.").since(513, 0),
+    def("/client/proc/SoundQuery()", "Lists the sounds playing for the player.").since(513, 0),
+    def("/client/proc/AllowUpload(filename, filelength)", "Whether the player may upload a file."),
+    def("/client/proc/CheckPassport(passport_identifier)", "Whether the player has a passport."),
+    def("/client/proc/DblClick(object, location, control, params)", "Called on every double click."),
+    def("/client/proc/Export(file)", "Saves a file on the player's computer."),
+    def("/client/proc/Import(Query)", "Reads a file saved on the player's computer."),
+    def("/client/proc/IsByondMember()", "Whether the player is a BYOND member."),
+    def("/client/proc/MouseDown(object, location, control, params)", "Called on every mouse button press."),
+    def("/client/proc/MouseUp(object, location, control, params)", "Called on every mouse button release."),
+    def("/client/proc/MouseDrag(src_object, over_object, src_location, over_location, src_control, over_control, params)", "Called while something is dragged."),
+    def("/client/proc/MouseDrop(src_object, over_object, src_location, over_location, src_control, over_control, params)", "Called when something is dropped."),
+    def("/client/proc/MouseEntered(object, location, control, params)", "Called when the mouse moves onto something."),
+    def("/client/proc/MouseExited(object, location, control, params)", "Called when the mouse leaves something."),
+    def("/client/proc/MouseMove(object, location, control, params)", "Called when the mouse moves."),
+    def("/client/proc/MouseWheel(object, delta_x, delta_y, location, control, params)", "Called when the mouse wheel turns."),
+    def("/client/proc/North()", "Called when the player moves north."),
+    def("/client/proc/South()", "Called when the player moves south."),
+    def("/client/proc/East()", "Called when the player moves east."),
+    def("/client/proc/West()", "Called when the player moves west."),
+    def("/client/proc/Northeast()", "Called when the player moves northeast."),
+    def("/client/proc/Northwest()", "Called when the player moves northwest."),
+    def("/client/proc/Southeast()", "Called when the player moves southeast."),
+    def("/client/proc/Southwest()", "Called when the player moves southwest."),
+    def("/client/proc/Center()", "Called when the player presses the center key."),
+    def("/client/proc/RenderIcon(object)", "Renders an object to an icon.").since(515, 0),
+    // /world
+    def("/world/var/name", "The name of the game."),
+    def("/world/var/time", "The time since the world started in ticks."),
+    def("/world/var/timeofday", "The time of day in tenths of a second."),
+    def("/world/var/realtime", "The time since 2000 in tenths of a second."),
+    def("/world/var/tick_lag = 1", "The length of a tick in tenths of a second."),
+    def("/world/var/fps = 10", "Ticks per second."),
+    def("/world/var/tick_usage", "The percentage of the current tick used so far."),
+    def("/world/var/cpu", "The percentage of the tick the last tick used."),
+    def("/world/var/maxx", "The width of the map."),
+    def("/world/var/maxy", "The height of the map."),
+    def("/world/var/maxz", "The number of z levels."),
+    def("/world/var/log", "Where `world.log <<` writes to."),
+    def("/world/var/port", "The port the world is hosted on."),
+    def("/world/var/contents", "Every area, turf, obj and mob."),
+    def("/world/var/system_type", "`UNIX` or `MS_WINDOWS`."),
+    def("/world/var/byond_version", "The BYOND version hosting the world."),
+    def("/world/var/byond_build", "The BYOND build hosting the world.").since(512, 0),
+    def("/world/var/address", "The IP address of the server."),
+    def("/world/var/area", "The default area type."),
+    def("/world/var/turf", "The default turf type."),
+    def("/world/var/mob", "The mob type new players get."),
+    def("/world/var/view = 5", "The default view size."),
+    def("/world/var/cache_lifespan = 30", "How many days unused resources are cached."),
+    def("/world/var/executor", "The program running `shell()` commands."),
+    def("/world/var/game_state", "Status data sent to the hub."),
+    def("/world/var/host", "The key of the player hosting the world."),
+    def("/world/var/hub", "The hub entry of the game."),
+    def("/world/var/hub_password", "The password of the hub entry."),
+    def("/world/var/icon_size = 32", "The size of map tiles in pixels."),
+    def("/world/var/internet_address", "The public IP address of the server."),
+    def("/world/var/loop_checks = 1", "Whether infinite loops are detected."),
+    def("/world/var/map_cpu", "The percentage of the tick used sending maps."),
+    def("/world/var/map_format = 0", "How the map is drawn."),
+    def("/world/var/movement_mode = 0", "Whether movement is by tile or by pixel."),
+    def("/world/var/params", "Parameters the world was started with."),
+    def("/world/var/process", "The process id of the server."),
+    def("/world/var/reachable", "Whether players can connect to the world."),
+    def("/world/var/sleep_offline = 0", "Whether the world pauses without players."),
+    def("/world/var/status", "The status shown on the hub."),
+    def("/world/var/timezone", "The offset of the server's time zone in hours."),
+    def("/world/var/url", "The address players connect to."),
+    def("/world/var/version = 0", "The version of the game on the hub."),
+    def("/world/var/visibility = 1", "Whether the world is shown on the hub."),
+    def("/world/proc/New()", "Called when the world starts."),
+    def("/world/proc/Del()", "Called when the world shuts down."),
+    def("/world/proc/Reboot(reason)", "Restarts the world."),
+    def("/world/proc/Topic(T, Addr, Master, Keys)", "Called for a world topic message."),
+    def("/world/proc/Export(Addr, File, Persist, Clients)", "Sends a topic or http request."),
+    def("/world/proc/GetConfig(config_set, param)", "Reads a configuration value."),
+    def("/world/proc/SetConfig(config_set, param, value)", "Writes a configuration value."),
+    def("/world/proc/Profile(command, type, format)", "Controls the profiler."),
+    def("/world/proc/AddCredits(player, credits, note)", "Gives a player hub credits."),
+    def("/world/proc/ClearMedal(medal, player, hub, hub_password)", "Takes a medal away from a player."),
+    def("/world/proc/Error(exception)", "Called for runtime errors."),
+    def("/world/proc/GetCredits(player)", "The hub credits of a player."),
+    def("/world/proc/GetMedal(medal, player, hub, hub_password)", "Whether a player has a medal."),
+    def("/world/proc/GetScores(key, fields, hub, hub_password)", "Reads scores from the hub."),
+    def("/world/proc/IsBanned(key, address, computer_id, type)", "Whether a player may connect."),
+    def("/world/proc/IsSubscribed(player, type)", "Whether a player is subscribed to the game."),
+    def("/world/proc/OpenPort(port)", "Starts hosting on a port."),
+    def("/world/proc/PayCredits(player, credits, note)", "Takes hub credits from a player."),
+    def("/world/proc/Repop()", "Recreates the objects of the map."),
+    def("/world/proc/SetMedal(medal, player, hub, hub_password)", "Gives a player a medal."),
+    def("/world/proc/SetScores(key, fields, hub, hub_password)", "Writes scores to the hub."),
+    // /list
+    def("/list/var/len", "The number of entries in the list."),
+    def("/list/proc/Add(Item1, ...)", "Appends the items."),
+    def("/list/proc/Remove(Item1, ...)", "Removes the last occurrence of each item."),
+    def("/list/proc/Find(Elem, Start = 1, End = 0)", "The index of `Elem`, `0` if missing."),
+    def("/list/proc/Copy(Start = 1, End = 0)", "A new list with the entries in the range."),
+    def("/list/proc/Cut(Start = 1, End = 0)", "Removes the entries in the range."),
+    def("/list/proc/Insert(Index, Item1, ...)", "Inserts the items at `Index`."),
+    def("/list/proc/Join(Glue, Start = 1, End = 0)", "The entries joined into text."),
+    def("/list/proc/Swap(Index1, Index2)", "Swaps two entries."),
+    def("/list/proc/Splice(Start = 1, End = 0, Item1, ...)", "Replaces the range with the items.")
+        .since(512, 0),
+    def("/list/proc/RemoveAll(Item1, ...)", "Removes every occurrence of each item.")
+        .since(515, 0),
+    // other builtin types
+    def("/image/var/icon", "The icon file the image is drawn from."),
+    def("/image/var/icon_state", "The state in `icon` the image is drawn with."),
+    def("/image/var/loc", "The object the image is attached to."),
+    def("/image/var/dir", "The direction the image faces."),
+    def("/image/var/layer", "The drawing order of the image."),
+    def("/image/var/plane", "The drawing plane of the image."),
+    def("/image/var/pixel_x = 0", "The horizontal offset in pixels."),
+    def("/image/var/pixel_y = 0", "The vertical offset in pixels."),
+    def("/image/var/alpha = 255", "The opacity, `0` is invisible."),
+    def("/image/var/color", "A color or color matrix multiplied with the icon."),
+    def("/image/var/appearance_flags = 0", "Flags changing how the image is drawn."),
+    def("/image/var/appearance", "The image's appearance as a single value."),
+    def("/image/var/overlays", "Appearances drawn on top of the image."),
+    def("/image/var/underlays", "Appearances drawn below the image."),
+    def("/image/var/transform", "A matrix transforming how the image is drawn."),
+    def("/image/var/override = 0", "Whether the image replaces the object it is attached to."),
+    def("/image/var/maptext", "Text drawn over the image."),
+    def("/image/var/name", "The name of the image."),
+    def("/image/var/filters", "Graphical filters applied to the image.").since(512, 0),
+    def("/icon/proc/Blend(icon, function, x, y)", "Combines another icon or a color with this one."),
+    def("/icon/proc/Crop(x1, y1, x2, y2)", "Cuts the icon down to a rectangle."),
+    def("/icon/proc/DrawBox(rgb, x1, y1, x2, y2)", "Fills a rectangle with a color."),
+    def("/icon/proc/Flip(dir)", "Mirrors the icon."),
+    def("/icon/proc/GetPixel(x, y, icon_state, dir, frame, moving)", "The color of a pixel."),
+    def("/icon/proc/Height()", "The height of the icon in pixels."),
+    def("/icon/proc/IconStates(mode = 0)", "The states of the icon."),
+    def("/icon/proc/Insert(new_icon, icon_state, dir, frame, moving, delay)", "Adds an icon as a state or frame."),
+    def("/icon/proc/MapColors(...)", "Transforms every color of the icon."),
+    def("/icon/proc/Scale(width, height)", "Resizes the icon."),
+    def("/icon/proc/SetIntensity(r, g, b)", "Brightens or darkens the icon."),
+    def("/icon/proc/Shift(dir, offset, wrap = 0)", "Moves the pixels of the icon."),
+    def("/icon/proc/SwapColor(old_rgb, new_rgb)", "Replaces a color."),
+    def("/icon/proc/Turn(angle)", "Rotates the icon."),
+    def("/icon/proc/Width()", "The width of the icon in pixels."),
+    def("/sound/var/file", "The sound file."),
+    def("/sound/var/repeat = 0", "Whether the sound loops."),
+    def("/sound/var/wait = 0", "Whether the sound waits for the channel to be free."),
+    def("/sound/var/channel = 0", "The channel the sound plays on."),
+    def("/sound/var/volume = 100", "The volume in percent."),
+    def("/sound/var/frequency = 0", "Changes the playback speed."),
+    def("/sound/var/pan = 0", "Moves the sound left or right."),
+    def("/sound/var/priority = 0", "Which sounds are dropped first when too many play."),
+    def("/sound/var/status = 0", "Flags for updating or pausing the sound."),
+    def("/sound/var/x", "The horizontal position of a 3D sound."),
+    def("/sound/var/y", "The vertical position of a 3D sound."),
+    def("/sound/var/z", "The depth of a 3D sound."),
+    def("/sound/var/falloff = 1", "How quickly a 3D sound fades with distance."),
+    def("/sound/var/environment", "The reverb environment."),
+    def("/sound/var/echo", "Echo settings."),
+    def("/sound/var/len", "The length of the sound in seconds."),
+    def("/sound/var/offset", "Where playback starts in seconds."),
+    def("/sound/var/params", "Extra parameters of the sound."),
+    def("/matrix/var/a = 1", "The x scale."),
+    def("/matrix/var/b = 0", "The x shear."),
+    def("/matrix/var/c = 0", "The x translation."),
+    def("/matrix/var/d = 0", "The y shear."),
+    def("/matrix/var/e = 1", "The y scale."),
+    def("/matrix/var/f = 0", "The y translation."),
+    def("/matrix/proc/Add(Matrix2)", "Adds another matrix."),
+    def("/matrix/proc/Interpolate(Matrix2, t)", "A matrix between this one and another."),
+    def("/matrix/proc/Invert()", "Inverts the matrix."),
+    def("/matrix/proc/Multiply(Matrix2)", "Multiplies with another matrix or a number."),
+    def("/matrix/proc/Scale(x, y)", "Scales the matrix."),
+    def("/matrix/proc/Subtract(Matrix2)", "Subtracts another matrix."),
+    def("/matrix/proc/Translate(x, y)", "Moves the matrix."),
+    def("/matrix/proc/Turn(angle)", "Rotates the matrix."),
+    def("/regex/var/name", "The pattern."),
+    def("/regex/var/flags", "The flags of the pattern."),
+    def("/regex/var/text", "The text of the last search."),
+    def("/regex/var/match", "The text of the last match."),
+    def("/regex/var/group", "The capture groups of the last match."),
+    def("/regex/var/index", "The position of the last match."),
+    def("/regex/var/next", "Where the next search starts."),
+    def("/regex/proc/Find(haystack, Start, End)", "Searches for the pattern."),
+    def("/regex/proc/Replace(haystack, replacement, Start, End)", "Replaces matches of the pattern."),
+    def("/savefile/var/cd", "The current directory."),
+    def("/savefile/var/dir", "The entries of the current directory."),
+    def("/savefile/var/eof", "Whether the current buffer is read to the end."),
+    def("/savefile/var/name", "The file name."),
+    def("/savefile/proc/ExportText(path, file)", "Writes the savefile as text."),
+    def("/savefile/proc/Flush()", "Writes pending changes to disk."),
+    def("/savefile/proc/ImportText(path, source)", "Reads savefile text."),
+    def("/savefile/proc/Lock(timeout)", "Locks the file for this world."),
+    def("/savefile/proc/Unlock()", "Releases the lock."),
+    def("/database/proc/Close()", "Closes the database."),
+    def("/database/proc/Error()", "The error code of the last operation."),
+    def("/database/proc/ErrorMsg()", "The error message of the last operation."),
+    def("/database/proc/Open(filename)", "Opens a database file."),
+    def("/exception/var/name", "The error message."),
+    def("/exception/var/desc", "Details of the error."),
+    def("/exception/var/file", "The file the error happened in."),
+    def("/exception/var/line", "The line the error happened on."),
+    def("/callee/var/proc", "The proc being run."),
+    def("/callee/var/args", "The arguments of the call."),
+    def("/callee/var/src", "The `src` of the call."),
+    def("/callee/var/usr", "The `usr` of the call."),
+    def("/callee/var/caller", "The call that made this one."),
+    def("/callee/var/name", "The name of the proc."),
+    def("/callee/var/desc", "The description of the proc."),
+    def("/callee/var/category", "The category of a verb."),
+    def("/callee/var/file", "The file being run."),
+    def("/callee/var/line", "The line being run."),
+    def("/generator/proc/Rand()", "A random value from the generator."),
+    // global procs
+    def("/proc/sleep(Delay)", "Pauses the proc for `Delay` tenths of a second."),
+    def("/proc/locate(Type)", "Finds an object by type, tag, reference or coordinates."),
+    def("/proc/istype(Val, Type)", "Whether `Val` is an instance of `Type`."),
+    def("/proc/ispath(Val, Type)", "Whether `Val` is a type path, below `Type` if given."),
+    def("/proc/isnum(Val)", "Whether `Val` is a number."),
+    def("/proc/istext(Val)", "Whether `Val` is text."),
+    def("/proc/islist(Val)", "Whether `Val` is a list."),
+    def("/proc/isnull(Val)", "Whether `Val` is null."),
+    def("/proc/isloc(Loc1, ...)", "Whether every argument is an atom that can hold others."),
+    def("/proc/isarea(Loc1, ...)", "Whether every argument is an area."),
+    def("/proc/isturf(Loc1, ...)", "Whether every argument is a turf."),
+    def("/proc/isobj(Loc1, ...)", "Whether every argument is an obj."),
+    def("/proc/ismob(Loc1, ...)", "Whether every argument is a mob."),
+    def("/proc/ismovable(Loc1, ...)", "Whether every argument is movable.").since(513, 0),
+    def("/proc/isicon(Icon)", "Whether `Icon` is an icon."),
+    def("/proc/isfile(File)", "Whether `File` is a file."),
+    def("/proc/json_encode(Value, flags)", "Converts a value to JSON text."),
+    def("/proc/json_decode(JSON, flags)", "Converts JSON text to a value."),
+    def("/proc/rgb(R, G, B, A, space)", "Builds a color from its components."),
+    def("/proc/rgb2num(color, space)", "Splits a color into its components.").since(514, 0),
+    def("/proc/gradient(Item1, Item2, ..., index)", "A color along a gradient.").since(514, 0),
+    def("/proc/text2num(T, radix = 10)", "Parses a number, null if `T` is not one."),
+    def("/proc/num2text(N, Digits, Radix)", "Formats a number as text."),
+    def("/proc/text2path(T)", "Parses a type path, null if it does not exist."),
+    def("/proc/length(Elem)", "The length of text or a list."),
+    def("/proc/copytext(T, Start = 1, End = 0)", "The text in the range."),
+    def("/proc/findtext(Haystack, Needle, Start = 1, End = 0)",
+        "The position of `Needle`, case insensitive."),
+    def("/proc/findtextEx(Haystack, Needle, Start = 1, End = 0)",
+        "The position of `Needle`, case sensitive."),
+    def("/proc/replacetext(Haystack, Needle, Replacement, Start = 1, End = 0)",
+        "Replaces every `Needle`, case insensitive."),
+    def("/proc/splittext(Text, Delimiter, Start = 1, End = 0, include_delimiters = 0)",
+        "Splits text into a list.").since(510, 0),
+    def("/proc/jointext(List, Glue, Start = 1, End = 0)", "Joins a list into text.")
+        .since(510, 0),
+    def("/proc/lowertext(T)", "The text in lower case."),
+    def("/proc/uppertext(T)", "The text in upper case."),
+    def("/proc/trimtext(Text)", "The text without leading and trailing whitespace.")
+        .since(515, 0),
+    def("/proc/ckey(Key)", "The canonical form of a key."),
+    def("/proc/html_encode(PlainText)", "Escapes text for use in HTML."),
+    def("/proc/url_encode(PlainText, format = 0)", "Escapes text for use in a URL."),
+    def("/proc/list2params(List)", "Encodes a list as URL parameters."),
+    def("/proc/params2list(Params)", "Decodes URL parameters into a list."),
+    def("/proc/abs(A)", "The absolute value."),
+    def("/proc/min(A, B, ...)", "The smallest argument or list entry."),
+    def("/proc/max(A, B, ...)", "The largest argument or list entry."),
+    def("/proc/round(A, B)", "Rounds `A` down, or to the nearest multiple of `B`."),
+    def("/proc/clamp(Number, Low, High)", "`Number` limited to the range.").since(513, 0),
+    def("/proc/sqrt(A)", "The square root."),
+    def("/proc/rand(L = 0, H = 1)", "A random number in the range."),
+    def("/proc/prob(P)", "True with a chance of `P` percent."),
+    def("/proc/pick(Val1, ...)", "A random argument or list entry."),
+    def("/proc/get_dist(Loc1, Loc2)", "The distance between two atoms in tiles."),
+    def("/proc/get_dir(Loc1, Loc2)", "The direction from one atom to another."),
+    def("/proc/get_step(Ref, Dir)", "The turf one step from `Ref` in `Dir`."),
+    def("/proc/step(Ref, Dir, Speed = 0)", "Moves `Ref` one step in `Dir`."),
+    def("/proc/walk(Ref, Dir, Lag = 0, Speed = 0)", "Keeps moving `Ref` in `Dir`."),
+    def("/proc/view(Dist = 5, Center = usr)", "The objects visible from `Center`."),
+    def("/proc/range(Dist, Center = usr)", "The objects within `Dist` of `Center`."),
+    def("/proc/typesof(Type1, ...)", "The types and every type below them."),
+    def("/proc/time2text(timestamp, format, timezone)", "Formats a time as text."),
+    def("/proc/md5(T)", "The MD5 hash of text or a file."),
+    def("/proc/sha1(T)", "The SHA1 hash of text or a file.").since(513, 0),
+    def("/proc/file(Path)", "A file reference."),
+    def("/proc/fdel(File)", "Deletes a file."),
+    def("/proc/fexists(File)", "Whether a file exists."),
+    def("/proc/sound(file, repeat = 0, wait, channel, volume)", "A sound to play."),
+    def("/proc/image(icon, loc, icon_state, layer, dir)", "An image to show."),
+    def("/proc/winset(player, control_id, params)", "Changes a skin control."),
+    def("/proc/winget(player, control_id, params)", "Reads a skin control."),
+    def("/proc/browse(Body, Options)", "Shows HTML in a browser window."),
+    def("/proc/alert(Usr = usr, Message, Title, Button1 = \"Ok\", Button2, Button3)",
+        "Shows a message box and waits for a button."),
+    def("/proc/CRASH(msg)", "Stops the proc with a runtime error."),
+    def("/proc/ref(Object)", "The reference text of an object.").since(515, 0),
+    def("/proc/nameof(Var)", "The name of a var or proc as text.").since(515, 0),
+    def("/proc/load_ext(LibName, FuncName)", "Loads a native library function.").since(515, 0),
+    def("/proc/initial(Var)", "The value a var had when the object was created."),
+    def("/proc/issaved(Var)", "Whether a var is saved to savefiles."),
+    def("/proc/arglist(List)", "Passes the entries of a list as the arguments of a call."),
+    def("/proc/text(FormatText, ...)", "Formats text with embedded arguments."),
+    def("/proc/isnan(n)", "Whether the number is not a number."),
+    def("/proc/isinf(n)", "Whether the number is infinite."),
+    def("/proc/ispointer(Value)", "Whether the value is a pointer.").since(515, 0),
+    def("/proc/hascall(Object, ProcName)", "Whether the object has a proc with the name."),
+    def("/proc/trunc(A)", "The number without its fraction.").since(515, 0),
+    def("/proc/fract(A)", "The fraction of the number.").since(515, 0),
+    def("/proc/sign(A)", "`1`, `-1` or `0` depending on the sign.").since(515, 0),
+    def("/proc/sin(X)", "The sine of an angle in degrees."),
+    def("/proc/cos(X)", "The cosine of an angle in degrees."),
+    def("/proc/tan(X)", "The tangent of an angle in degrees."),
+    def("/proc/arcsin(X)", "The inverse sine in degrees."),
+    def("/proc/arccos(X)", "The inverse cosine in degrees."),
+    def("/proc/arctan(A, B)", "The inverse tangent in degrees."),
+    def("/proc/log(X, Y)", "The logarithm of `Y` to base `X`, or the natural logarithm of `X`."),
+    def("/proc/roll(ndice, sides)", "Rolls dice, `roll(\"3d6\")` or `roll(3, 6)`."),
+    def("/proc/rand_seed(Seed)", "Seeds the random number generator."),
+    def("/proc/turn(Dir, Angle)", "Rotates a direction, matrix or icon."),
+    def("/proc/ascii2text(N)", "The character with the code."),
+    def("/proc/text2ascii(T, pos = 1)", "The character code at a position."),
+    def("/proc/file2text(File)", "The contents of a file."),
+    def("/proc/text2file(Text, File)", "Appends text to a file."),
+    def("/proc/flist(Path)", "The files in a directory."),
+    def("/proc/fcopy(Src, Dst)", "Copies a file."),
+    def("/proc/ftime(File, IsCreationTime = 0)", "When a file was changed or created."),
+    def("/proc/shell(Command)", "Runs a shell command."),
+    def("/proc/sorttext(T1, T2, ...)", "Compares text alphabetically, case insensitive."),
+    def("/proc/sorttextEx(T1, T2, ...)", "Compares text alphabetically, case sensitive."),
+    def("/proc/ckeyEx(Text)", "The key without invalid characters, keeping case."),
+    def("/proc/html_decode(HtmlText)", "Reverses `html_encode()`."),
+    def("/proc/url_decode(UrlText)", "Reverses `url_encode()`."),
+    def("/proc/length_char(E)", "The length of text in characters."),
+    def("/proc/copytext_char(T, Start = 1, End = 0)", "The text in the range, counted in characters."),
+    def("/proc/findtext_char(Haystack, Needle, Start = 1, End = 0)", "`findtext()` counted in characters."),
+    def("/proc/findlasttext(Haystack, Needle, Start = 0, End = 1)", "The last position of `Needle`, case insensitive."),
+    def("/proc/findlasttextEx(Haystack, Needle, Start = 0, End = 1)", "The last position of `Needle`, case sensitive."),
+    def("/proc/replacetextEx(Haystack, Needle, Replacement, Start = 1, End = 0)", "Replaces every `Needle`, case sensitive."),
+    def("/proc/spantext(Haystack, Needles, Start = 1)", "How many characters from `Needles` the text starts with."),
+    def("/proc/nonspantext(Haystack, Needles, Start = 1)", "How many characters not in `Needles` the text starts with."),
+    def("/proc/splicetext(Text, Start = 1, End = 0, Insert)", "Replaces a range of the text.").since(515, 0),
+    def("/proc/lentext(T)", "The length of text, use `length()` instead."),
+    def("/proc/icon(icon, icon_state, dir, frame, moving)", "A copy of an icon to edit."),
+    def("/proc/icon_states(Icon, mode = 0)", "The states of an icon."),
+    def("/proc/flick(Icon, Object)", "Plays an animation once."),
+    def("/proc/animate(Object, time, loop, easing, flags, delay, tag, ...)", "Animates vars of an object."),
+    def("/proc/matrix(...)", "A transformation matrix."),
+    def("/proc/filter(type, ...)", "A graphical filter.").since(512, 0),
+    def("/proc/generator(type, A, B, rand)", "A random value generator.").since(514, 0),
+    def("/proc/regex(pattern, flags)", "A regular expression."),
+    def("/proc/REGEX_QUOTE(text)", "Escapes text for use in a pattern."),
+    def("/proc/REGEX_QUOTE_REPLACEMENT(text)", "Escapes text for use as a replacement."),
+    def("/proc/orange(Dist = 5, Center = usr)", "`range()` without the center."),
+    def("/proc/oview(Dist = 5, Center = usr)", "`view()` without the center."),
+    def("/proc/viewers(Depth = 5, Center = usr)", "The mobs that can see `Center`."),
+    def("/proc/oviewers(Depth = 5, Center = usr)", "`viewers()` without the center."),
+    def("/proc/hearers(Depth = 5, Center = usr)", "The mobs that can hear `Center`."),
+    def("/proc/ohearers(Depth = 5, Center = usr)", "`hearers()` without the center."),
+    def("/proc/block(Start, End, ...)", "The turfs in a box."),
+    def("/proc/bounds(Ref, ...)", "The objects overlapping a box in pixel movement."),
+    def("/proc/obounds(Ref, ...)", "`bounds()` without `Ref`."),
+    def("/proc/bounds_dist(Ref, Target)", "The distance between bounding boxes in pixels."),
+    def("/proc/get_step_to(Ref, Trg, Min = 0)", "A step along a path to `Trg`."),
+    def("/proc/get_step_towards(Ref, Trg)", "A step towards `Trg`."),
+    def("/proc/get_step_away(Ref, Trg, Max = 5)", "A step away from `Trg`."),
+    def("/proc/get_step_rand(Ref)", "A random step."),
+    def("/proc/step_to(Ref, Trg, Min = 0, Speed = 0)", "Steps along a path to `Trg`."),
+    def("/proc/step_towards(Ref, Trg, Speed = 0)", "Steps towards `Trg`."),
+    def("/proc/step_away(Ref, Trg, Max = 5, Speed = 0)", "Steps away from `Trg`."),
+    def("/proc/step_rand(Ref, Speed = 0)", "Steps in a random direction."),
+    def("/proc/walk_to(Ref, Trg, Min = 0, Lag = 0, Speed = 0)", "Keeps moving `Ref` to `Trg`."),
+    def("/proc/walk_towards(Ref, Trg, Lag = 0, Speed = 0)", "Keeps moving `Ref` towards `Trg`."),
+    def("/proc/walk_away(Ref, Trg, Max = 5, Lag = 0, Speed = 0)", "Keeps moving `Ref` away from `Trg`."),
+    def("/proc/walk_rand(Ref, Lag = 0, Speed = 0)", "Keeps moving `Ref` randomly."),
+    def("/proc/missile(Type, Start, End)", "Animates a projectile."),
+    def("/proc/output(msg, control)", "Sends output to a skin control."),
+    def("/proc/link(url)", "Opens a URL for the player."),
+    def("/proc/run(File)", "Opens a file for the player."),
+    def("/proc/ftp(File, Name)", "Sends a file to the player."),
+    def("/proc/stat(Name, Value)", "Adds a line to the statpanel."),
+    def("/proc/statpanel(Panel, Name, Value)", "Switches to or adds to a statpanel."),
+    def("/proc/browse_rsc(File, FileName)", "Sends a resource to the player's browser cache."),
+    def("/proc/winexists(player, control_id)", "The type of a skin control."),
+    def("/proc/winclone(player, window_name, clone_name)", "Copies a skin control."),
+    def("/proc/winshow(player, window, show = 1)", "Shows or hides a window."),
+    def("/proc/ASSERT(expression)", "Stops the proc with an error if the expression is false."),
+    def("/proc/EXCEPTION(value)", "An exception to throw."),
+    def("/proc/refcount(Object)", "The number of references to an object.").since(515, 0),
+];
+
+impl ObjectTree {
+    /// Parses the builtin table and merges it into the tree, linking each var and proc to its
+    /// table entry.
+    pub(super) fn add_builtins(&mut self) {
+        let ast = Self::parse_builtins().expect("the builtin table is valid DM");
+        self.add_ast(&ast);
+        for declaration in ast.iter_declarations() {
+            match declaration {
+                Declaration::Var(var) => {
+                    let builtin = builtin_at(&var.span);
+                    let node = self.node_mut(&var.owner);
+                    let index = node.var_index[&var.name];
+                    node.vars[index].builtin = Some(builtin);
+                }
+                Declaration::Proc(proc) => {
+                    let builtin = builtin_at(&proc.span);
+                    let node = self.node_mut(&proc.owner);
+                    let index = node.proc_index[&proc.name];
+                    node.procs[index].builtin = Some(builtin);
+                }
+                Declaration::Type(_) | Declaration::ParentType(_) => {}
+            }
+        }
+    }
+
+    /// Every builtin is on its own line, so a declaration's line is its index in the table.
+    fn parse_builtins() -> Result<DmAst, ParseError> {
+        let lines: Vec<String> = BUILTINS
+            .iter()
+            .map(|builtin| builtin.declaration.to_string())
+            .collect();
+        let path = Path::new(BUILTINS_PATH);
+        let lexemes = Lexer::new(DmTokenizer::new(path, &lines).map(Ok)).lex()?;
+        SyntaxParser::new(lexemes).parse_file(path)
+    }
+}
+
+fn builtin_at(span: &Span) -> &'static BuiltinDefinition {
+    let line = span.line().expect("builtin spans have a source");
+    &BUILTINS[line - 1]
+}
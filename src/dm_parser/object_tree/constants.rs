@@ -0,0 +1,413 @@
+use std::cmp::Ordering;
+
+use crate::{
+    dm_parser::{
+        ast::expression::{Argument, BinaryOp, Expression, ExpressionKind, StringSegment, UnaryOp},
+        dm_types::DmValue,
+        parsing::type_path::DmTypePath,
+    },
+    tokens::span::Span,
+    util::symbol::Symbol,
+};
+
+use super::{resolve::ResolvedPath, ObjectTree};
+
+/// BYOND's bitwise operators work on 24 bit integers.
+const BIT_MASK: i32 = 0xFF_FFFF;
+
+/// The initial value of a var on a type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InitialValue {
+    Constant(DmValue),
+    /// The initializer runs code when the object is created, or cannot be evaluated at all.
+    NonConstant(NotConstant),
+}
+
+/// Why an expression is not a compile time constant.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotConstant {
+    pub span: Span,
+    pub reason: String,
+}
+
+impl ObjectTree {
+    /// The initial value of the var `name` on `path`, assigned there or inherited.
+    /// A var without a value starts as `null`. `None` if no type up the chain has the var.
+    pub fn initial_value(&self, path: &DmTypePath, name: Symbol) -> Option<InitialValue> {
+        ConstEvaluator::new(self).initial_value(path, name)
+    }
+
+    /// Every var declared or assigned on `path` or its parents with its initial value, in the
+    /// order they first appear going up from `path`.
+    pub fn initial_values(&self, path: &DmTypePath) -> Vec<(Symbol, InitialValue)> {
+        let mut names = vec![];
+        for node in self.ancestors(path) {
+            for var in node.vars() {
+                if !names.contains(&var.name()) {
+                    names.push(var.name());
+                }
+            }
+        }
+        let mut evaluator = ConstEvaluator::new(self);
+        names
+            .into_iter()
+            .filter_map(|name| Some((name, evaluator.initial_value(path, name)?)))
+            .collect()
+    }
+
+    /// Evaluates `expression` as if it was written on the type `context`. `#define`d constants
+    /// are already replaced by the preprocessor, `const` vars, `initial()` and type paths are
+    /// looked up in the tree.
+    pub fn evaluate_constant(
+        &self,
+        expression: &Expression,
+        context: &DmTypePath,
+    ) -> Result<DmValue, NotConstant> {
+        ConstEvaluator::new(self).evaluate(expression, context)
+    }
+}
+
+struct ConstEvaluator<'a> {
+    tree: &'a ObjectTree,
+    /// The vars being evaluated, to stop on vars whose values refer to each other.
+    evaluating: Vec<(DmTypePath, Symbol)>,
+}
+
+fn not_constant<T>(span: &Span, reason: impl Into<String>) -> Result<T, NotConstant> {
+    Err(NotConstant {
+        span: span.clone(),
+        reason: reason.into(),
+    })
+}
+
+impl<'a> ConstEvaluator<'a> {
+    fn new(tree: &'a ObjectTree) -> Self {
+        Self {
+            tree,
+            evaluating: vec![],
+        }
+    }
+
+    fn initial_value(&mut self, path: &DmTypePath, name: Symbol) -> Option<InitialValue> {
+        let mut exists = false;
+        let mut assigned = None;
+        for node in self.tree.ancestors(path) {
+            let Some(var) = node.var(name) else {
+                continue;
+            };
+            exists = true;
+            if let Some(value) = var.value() {
+                assigned = Some((node.path().clone(), value));
+                break;
+            }
+        }
+        // global vars live on the root, which is not an ancestor of anything
+        if !exists {
+            let var = self.tree.root().var(name)?;
+            assigned = var.value().map(|value| (DmTypePath::default(), value));
+        }
+
+        let Some((owner, value)) = assigned else {
+            return Some(InitialValue::Constant(DmValue::Null));
+        };
+        if self.evaluating.contains(&(owner.clone(), name)) {
+            return Some(InitialValue::NonConstant(NotConstant {
+                span: value.span.clone(),
+                reason: format!("the value of `{name}` depends on itself"),
+            }));
+        }
+        self.evaluating.push((owner.clone(), name));
+        let result = self.evaluate(value, &owner);
+        self.evaluating.pop();
+        Some(match result {
+            Ok(value) => InitialValue::Constant(value),
+            Err(not_constant) => InitialValue::NonConstant(not_constant),
+        })
+    }
+
+    fn initial_value_of(
+        &mut self,
+        path: &DmTypePath,
+        name: Symbol,
+        span: &Span,
+    ) -> Result<DmValue, NotConstant> {
+        match self.initial_value(path, name) {
+            Some(InitialValue::Constant(value)) => Ok(value),
+            Some(InitialValue::NonConstant(not_constant)) => Err(not_constant),
+            None => not_constant(span, format!("`{path}` has no var `{name}`")),
+        }
+    }
+
+    fn evaluate(
+        &mut self,
+        expression: &Expression,
+        context: &DmTypePath,
+    ) -> Result<DmValue, NotConstant> {
+        let span = &expression.span;
+        match &expression.kind {
+            ExpressionKind::Null => Ok(DmValue::Null),
+            ExpressionKind::Number(num) => Ok(DmValue::Num(*num)),
+            ExpressionKind::Resource(resource) => Ok(DmValue::Resource(resource.clone())),
+            ExpressionKind::String(segments) => {
+                let mut text = String::new();
+                for segment in segments {
+                    match segment {
+                        StringSegment::Text(part) => text.push_str(part),
+                        StringSegment::Expression(expression) => {
+                            text.push_str(&self.evaluate(expression, context)?.to_text())
+                        }
+                    }
+                }
+                Ok(DmValue::Text(text))
+            }
+            ExpressionKind::Ident(name) => self.evaluate_ident(*name, context, span),
+            ExpressionKind::Path { path, vars } => {
+                if !vars.is_empty() {
+                    return not_constant(span, "a path with var overrides is not a constant");
+                }
+                match self.tree.resolve_path(path, context) {
+                    Ok(ResolvedPath::Type(path)) => Ok(DmValue::Path(path)),
+                    Ok(ResolvedPath::Proc { on, name, .. }) => Ok(DmValue::ProcPath { on, name }),
+                    Err(error) => not_constant(span, error.to_string()),
+                }
+            }
+            ExpressionKind::StaticField { object, name } => {
+                let DmValue::Path(path) = self.evaluate(object, context)? else {
+                    return not_constant(span, "`::` needs a type path");
+                };
+                self.initial_value_of(&path, *name, span)
+            }
+            ExpressionKind::Unary { op, operand } => {
+                let operand = self.evaluate(operand, context)?;
+                Self::unary(*op, operand, span)
+            }
+            ExpressionKind::Binary { op, lhs, rhs } => {
+                let lhs = self.evaluate(lhs, context)?;
+                match op {
+                    BinaryOp::And if !lhs.is_truthy() => Ok(lhs),
+                    BinaryOp::Or if lhs.is_truthy() => Ok(lhs),
+                    BinaryOp::And | BinaryOp::Or => self.evaluate(rhs, context),
+                    _ => {
+                        let rhs = self.evaluate(rhs, context)?;
+                        Self::binary(*op, lhs, rhs, span)
+                    }
+                }
+            }
+            ExpressionKind::Ternary {
+                condition,
+                if_true,
+                if_false,
+            } => {
+                if self.evaluate(condition, context)?.is_truthy() {
+                    self.evaluate(if_true, context)
+                } else {
+                    self.evaluate(if_false, context)
+                }
+            }
+            ExpressionKind::List { args, .. } => {
+                let mut entries = vec![];
+                for arg in args {
+                    entries.push(self.evaluate_list_entry(arg, context)?);
+                }
+                Ok(DmValue::List(entries))
+            }
+            ExpressionKind::Call { callee, args } => match callee.kind {
+                ExpressionKind::Ident(name) if name == "initial" => {
+                    self.evaluate_initial(args, context, span)
+                }
+                _ => not_constant(span, "a proc call runs when the object is created"),
+            },
+            ExpressionKind::New { .. } => {
+                not_constant(span, "`new` creates the object when its owner is created")
+            }
+            ExpressionKind::Dot | ExpressionKind::ParentProc => {
+                not_constant(span, "`.` and `..` only exist inside of procs")
+            }
+            ExpressionKind::Assign { .. } => not_constant(span, "an assignment is not a constant"),
+            ExpressionKind::Field { .. } | ExpressionKind::Index { .. } => {
+                not_constant(span, "reading another object is not a constant")
+            }
+            ExpressionKind::Locate { .. }
+            | ExpressionKind::Input { .. }
+            | ExpressionKind::DynamicCall { .. } => {
+                not_constant(span, "a proc call runs when the object is created")
+            }
+        }
+    }
+
+    /// A var used by name, only `const` vars on the type or globally are constants.
+    fn evaluate_ident(
+        &mut self,
+        name: Symbol,
+        context: &DmTypePath,
+        span: &Span,
+    ) -> Result<DmValue, NotConstant> {
+        let owner = match self.tree.find_var(context, name) {
+            Some((node, declaration)) => Some((node.path().clone(), declaration.modifiers.clone())),
+            None => self
+                .tree
+                .root()
+                .var(name)
+                .and_then(|var| var.declaration())
+                .map(|declaration| (DmTypePath::default(), declaration.modifiers.clone())),
+        };
+        match owner {
+            Some((owner, modifiers)) if modifiers.is_const => {
+                self.initial_value_of(&owner, name, span)
+            }
+            Some(_) => not_constant(span, format!("`{name}` is not a const var")),
+            None => not_constant(span, format!("`{name}` is not a known constant")),
+        }
+    }
+
+    /// `initial(x)` or `initial(/obj/item::x)`
+    fn evaluate_initial(
+        &mut self,
+        args: &[Argument],
+        context: &DmTypePath,
+        span: &Span,
+    ) -> Result<DmValue, NotConstant> {
+        let [Argument { key: None, value }] = args else {
+            return not_constant(span, "`initial()` takes a single var");
+        };
+        match &value.kind {
+            // `force = initial(force) * 2` builds on the value the type inherits
+            ExpressionKind::Ident(name) if self.evaluating.contains(&(context.clone(), *name)) => {
+                match self.tree.parent(context) {
+                    Some(parent) => self.initial_value_of(parent.path(), *name, span),
+                    None => Ok(DmValue::Null),
+                }
+            }
+            ExpressionKind::Ident(name) => self.initial_value_of(context, *name, span),
+            ExpressionKind::StaticField { .. } => self.evaluate(value, context),
+            _ => not_constant(span, "`initial()` of another object is not a constant"),
+        }
+    }
+
+    fn evaluate_list_entry(
+        &mut self,
+        arg: &Argument,
+        context: &DmTypePath,
+    ) -> Result<(DmValue, Option<DmValue>), NotConstant> {
+        let value = self.evaluate(&arg.value, context)?;
+        let Some(key) = &arg.key else {
+            return Ok((value, None));
+        };
+        // `list(a = 1)` uses the name as a text key
+        let key = match &key.kind {
+            ExpressionKind::Ident(name) => DmValue::Text(name.to_string()),
+            _ => self.evaluate(key, context)?,
+        };
+        Ok((key, Some(value)))
+    }
+
+    fn unary(op: UnaryOp, operand: DmValue, span: &Span) -> Result<DmValue, NotConstant> {
+        match op {
+            UnaryOp::Not => Ok(bool_value(!operand.is_truthy())),
+            UnaryOp::Negate => Ok(DmValue::Num(-as_num(&operand, span)?)),
+            UnaryOp::BitNot => Ok(DmValue::Num(
+                (!(as_num(&operand, span)? as i32) & BIT_MASK) as f32,
+            )),
+            UnaryOp::PreIncrement
+            | UnaryOp::PreDecrement
+            | UnaryOp::PostIncrement
+            | UnaryOp::PostDecrement => not_constant(span, "`++` and `--` change a var"),
+        }
+    }
+
+    fn binary(
+        op: BinaryOp,
+        lhs: DmValue,
+        rhs: DmValue,
+        span: &Span,
+    ) -> Result<DmValue, NotConstant> {
+        match (op, &lhs, &rhs) {
+            (BinaryOp::Add, DmValue::Text(a), DmValue::Text(b)) => {
+                return Ok(DmValue::Text(format!("{a}{b}")))
+            }
+            (BinaryOp::Add, DmValue::Text(_), DmValue::Null) => return Ok(lhs),
+            (BinaryOp::Add, DmValue::Null, DmValue::Text(_)) => return Ok(rhs),
+            (BinaryOp::Eq, _, _) => return Ok(bool_value(lhs == rhs)),
+            (BinaryOp::NotEq, _, _) => return Ok(bool_value(lhs != rhs)),
+            (BinaryOp::Equiv | BinaryOp::NotEquiv, DmValue::Text(a), DmValue::Text(b)) => {
+                let equal = a.eq_ignore_ascii_case(b);
+                return Ok(bool_value(equal == (op == BinaryOp::Equiv)));
+            }
+            (BinaryOp::Equiv, _, _) => return Ok(bool_value(lhs == rhs)),
+            (BinaryOp::NotEquiv, _, _) => return Ok(bool_value(lhs != rhs)),
+            (BinaryOp::In, _, DmValue::List(entries)) => {
+                return Ok(bool_value(entries.iter().any(|(key, _)| *key == lhs)))
+            }
+            (
+                BinaryOp::Less | BinaryOp::LessEq | BinaryOp::Greater | BinaryOp::GreaterEq,
+                DmValue::Text(a),
+                DmValue::Text(b),
+            ) => return Ok(bool_value(compare(op, a.cmp(b)))),
+            _ => {}
+        }
+
+        let a = as_num(&lhs, span)?;
+        let b = as_num(&rhs, span)?;
+        let bits = |result: i32| DmValue::Num((result & BIT_MASK) as f32);
+        Ok(match op {
+            BinaryOp::Add => DmValue::Num(a + b),
+            BinaryOp::Sub => DmValue::Num(a - b),
+            BinaryOp::Mul => DmValue::Num(a * b),
+            BinaryOp::Pow => DmValue::Num(a.powf(b)),
+            BinaryOp::Div | BinaryOp::Mod | BinaryOp::FloatMod if b == 0.0 => {
+                return not_constant(span, "division by zero");
+            }
+            BinaryOp::Div => DmValue::Num(a / b),
+            // `%` works on whole numbers, `%%` keeps the fraction
+            BinaryOp::Mod => match (a as i32).checked_rem(b as i32) {
+                Some(remainder) => DmValue::Num(remainder as f32),
+                // a fraction below 1 is a divisor of zero once truncated
+                None => return not_constant(span, "division by zero"),
+            },
+            BinaryOp::FloatMod => DmValue::Num(a - b * (a / b).floor()),
+            BinaryOp::LShift => bits((a as i32) << (b as i32).clamp(0, 31)),
+            BinaryOp::RShift => bits((a as i32) >> (b as i32).clamp(0, 31)),
+            BinaryOp::BitAnd => bits(a as i32 & b as i32),
+            BinaryOp::BitOr => bits(a as i32 | b as i32),
+            BinaryOp::BitXor => bits(a as i32 ^ b as i32),
+            BinaryOp::Less | BinaryOp::LessEq | BinaryOp::Greater | BinaryOp::GreaterEq => {
+                let Some(ordering) = a.partial_cmp(&b) else {
+                    return Ok(bool_value(false));
+                };
+                bool_value(compare(op, ordering))
+            }
+            BinaryOp::Eq
+            | BinaryOp::NotEq
+            | BinaryOp::Equiv
+            | BinaryOp::NotEquiv
+            | BinaryOp::And
+            | BinaryOp::Or => unreachable!("handled above"),
+            BinaryOp::In => return not_constant(span, "`in` needs a list"),
+        })
+    }
+}
+
+fn bool_value(value: bool) -> DmValue {
+    DmValue::Num(if value { 1.0 } else { 0.0 })
+}
+
+fn compare(op: BinaryOp, ordering: Ordering) -> bool {
+    match op {
+        BinaryOp::Less => ordering.is_lt(),
+        BinaryOp::LessEq => ordering.is_le(),
+        BinaryOp::Greater => ordering.is_gt(),
+        _ => ordering.is_ge(),
+    }
+}
+
+/// Numbers as numbers and `null` as `0`, anything else has no arithmetic.
+fn as_num(value: &DmValue, span: &Span) -> Result<f32, NotConstant> {
+    match value {
+        DmValue::Num(num) => Ok(*num),
+        DmValue::Null => Ok(0.0),
+        _ => not_constant(
+            span,
+            format!("arithmetic on a {} is not a constant", value.type_name()),
+        ),
+    }
+}
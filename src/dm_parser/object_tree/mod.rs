@@ -0,0 +1,257 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{tokens::span::Span, util::symbol::Symbol};
+
+use super::{
+    ast::{
+        declaration::{ParentTypeDeclaration, ProcDeclaration, ProcKind, VarDeclaration},
+        expression::Expression,
+    },
+    parsing::type_path::DmTypePath,
+};
+
+pub mod builder;
+pub mod builtins;
+pub mod constants;
+pub mod procs;
+pub mod resolve;
+
+use builtins::BuiltinDefinition;
+
+/// Every type of the environment with the vars and procs declared on it.
+/// Declarations of the same type from different files are merged into a single node, in include
+/// order, and each keeps its own span.
+#[derive(Debug, Clone)]
+pub struct ObjectTree {
+    types: Vec<TypeNode>,
+    index: HashMap<DmTypePath, usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TypeNode {
+    path: DmTypePath,
+    builtin: bool,
+    /// The parent when no `parent_type` is declared: the type one level up, `/datum` for types
+    /// declared directly under the root, or the builtin parent of builtin types.
+    default_parent: Option<DmTypePath>,
+    /// `parent_type = /obj/base`, the last one declared wins.
+    parent_type: Option<ParentTypeDeclaration>,
+    /// Every block declaring this type, in include order.
+    locations: Vec<Span>,
+    vars: Vec<TypeVar>,
+    var_index: HashMap<Symbol, usize>,
+    procs: Vec<TypeProc>,
+    proc_index: HashMap<Symbol, usize>,
+}
+
+/// A var as it appears on a single type, its declaration and every value assigned to it there.
+#[derive(Debug, Clone)]
+pub struct TypeVar {
+    name: Symbol,
+    declarations: Vec<Arc<VarDeclaration>>,
+    builtin: Option<&'static BuiltinDefinition>,
+}
+
+/// A proc or verb as it appears on a single type, its declaration and every override there.
+#[derive(Debug, Clone)]
+pub struct TypeProc {
+    name: Symbol,
+    definitions: Vec<Arc<ProcDeclaration>>,
+    builtin: Option<&'static BuiltinDefinition>,
+}
+
+impl Default for ObjectTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ObjectTree {
+    pub fn get(&self, path: &DmTypePath) -> Option<&TypeNode> {
+        self.index.get(path).map(|&index| &self.types[index])
+    }
+
+    pub fn contains(&self, path: &DmTypePath) -> bool {
+        self.index.contains_key(path)
+    }
+
+    /// The root `/`, which holds global vars and procs.
+    pub fn root(&self) -> &TypeNode {
+        &self.types[0]
+    }
+
+    /// Every type, in the order they were first seen. Builtin types come first.
+    pub fn iter(&self) -> impl Iterator<Item = &TypeNode> {
+        self.types.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+
+    pub fn parent(&self, path: &DmTypePath) -> Option<&TypeNode> {
+        self.get(self.get(path)?.parent_path()?)
+    }
+
+    /// The type at `path` followed by its parents up to the root.
+    /// Stops early at a missing type or a `parent_type` cycle.
+    pub fn ancestors<'a>(&'a self, path: &DmTypePath) -> impl Iterator<Item = &'a TypeNode> + 'a {
+        let mut next = self.get(path);
+        let mut remaining = self.types.len();
+        std::iter::from_fn(move || {
+            if remaining == 0 {
+                return None;
+            }
+            remaining -= 1;
+            let node = next?;
+            next = node.parent_path().and_then(|parent| self.get(parent));
+            Some(node)
+        })
+    }
+
+    /// True if `path` is `ancestor` or inherits from it.
+    pub fn is_subtype_of(&self, path: &DmTypePath, ancestor: &DmTypePath) -> bool {
+        self.ancestors(path).any(|node| node.path() == ancestor)
+    }
+
+    /// The types whose parent is `path`.
+    pub fn children<'a>(&'a self, path: &'a DmTypePath) -> impl Iterator<Item = &'a TypeNode> + 'a {
+        self.types
+            .iter()
+            .filter(move |node| node.parent_path() == Some(path))
+    }
+
+    /// The type that declares the var `name` as seen from `path`, and its declaration.
+    pub fn find_var(
+        &self,
+        path: &DmTypePath,
+        name: Symbol,
+    ) -> Option<(&TypeNode, &VarDeclaration)> {
+        self.ancestors(path).find_map(|node| {
+            let declaration = node.var(name)?.declaration()?;
+            Some((node, declaration))
+        })
+    }
+
+    /// The value of the var `name` on `path`, either assigned there or inherited.
+    pub fn var_value(&self, path: &DmTypePath, name: Symbol) -> Option<&Expression> {
+        self.ancestors(path)
+            .find_map(|node| node.var(name)?.value())
+    }
+
+    /// The closest type to `path` that declares or overrides the proc `name`.
+    pub fn find_proc(&self, path: &DmTypePath, name: Symbol) -> Option<(&TypeNode, &TypeProc)> {
+        self.ancestors(path)
+            .find_map(|node| Some((node, node.proc(name)?)))
+    }
+}
+
+impl TypeNode {
+    pub fn path(&self) -> &DmTypePath {
+        &self.path
+    }
+
+    /// Builtin types exist even when nothing in the environment declares them.
+    pub fn is_builtin(&self) -> bool {
+        self.builtin
+    }
+
+    pub fn parent_path(&self) -> Option<&DmTypePath> {
+        match &self.parent_type {
+            Some(parent_type) => Some(&parent_type.parent),
+            None => self.default_parent.as_ref(),
+        }
+    }
+
+    pub fn parent_type(&self) -> Option<&ParentTypeDeclaration> {
+        self.parent_type.as_ref()
+    }
+
+    pub fn locations(&self) -> &[Span] {
+        &self.locations
+    }
+
+    pub fn vars(&self) -> &[TypeVar] {
+        &self.vars
+    }
+
+    pub fn var(&self, name: Symbol) -> Option<&TypeVar> {
+        self.var_index.get(&name).map(|&index| &self.vars[index])
+    }
+
+    /// Procs and verbs declared or overridden on this type.
+    pub fn procs(&self) -> &[TypeProc] {
+        &self.procs
+    }
+
+    pub fn proc(&self, name: Symbol) -> Option<&TypeProc> {
+        self.proc_index.get(&name).map(|&index| &self.procs[index])
+    }
+
+    pub fn verbs(&self) -> impl Iterator<Item = &TypeProc> {
+        self.procs.iter().filter(|proc| proc.is_verb())
+    }
+}
+
+impl TypeVar {
+    pub fn name(&self) -> Symbol {
+        self.name
+    }
+
+    /// Every declaration and assignment of the var on this type, in include order.
+    pub fn declarations(&self) -> &[Arc<VarDeclaration>] {
+        &self.declarations
+    }
+
+    /// The `var/` declaration on this type, `None` when it only overrides an inherited var.
+    pub fn declaration(&self) -> Option<&VarDeclaration> {
+        self.declarations
+            .iter()
+            .find(|declaration| declaration.is_declaration)
+            .map(AsRef::as_ref)
+    }
+
+    /// The last value assigned on this type, which is the one BYOND uses.
+    pub fn value(&self) -> Option<&Expression> {
+        self.declarations
+            .iter()
+            .rev()
+            .find_map(|declaration| declaration.value.as_ref())
+    }
+
+    /// The builtin table entry if BYOND declares the var on this type.
+    pub fn builtin(&self) -> Option<&'static BuiltinDefinition> {
+        self.builtin
+    }
+}
+
+impl TypeProc {
+    pub fn name(&self) -> Symbol {
+        self.name
+    }
+
+    /// The declaration and every override on this type, in include order.
+    pub fn definitions(&self) -> &[Arc<ProcDeclaration>] {
+        &self.definitions
+    }
+
+    /// `proc` or `verb` if it is declared on this type, `None` if it only overrides.
+    pub fn kind(&self) -> Option<ProcKind> {
+        self.definitions
+            .iter()
+            .find_map(|definition| definition.declared_kind)
+    }
+
+    pub fn is_verb(&self) -> bool {
+        self.kind() == Some(ProcKind::Verb)
+    }
+
+    /// The builtin table entry if BYOND declares the proc on this type.
+    pub fn builtin(&self) -> Option<&'static BuiltinDefinition> {
+        self.builtin
+    }
+}
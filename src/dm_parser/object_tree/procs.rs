@@ -0,0 +1,147 @@
+use std::{collections::HashSet, fmt::Display};
+
+use crate::{
+    dm_parser::{
+        ast::{
+            declaration::ProcDeclaration,
+            expression::{Expression, ExpressionKind},
+            visit::{self, Visitor},
+        },
+        parsing::type_path::DmTypePath,
+    },
+    tokens::span::Span,
+    util::symbol::Symbol,
+};
+
+use super::ObjectTree;
+
+/// A single definition of a proc: the `index`th definition of `name` on the type `owner`.
+/// A type may define the same proc more than once, the later definitions override the earlier
+/// ones in declaration order.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProcRef {
+    pub owner: DmTypePath,
+    pub name: Symbol,
+    pub index: usize,
+}
+
+impl Display for ProcRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.owner.is_root() {
+            write!(f, "/proc/{}", self.name)?;
+        } else {
+            write!(f, "{}/proc/{}", self.owner, self.name)?;
+        }
+        if self.index > 0 {
+            write!(f, " (definition {})", self.index + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// A `..()` call and the definition it invokes, `None` when there is nothing to call.
+#[derive(Debug, Clone)]
+pub struct ParentCall {
+    pub span: Span,
+    pub target: Option<ProcRef>,
+}
+
+impl ObjectTree {
+    pub fn proc_definition(&self, proc: &ProcRef) -> Option<&ProcDeclaration> {
+        self.get(&proc.owner)?
+            .proc(proc.name)?
+            .definitions()
+            .get(proc.index)
+            .map(AsRef::as_ref)
+    }
+
+    /// Every proc definition in the tree, in type order and then declaration order.
+    pub fn proc_refs(&self) -> impl Iterator<Item = ProcRef> + '_ {
+        self.iter().flat_map(|node| {
+            node.procs().iter().flat_map(move |proc| {
+                (0..proc.definitions().len()).map(move |index| ProcRef {
+                    owner: node.path().clone(),
+                    name: proc.name(),
+                    index,
+                })
+            })
+        })
+    }
+
+    /// The definition that runs when `name` is called on an instance of `path`, the last one on
+    /// the closest type that defines it.
+    pub fn implementation(&self, path: &DmTypePath, name: Symbol) -> Option<ProcRef> {
+        let (owner, proc) = self.find_proc(path, name)?;
+        Some(ProcRef {
+            owner: owner.path().clone(),
+            name,
+            index: proc.definitions().len() - 1,
+        })
+    }
+
+    /// The definition `proc` overrides, which is also what `..()` calls from it. That is the
+    /// previous definition on the same type, or the implementation of the closest parent type.
+    pub fn overridden(&self, proc: &ProcRef) -> Option<ProcRef> {
+        if proc.index > 0 {
+            return Some(ProcRef {
+                index: proc.index - 1,
+                ..proc.clone()
+            });
+        }
+        let parent = self.parent(&proc.owner)?;
+        self.implementation(parent.path(), proc.name)
+    }
+
+    /// `proc` followed by every definition it overrides, up to the original declaration.
+    pub fn override_chain(&self, proc: &ProcRef) -> impl Iterator<Item = ProcRef> + '_ {
+        let mut next = Some(proc.clone());
+        // a `parent_type` cycle would otherwise never end
+        let mut visited = HashSet::new();
+        std::iter::from_fn(move || {
+            let current = next.take()?;
+            if !visited.insert(current.clone()) {
+                return None;
+            }
+            next = self.overridden(&current);
+            Some(current)
+        })
+    }
+
+    /// The definition that originally declared the proc overridden by `proc`.
+    pub fn original_declaration(&self, proc: &ProcRef) -> Option<ProcRef> {
+        self.override_chain(proc).last()
+    }
+
+    /// Every `..()` in the body of `proc` with the definition it calls.
+    pub fn parent_calls(&self, proc: &ProcRef) -> Vec<ParentCall> {
+        let Some(definition) = self.proc_definition(proc) else {
+            return vec![];
+        };
+        let mut finder = ParentCallFinder { spans: vec![] };
+        visit::walk_proc(&mut finder, definition);
+        let target = self.overridden(proc);
+        finder
+            .spans
+            .into_iter()
+            .map(|span| ParentCall {
+                span,
+                target: target.clone(),
+            })
+            .collect()
+    }
+}
+
+struct ParentCallFinder {
+    spans: Vec<Span>,
+}
+
+impl Visitor for ParentCallFinder {
+    fn visit_expression(&mut self, expression: &Expression) {
+        if let ExpressionKind::Call { callee, .. } = &expression.kind {
+            if callee.kind == ExpressionKind::ParentProc {
+                self.spans.push(expression.span.clone());
+            }
+        }
+        visit::walk_expression(self, expression);
+    }
+}
@@ -0,0 +1,214 @@
+use std::fmt::Display;
+
+use crate::{
+    dm_parser::{
+        ast::{
+            declaration::{Declaration, ProcDeclaration},
+            expression::{Expression, ExpressionKind, PathLiteral, PathOp},
+            visit::{self, Visitor},
+            DmAst,
+        },
+        parsing::type_path::{DmTypePath, PathKeyword},
+    },
+    tokens::span::Span,
+    util::symbol::Symbol,
+};
+
+use super::ObjectTree;
+
+/// What a path literal refers to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolvedPath {
+    Type(DmTypePath),
+    /// `/obj/item/proc/attack` or `.proc/attack`, the proc `name` as seen from the type `on`,
+    /// declared or overridden closest on `owner`.
+    Proc {
+        on: DmTypePath,
+        owner: DmTypePath,
+        name: Symbol,
+    },
+}
+
+/// Why a path literal could not be resolved. `path` is the literal up to the part that failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathError {
+    NotFound {
+        path: String,
+    },
+    /// A `:` search found more than one type at the same depth.
+    Ambiguous {
+        path: String,
+        candidates: Vec<DmTypePath>,
+    },
+}
+
+impl Display for PathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathError::NotFound { path } => write!(f, "`{path}` does not exist"),
+            PathError::Ambiguous { path, candidates } => {
+                let candidates: Vec<String> = candidates.iter().map(ToString::to_string).collect();
+                write!(
+                    f,
+                    "`{path}` is ambiguous, it could be any of {}",
+                    candidates.join(", ")
+                )
+            }
+        }
+    }
+}
+
+/// A path literal in the code that could not be resolved.
+#[derive(Debug, Clone)]
+pub struct UnresolvedPath {
+    pub span: Span,
+    pub error: PathError,
+}
+
+impl ObjectTree {
+    /// Resolves a path literal written on the type `context` with BYOND's path operators.
+    /// A leading `/` starts from the root, anything else from `context`. After that `/` goes to
+    /// a child, `.` searches for the name upward from the current type and `:` searches downward
+    /// for the closest subtype with the name. `proc/name` and `verb/name` find the proc as seen
+    /// from the current type.
+    pub fn resolve_path(
+        &self,
+        path: &PathLiteral,
+        context: &DmTypePath,
+    ) -> Result<ResolvedPath, PathError> {
+        let not_found = |end: usize| PathError::NotFound {
+            path: PathLiteral {
+                parts: path.parts[..end].to_vec(),
+            }
+            .to_string(),
+        };
+
+        let mut current = match path.parts.first() {
+            None | Some((PathOp::Slash, _)) => DmTypePath::default(),
+            Some(_) => context.clone(),
+        };
+        let mut index = 0;
+        while let Some(&(op, name)) = path.parts.get(index) {
+            if matches!(
+                PathKeyword::from_part(name),
+                Some(PathKeyword::Proc | PathKeyword::Verb)
+            ) {
+                let [(_, proc_name)] = path.parts[index + 1..] else {
+                    return Err(not_found(path.parts.len()));
+                };
+                let Some((owner, _)) = self.find_proc(&current, proc_name) else {
+                    return Err(not_found(path.parts.len()));
+                };
+                return Ok(ResolvedPath::Proc {
+                    owner: owner.path().clone(),
+                    on: current,
+                    name: proc_name,
+                });
+            }
+
+            current = match op {
+                PathOp::Slash => {
+                    let child = current.child(name);
+                    if !self.contains(&child) {
+                        return Err(not_found(index + 1));
+                    }
+                    child
+                }
+                PathOp::Dot => self
+                    .search_upward(&current, name)
+                    .ok_or_else(|| not_found(index + 1))?,
+                PathOp::Colon => {
+                    let mut candidates = self.search_downward(&current, name);
+                    match candidates.len() {
+                        0 => return Err(not_found(index + 1)),
+                        1 => candidates.remove(0),
+                        _ => {
+                            return Err(PathError::Ambiguous {
+                                path: PathLiteral {
+                                    parts: path.parts[..=index].to_vec(),
+                                }
+                                .to_string(),
+                                candidates,
+                            })
+                        }
+                    }
+                }
+            };
+            index += 1;
+        }
+        Ok(ResolvedPath::Type(current))
+    }
+
+    /// The closest type named `name` directly under `from` or under one of the types above it.
+    fn search_upward(&self, from: &DmTypePath, name: Symbol) -> Option<DmTypePath> {
+        let mut current = Some(from.clone());
+        while let Some(path) = current {
+            let candidate = path.child(name);
+            if self.contains(&candidate) {
+                return Some(candidate);
+            }
+            current = path.parent();
+        }
+        None
+    }
+
+    /// The shallowest types named `name` anywhere below `from`.
+    fn search_downward(&self, from: &DmTypePath, name: Symbol) -> Vec<DmTypePath> {
+        let mut candidates: Vec<DmTypePath> = self
+            .iter()
+            .map(|node| node.path())
+            .filter(|path| path != &from && path.is_subtype_of(from) && path.name() == Some(name))
+            .cloned()
+            .collect();
+        if let Some(depth) = candidates.iter().map(DmTypePath::depth).min() {
+            candidates.retain(|path| path.depth() == depth);
+        }
+        candidates
+    }
+
+    /// Every path literal in `asts` that does not resolve.
+    pub fn unresolved_paths(&self, asts: &[DmAst]) -> Vec<UnresolvedPath> {
+        let mut checker = PathChecker {
+            tree: self,
+            context: DmTypePath::default(),
+            unresolved: vec![],
+        };
+        for ast in asts {
+            visit::walk_ast(&mut checker, ast);
+        }
+        checker.unresolved
+    }
+}
+
+/// Resolves every path literal, relative paths are resolved against the type they appear on.
+struct PathChecker<'a> {
+    tree: &'a ObjectTree,
+    context: DmTypePath,
+    unresolved: Vec<UnresolvedPath>,
+}
+
+impl Visitor for PathChecker<'_> {
+    fn visit_declaration(&mut self, declaration: &Declaration) {
+        if let Declaration::Var(var) = declaration {
+            self.context = var.owner.clone();
+        }
+        visit::walk_declaration(self, declaration);
+    }
+
+    fn visit_proc(&mut self, proc: &ProcDeclaration) {
+        self.context = proc.owner.clone();
+        visit::walk_proc(self, proc);
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        if let ExpressionKind::Path { path, .. } = &expression.kind {
+            if let Err(error) = self.tree.resolve_path(path, &self.context) {
+                self.unresolved.push(UnresolvedPath {
+                    span: expression.span.clone(),
+                    error,
+                });
+            }
+        }
+        visit::walk_expression(self, expression);
+    }
+}
@@ -0,0 +1,98 @@
+use log::error;
+
+use crate::util::ParseError;
+
+use super::{
+    lexer::{located_error, LexemeKind},
+    syntax::SyntaxParser,
+};
+
+/// Blocks are written with indentation, with `{}` or both mixed freely, and entries within a
+/// block are separated by line breaks or `;`.
+impl SyntaxParser {
+    /// True at a `{`, including one on the next line.
+    pub(super) fn at_block_open(&self) -> bool {
+        self.at_punct("{")
+            || (self
+                .peek()
+                .is_some_and(|lexeme| lexeme.kind == LexemeKind::Newline)
+                && self.peek_nth(1).is_some_and(|lexeme| lexeme.is_punct("{")))
+    }
+
+    /// True when a `{}` or indented block follows.
+    pub(super) fn at_any_block(&self) -> bool {
+        self.at_block_open() || self.at_block_start()
+    }
+
+    /// Calls `entry` for each entry of a `{}` or indented block, each entry must end its line or
+    /// be followed by a `;`. Does nothing when no block follows.
+    pub(super) fn parse_block_entries(
+        &mut self,
+        mut entry: impl FnMut(&mut Self) -> Result<(), ParseError>,
+    ) -> Result<(), ParseError> {
+        if self.at_block_open() {
+            self.skip_separators();
+            let open = self.expect_punct("{")?;
+            loop {
+                self.skip_separators();
+                if self.eat_punct("}") {
+                    return Ok(());
+                }
+                if self.is_at_end() {
+                    error!("unclosed `{{`");
+                    return Err(located_error(ParseError::UNEXPECTED_EOL, &open));
+                }
+                entry(self)?;
+                self.expect_entry_end()?;
+            }
+        }
+
+        if !self.at_block_start() {
+            return Ok(());
+        }
+        self.next(); // newline
+        self.next(); // indent
+        while let Some(lexeme) = self.peek() {
+            match lexeme.kind {
+                LexemeKind::Dedent => {
+                    self.next();
+                    break;
+                }
+                LexemeKind::Indent => {
+                    error!("indented line does not belong to any block");
+                    return Err(self.error_here(ParseError::MISMATCHED_INDENTATION_COUNT));
+                }
+                LexemeKind::Newline => self.skip_separators(),
+                _ if lexeme.is_punct(";") => self.skip_separators(),
+                _ => {
+                    entry(self)?;
+                    self.expect_entry_end()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Skips line breaks and `;` between entries.
+    pub(super) fn skip_separators(&mut self) {
+        while self
+            .peek()
+            .is_some_and(|lexeme| lexeme.kind == LexemeKind::Newline || lexeme.is_punct(";"))
+        {
+            self.next();
+        }
+    }
+
+    /// Checks that an entry of a block ended, at a line break, a `;`, the end of the enclosing
+    /// `{}` block or right after a block of its own.
+    pub(super) fn expect_entry_end(&self) -> Result<(), ParseError> {
+        if self.at_line_end() || self.after_block_end() {
+            return Ok(());
+        }
+        error!(
+            "expected the end of the line, found {}",
+            self.describe_next()
+        );
+        Err(self.error_here(ParseError::EXPECTED_DIFFERENT_TOKEN))
+    }
+}
@@ -0,0 +1,417 @@
+use std::{path::Path, sync::Arc};
+
+use log::error;
+
+use crate::{
+    dm_parser::ast::{
+        declaration::{
+            Declaration, ParentTypeDeclaration, ProcDeclaration, ProcKind, ProcParam,
+            TypeDeclaration, VarDeclaration, VarModifiers,
+        },
+        DmAst,
+    },
+    tokens::span::Span,
+    util::{symbol::Symbol, ParseError},
+};
+
+use super::{
+    lexer::{located_error, Lexeme, LexemeKind},
+    syntax::{Restrictions, SyntaxParser},
+    type_path::{DmTypePath, PathKeyword},
+};
+
+/// Input types that are not type paths, `as num` does not make a proc return `/num`.
+const PRIMITIVE_INPUT_TYPES: &[&str] = &[
+    "anything",
+    "color",
+    "command_text",
+    "file",
+    "key",
+    "message",
+    "null",
+    "num",
+    "password",
+    "text",
+];
+
+/// What a declaration path such as `/obj/var/tmp/x` declares, judged by its `var`, `proc` and
+/// `verb` segments.
+enum PathMeaning {
+    /// `/obj/item`, or `/obj/name` when it is followed by a value or parameters.
+    Type(DmTypePath),
+    /// `/obj/var/global`, a block of vars.
+    VarBlock,
+    /// `/obj/proc`, a block of procs.
+    ProcBlock,
+    Var {
+        owner: DmTypePath,
+        modifiers: VarModifiers,
+        var_type: Option<DmTypePath>,
+        name: Symbol,
+    },
+    Proc {
+        owner: DmTypePath,
+        kind: ProcKind,
+        name: Symbol,
+    },
+}
+
+impl PathMeaning {
+    fn of(path: &[Symbol]) -> Result<Self, ParseError> {
+        let type_path = DmTypePath::from_parts(path);
+        let Some((keyword, path_keyword)) = type_path.find_keyword() else {
+            return Ok(PathMeaning::Type(type_path));
+        };
+
+        let owner = DmTypePath::from_parts(&path[..keyword]);
+        let rest = &path[keyword + 1..];
+        if path_keyword == PathKeyword::Var {
+            let mut modifiers = VarModifiers::default();
+            let mut rest = rest;
+            while let Some((first, remaining)) = rest.split_first() {
+                if !modifiers.set(first.as_str()) {
+                    break;
+                }
+                rest = remaining;
+            }
+            let Some((name, var_type)) = rest.split_last() else {
+                return Ok(PathMeaning::VarBlock);
+            };
+            return Ok(PathMeaning::Var {
+                owner,
+                modifiers,
+                var_type: (!var_type.is_empty()).then(|| DmTypePath::from_parts(var_type)),
+                name: *name,
+            });
+        }
+
+        let kind = if path_keyword == PathKeyword::Verb {
+            ProcKind::Verb
+        } else {
+            ProcKind::Proc
+        };
+        match rest {
+            [] => Ok(PathMeaning::ProcBlock),
+            [name] => Ok(PathMeaning::Proc {
+                owner,
+                kind,
+                name: *name,
+            }),
+            _ => {
+                error!(
+                    "`{}` has more than a name after `{}`",
+                    DmTypePath::from_parts(path),
+                    path[keyword]
+                );
+                Err(ParseError::INVALID_IDENTIFIER)
+            }
+        }
+    }
+}
+
+impl SyntaxParser {
+    pub fn parse_file(mut self, path: &Path) -> Result<DmAst, ParseError> {
+        let declarations = self.parse_declarations(&[])?;
+        if self.peek().is_some() {
+            error!("unexpected {} at the top level", self.describe_next());
+            return Err(self.error_here(ParseError::MISMATCHED_INDENTATION_COUNT));
+        }
+        Ok(DmAst {
+            path: path.to_path_buf(),
+            declarations,
+        })
+    }
+
+    /// Parses declarations until the end of the file, or a stray `}` or dedent.
+    fn parse_declarations(&mut self, context: &[Symbol]) -> Result<Vec<Declaration>, ParseError> {
+        let mut declarations = vec![];
+        loop {
+            self.skip_separators();
+            let Some(lexeme) = self.peek() else {
+                break;
+            };
+            match lexeme.kind {
+                LexemeKind::Dedent => break,
+                LexemeKind::Indent => {
+                    error!("indented line does not belong to any block");
+                    return Err(self.error_here(ParseError::MISMATCHED_INDENTATION_COUNT));
+                }
+                _ if lexeme.is_punct("}") => break,
+                _ => {
+                    self.parse_declaration(context, &mut declarations)?;
+                    self.expect_entry_end()?;
+                }
+            }
+        }
+        Ok(declarations)
+    }
+
+    fn parse_declaration(
+        &mut self,
+        context: &[Symbol],
+        declarations: &mut Vec<Declaration>,
+    ) -> Result<(), ParseError> {
+        let start = self.span_here();
+        let mut path = if self.eat_punct("/") {
+            vec![]
+        } else {
+            context.to_vec()
+        };
+        loop {
+            path.push(self.expect_ident()?.0);
+            if !self.eat_punct("/") {
+                break;
+            }
+        }
+
+        let meaning = PathMeaning::of(&path).map_err(|err| located_error(err, &start))?;
+
+        if self.at_punct("(") {
+            let (owner, kind, name) = match meaning {
+                PathMeaning::Proc { owner, kind, name } => (owner, Some(kind), name),
+                PathMeaning::Type(type_path) if !type_path.is_root() => {
+                    let (owner, name) = type_path.split_last().unwrap();
+                    (owner, None, name)
+                }
+                _ => {
+                    error!("parameters after `{}`", DmTypePath::from_parts(path));
+                    return Err(self.error_here(ParseError::EXPECTED_DIFFERENT_TOKEN));
+                }
+            };
+            let proc = self.parse_proc(owner, kind, name, start)?;
+            declarations.push(Declaration::Proc(Arc::new(proc)));
+            return Ok(());
+        }
+
+        match meaning {
+            PathMeaning::Var {
+                owner,
+                modifiers,
+                var_type,
+                name,
+            } => {
+                let var = self.parse_var(owner, name, true, modifiers, var_type, start)?;
+                declarations.push(Declaration::Var(Arc::new(var)));
+            }
+            PathMeaning::Type(type_path) if self.at_punct("=") => {
+                let (owner, name) = type_path.split_last().unwrap();
+                if name == "parent_type" {
+                    self.next();
+                    let parent = self.parse_type_path_value()?;
+                    declarations.push(Declaration::ParentType(ParentTypeDeclaration {
+                        owner,
+                        parent,
+                        span: start.to(&self.previous_span()),
+                    }));
+                } else {
+                    let var =
+                        self.parse_var(owner, name, false, Default::default(), None, start)?;
+                    declarations.push(Declaration::Var(Arc::new(var)));
+                }
+            }
+            PathMeaning::Type(type_path) => {
+                let type_declarations = self.parse_block(&path)?;
+                declarations.push(Declaration::Type(TypeDeclaration {
+                    path: type_path,
+                    declarations: type_declarations,
+                    span: start.to(&self.previous_span()),
+                }));
+            }
+            PathMeaning::VarBlock | PathMeaning::ProcBlock => {
+                declarations.extend(self.parse_block(&path)?);
+            }
+            PathMeaning::Proc { .. } => {
+                error!(
+                    "missing parameters after `{}`",
+                    DmTypePath::from_parts(path)
+                );
+                return Err(self.error_here(ParseError::EXPECTED_DIFFERENT_TOKEN));
+            }
+        }
+        Ok(())
+    }
+
+    /// The declarations of a `{}` or indented block after `context`, if there is one.
+    fn parse_block(&mut self, context: &[Symbol]) -> Result<Vec<Declaration>, ParseError> {
+        let mut declarations = vec![];
+        self.parse_block_entries(|parser| parser.parse_declaration(context, &mut declarations))?;
+        Ok(declarations)
+    }
+
+    fn parse_var(
+        &mut self,
+        owner: DmTypePath,
+        name: Symbol,
+        is_declaration: bool,
+        modifiers: VarModifiers,
+        var_type: Option<DmTypePath>,
+        start: Span,
+    ) -> Result<VarDeclaration, ParseError> {
+        let mut dimensions = vec![];
+        while self.eat_punct("[") {
+            if self.eat_punct("]") {
+                dimensions.push(None);
+                continue;
+            }
+            dimensions.push(Some(self.parse_expression()?));
+            self.expect_punct("]")?;
+        }
+
+        let mut input_types = self.parse_input_types();
+        let mut value = None;
+        if self.eat_punct("=") {
+            value = Some(self.parse_expression()?);
+            if input_types.is_empty() {
+                input_types = self.parse_input_types();
+            }
+        }
+
+        Ok(VarDeclaration {
+            owner,
+            name,
+            is_declaration,
+            modifiers,
+            var_type,
+            dimensions,
+            input_types,
+            value,
+            span: start.to(&self.previous_span()),
+        })
+    }
+
+    /// `as num|text`
+    pub(super) fn parse_input_types(&mut self) -> Vec<Symbol> {
+        let mut input_types = vec![];
+        if !self.eat_ident("as") {
+            return input_types;
+        }
+        while let Some(input_type) = self.peek().and_then(Lexeme::ident) {
+            self.next();
+            input_types.push(input_type);
+            if !self.eat_punct("|") {
+                break;
+            }
+        }
+        input_types
+    }
+
+    /// The value of `parent_type`, a type path literal.
+    fn parse_type_path_value(&mut self) -> Result<DmTypePath, ParseError> {
+        let mut parts = vec![];
+        self.expect_punct("/")?;
+        loop {
+            parts.push(self.expect_ident()?.0);
+            if !self.eat_punct("/") {
+                break;
+            }
+        }
+        Ok(DmTypePath::from_parts(parts))
+    }
+
+    fn parse_proc(
+        &mut self,
+        owner: DmTypePath,
+        declared_kind: Option<ProcKind>,
+        name: Symbol,
+        start: Span,
+    ) -> Result<ProcDeclaration, ParseError> {
+        self.expect_punct("(")?;
+        let mut params = vec![];
+        let mut variadic = false;
+        while !self.eat_punct(")") {
+            if self.eat_punct("...") {
+                variadic = true;
+                self.eat_punct(",");
+                continue;
+            }
+            params.push(self.parse_param()?);
+            if !self.at_punct(")") {
+                self.expect_punct(",")?;
+            }
+        }
+
+        let return_type = self.parse_return_type();
+        let span = start.to(&self.previous_span());
+
+        let body = self.parse_body()?;
+
+        Ok(ProcDeclaration {
+            owner,
+            name,
+            declared_kind,
+            params,
+            variadic,
+            return_type,
+            body,
+            span,
+        })
+    }
+
+    fn parse_param(&mut self) -> Result<ProcParam, ParseError> {
+        let start = self.span_here();
+        self.eat_punct("/");
+        let mut path = vec![];
+        loop {
+            path.push(self.expect_ident()?.0);
+            if !self.eat_punct("/") {
+                break;
+            }
+        }
+        if path.first().is_some_and(|part| *part == "var") {
+            path.remove(0);
+        }
+        let Some((name, var_type)) = path.split_last() else {
+            error!("parameter has no name");
+            return Err(self.error_here(ParseError::INVALID_IDENTIFIER));
+        };
+
+        let mut default = None;
+        if self.eat_punct("=") {
+            let restrictions = Restrictions {
+                no_in: true,
+                ..Default::default()
+            };
+            default = Some(self.with_restrictions(restrictions, Self::parse_expression)?);
+        }
+        let input_types = self.parse_input_types();
+        let mut input_list = None;
+        if self.eat_ident("in") {
+            input_list = Some(self.parse_expression()?);
+        }
+
+        Ok(ProcParam {
+            name: *name,
+            var_type: (!var_type.is_empty()).then(|| DmTypePath::from_parts(var_type)),
+            default,
+            input_types,
+            input_list,
+            span: start.to(&self.previous_span()),
+        })
+    }
+
+    /// `as /obj/item`, or `as obj`. Primitive input types such as `as num` are not a type.
+    fn parse_return_type(&mut self) -> Option<DmTypePath> {
+        if !self.at_ident("as") {
+            return None;
+        }
+        if !self.peek_nth(1).is_some_and(|lexeme| lexeme.is_punct("/")) {
+            return match self.parse_input_types().as_slice() {
+                [single] if !PRIMITIVE_INPUT_TYPES.contains(&single.as_str()) => {
+                    Some(DmTypePath::from_parts(vec![*single]))
+                }
+                _ => None,
+            };
+        }
+
+        self.next(); // as
+        let mut parts = vec![];
+        while self.eat_punct("/") {
+            let Some(part) = self.peek().and_then(Lexeme::ident) else {
+                break;
+            };
+            self.next();
+            parts.push(part);
+        }
+        Some(DmTypePath::from_parts(parts))
+    }
+}
@@ -0,0 +1,491 @@
+use log::error;
+
+use crate::{
+    dm_parser::ast::expression::{
+        Argument, AssignOp, BinaryOp, Expression, ExpressionKind, FieldAccess, PathLiteral, PathOp,
+        StringSegment, UnaryOp,
+    },
+    tokens::span::Span,
+    util::ParseError,
+};
+
+use super::{
+    lexer::{Lexeme, LexemeKind, StringPart},
+    syntax::{Restrictions, SyntaxParser},
+};
+
+impl SyntaxParser {
+    /// Parses a full expression, including assignments.
+    pub fn parse_expression(&mut self) -> Result<Expression, ParseError> {
+        let target = self.parse_ternary()?;
+        let Some(op) = self.peek_assign_op() else {
+            return Ok(target);
+        };
+        self.next();
+        let value = self.parse_expression()?;
+        let span = target.span.to(&value.span);
+        Ok(Expression::new(
+            ExpressionKind::Assign {
+                op,
+                target: Box::new(target),
+                value: Box::new(value),
+            },
+            span,
+        ))
+    }
+
+    fn peek_assign_op(&self) -> Option<AssignOp> {
+        match self.peek()?.kind {
+            LexemeKind::Punct(punct) => AssignOp::from_punct(punct),
+            _ => None,
+        }
+    }
+
+    fn parse_ternary(&mut self) -> Result<Expression, ParseError> {
+        let condition = self.parse_binary(0)?;
+        if !self.eat_punct("?") {
+            return Ok(condition);
+        }
+
+        let restrictions = Restrictions {
+            no_colon: true,
+            ..self.restrictions
+        };
+        let if_true = self.with_restrictions(restrictions, Self::parse_ternary)?;
+        self.expect_punct(":")?;
+        let if_false = self.parse_ternary()?;
+        let span = condition.span.to(&if_false.span);
+        Ok(Expression::new(
+            ExpressionKind::Ternary {
+                condition: Box::new(condition),
+                if_true: Box::new(if_true),
+                if_false: Box::new(if_false),
+            },
+            span,
+        ))
+    }
+
+    fn peek_binary_op(&self) -> Option<BinaryOp> {
+        match self.peek()?.kind {
+            LexemeKind::Punct(punct) => BinaryOp::from_punct(punct),
+            LexemeKind::Ident(ident) if ident == "in" && !self.restrictions.no_in => {
+                Some(BinaryOp::In)
+            }
+            _ => None,
+        }
+    }
+
+    /// Parses binary operators binding at least as tightly as `min_strength`.
+    pub(super) fn parse_binary(&mut self, min_strength: u8) -> Result<Expression, ParseError> {
+        let mut lhs = self.parse_unary()?;
+        while let Some(op) = self.peek_binary_op() {
+            if op.strength() < min_strength {
+                break;
+            }
+            self.next();
+            // `**` is right associative
+            let rhs_strength = if op == BinaryOp::Pow {
+                op.strength()
+            } else {
+                op.strength() + 1
+            };
+            let rhs = self.parse_binary(rhs_strength)?;
+            let span = lhs.span.to(&rhs.span);
+            lhs = Expression::new(
+                ExpressionKind::Binary {
+                    op,
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                },
+                span,
+            );
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expression, ParseError> {
+        let start = self.span_here();
+        let op = match self.peek().map(|lexeme| &lexeme.kind) {
+            Some(LexemeKind::Punct("-")) => UnaryOp::Negate,
+            Some(LexemeKind::Punct("!")) => UnaryOp::Not,
+            Some(LexemeKind::Punct("~")) => UnaryOp::BitNot,
+            Some(LexemeKind::Punct("++")) => UnaryOp::PreIncrement,
+            Some(LexemeKind::Punct("--")) => UnaryOp::PreDecrement,
+            _ => return self.parse_postfix(),
+        };
+        self.next();
+        let operand = self.parse_unary()?;
+        let span = start.to(&operand.span);
+        Ok(Expression::new(
+            ExpressionKind::Unary {
+                op,
+                operand: Box::new(operand),
+            },
+            span,
+        ))
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expression, ParseError> {
+        let mut expression = self.parse_primary()?;
+        loop {
+            let followed_by_ident = self
+                .peek_nth(1)
+                .is_some_and(|lexeme| lexeme.ident().is_some());
+            let access = match self.peek().map(|lexeme| &lexeme.kind) {
+                Some(LexemeKind::Punct(".")) if followed_by_ident => FieldAccess::Dot,
+                Some(LexemeKind::Punct("?.")) => FieldAccess::SafeDot,
+                Some(LexemeKind::Punct(":"))
+                    if followed_by_ident && !self.restrictions.no_colon =>
+                {
+                    FieldAccess::Colon
+                }
+                Some(LexemeKind::Punct("::")) => {
+                    self.next();
+                    let (name, name_span) = self.expect_ident()?;
+                    let span = expression.span.to(&name_span);
+                    expression = Expression::new(
+                        ExpressionKind::StaticField {
+                            object: Box::new(expression),
+                            name,
+                        },
+                        span,
+                    );
+                    continue;
+                }
+                Some(LexemeKind::Punct(open @ ("[" | "?["))) => {
+                    let safe = *open == "?[";
+                    self.next();
+                    let index =
+                        self.with_restrictions(Restrictions::default(), Self::parse_expression)?;
+                    let end = self.expect_punct("]")?;
+                    let span = expression.span.to(&end);
+                    expression = Expression::new(
+                        ExpressionKind::Index {
+                            object: Box::new(expression),
+                            index: Box::new(index),
+                            safe,
+                        },
+                        span,
+                    );
+                    continue;
+                }
+                Some(LexemeKind::Punct("(")) => {
+                    let args = self.parse_arguments()?;
+                    let span = expression.span.to(&self.previous_span());
+                    expression = Expression::new(
+                        ExpressionKind::Call {
+                            callee: Box::new(expression),
+                            args,
+                        },
+                        span,
+                    );
+                    continue;
+                }
+                Some(LexemeKind::Punct(step @ ("++" | "--"))) => {
+                    let op = if *step == "++" {
+                        UnaryOp::PostIncrement
+                    } else {
+                        UnaryOp::PostDecrement
+                    };
+                    self.next();
+                    let span = expression.span.to(&self.previous_span());
+                    expression = Expression::new(
+                        ExpressionKind::Unary {
+                            op,
+                            operand: Box::new(expression),
+                        },
+                        span,
+                    );
+                    continue;
+                }
+                _ => break,
+            };
+            self.next();
+            let (name, name_span) = self.expect_ident()?;
+            let span = expression.span.to(&name_span);
+            expression = Expression::new(
+                ExpressionKind::Field {
+                    object: Box::new(expression),
+                    access,
+                    name,
+                },
+                span,
+            );
+        }
+        Ok(expression)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expression, ParseError> {
+        let start = self.span_here();
+        let Some(lexeme) = self.peek().cloned() else {
+            error!("expected an expression, found end of file");
+            return Err(self.error_here(ParseError::UNEXPECTED_EOL));
+        };
+        let followed_by_paren = self.peek_nth(1).is_some_and(|next| next.is_punct("("));
+
+        let kind = match lexeme.kind {
+            LexemeKind::Number(number) => {
+                self.next();
+                ExpressionKind::Number(number)
+            }
+            LexemeKind::String(parts) => {
+                self.next();
+                ExpressionKind::String(Self::parse_string_parts(parts, &lexeme.span)?)
+            }
+            LexemeKind::Resource(resource) => {
+                self.next();
+                ExpressionKind::Resource(resource)
+            }
+            LexemeKind::Ident(ident) => match ident.as_str() {
+                "null" => {
+                    self.next();
+                    ExpressionKind::Null
+                }
+                "new" => return self.parse_new(),
+                "list" | "alist" if followed_by_paren => {
+                    self.next();
+                    ExpressionKind::List {
+                        associative_only: ident == "alist",
+                        args: self.parse_arguments()?,
+                    }
+                }
+                "locate" if followed_by_paren => {
+                    self.next();
+                    let args = self.parse_arguments()?;
+                    ExpressionKind::Locate {
+                        args,
+                        in_list: self.parse_in_list()?,
+                    }
+                }
+                "input" if followed_by_paren => {
+                    self.next();
+                    let args = self.parse_arguments()?;
+                    let input_types = self.parse_input_types();
+                    ExpressionKind::Input {
+                        args,
+                        input_types,
+                        in_list: self.parse_in_list()?,
+                    }
+                }
+                "call" | "call_ext" if followed_by_paren => {
+                    self.next();
+                    let target = self.parse_arguments()?;
+                    if !self.at_punct("(") {
+                        error!("`{ident}()` must be followed by the arguments of the call");
+                        return Err(self.error_here(ParseError::EXPECTED_DIFFERENT_TOKEN));
+                    }
+                    ExpressionKind::DynamicCall {
+                        external: ident == "call_ext",
+                        target,
+                        args: self.parse_arguments()?,
+                    }
+                }
+                _ => {
+                    self.next();
+                    ExpressionKind::Ident(ident)
+                }
+            },
+            LexemeKind::Punct("/") => return self.parse_path_expression(),
+            LexemeKind::Punct(".")
+                if self.peek_nth(1).is_some_and(|next| next.ident().is_some()) =>
+            {
+                return self.parse_path_expression()
+            }
+            LexemeKind::Punct(".") => {
+                self.next();
+                ExpressionKind::Dot
+            }
+            LexemeKind::Punct("..") => {
+                self.next();
+                ExpressionKind::ParentProc
+            }
+            LexemeKind::Punct("(") => {
+                self.next();
+                let inner =
+                    self.with_restrictions(Restrictions::default(), Self::parse_expression)?;
+                let end = self.expect_punct(")")?;
+                return Ok(Expression::new(inner.kind, start.to(&end)));
+            }
+            _ => {
+                error!("expected an expression, found {}", self.describe_next());
+                return Err(self.error_here(ParseError::EXPECTED_DIFFERENT_TOKEN));
+            }
+        };
+        Ok(Expression::new(kind, start.to(&self.previous_span())))
+    }
+
+    fn parse_string_parts(
+        parts: Vec<StringPart>,
+        string_span: &Span,
+    ) -> Result<Vec<StringSegment>, ParseError> {
+        parts
+            .into_iter()
+            .map(|part| match part {
+                StringPart::Text(text) => Ok(StringSegment::Text(text)),
+                StringPart::Expression(lexemes) if lexemes.is_empty() => {
+                    Ok(StringSegment::Expression(Expression::new(
+                        ExpressionKind::Null,
+                        string_span.clone(),
+                    )))
+                }
+                StringPart::Expression(lexemes) => {
+                    let mut parser = SyntaxParser::new(lexemes);
+                    let expression = parser.parse_expression()?;
+                    if !parser.is_at_end() {
+                        error!(
+                            "unexpected {} in embedded expression",
+                            parser.describe_next()
+                        );
+                        return Err(parser.error_here(ParseError::EXPECTED_DIFFERENT_TOKEN));
+                    }
+                    Ok(StringSegment::Expression(expression))
+                }
+            })
+            .collect()
+    }
+
+    /// `in container` after `locate()` and `input()`.
+    fn parse_in_list(&mut self) -> Result<Option<Box<Expression>>, ParseError> {
+        if self.restrictions.no_in || !self.eat_ident("in") {
+            return Ok(None);
+        }
+        Ok(Some(Box::new(self.parse_binary(BinaryOp::Or.strength())?)))
+    }
+
+    /// Parses `(a, b = 1, ...)`. Empty arguments are passed as `null`.
+    pub(super) fn parse_arguments(&mut self) -> Result<Vec<Argument>, ParseError> {
+        self.expect_punct("(")?;
+        self.with_restrictions(Restrictions::default(), |parser| {
+            let mut args = vec![];
+            loop {
+                if parser.eat_punct(")") {
+                    return Ok(args);
+                }
+                if parser.at_punct(",") {
+                    let span = parser.span_here();
+                    parser.next();
+                    args.push(Argument {
+                        key: None,
+                        value: Expression::new(ExpressionKind::Null, span),
+                    });
+                    continue;
+                }
+
+                let value = parser.parse_expression()?;
+                args.push(match value.kind {
+                    ExpressionKind::Assign {
+                        op: AssignOp::Assign,
+                        target,
+                        value,
+                    } => Argument {
+                        key: Some(*target),
+                        value: *value,
+                    },
+                    _ => Argument { key: None, value },
+                });
+                if !parser.at_punct(")") {
+                    parser.expect_punct(",")?;
+                }
+            }
+        })
+    }
+
+    /// `new /obj(loc)`, `new path_var(loc)` or `new(loc)`.
+    fn parse_new(&mut self) -> Result<Expression, ParseError> {
+        let start = self.next().unwrap().span;
+        let new_type = match self.peek().map(|lexeme| &lexeme.kind) {
+            Some(LexemeKind::Punct("/" | ".")) => Some(self.parse_path_expression()?),
+            Some(LexemeKind::Ident(ident)) => {
+                let ident = *ident;
+                let mut new_type = Expression::new(ExpressionKind::Ident(ident), self.span_here());
+                self.next();
+                // `new src.type()`, calls are the arguments of `new` and not part of the type
+                while self.at_punct(".") || (self.at_punct(":") && !self.restrictions.no_colon) {
+                    let access = if self.at_punct(".") {
+                        FieldAccess::Dot
+                    } else {
+                        FieldAccess::Colon
+                    };
+                    self.next();
+                    let (name, name_span) = self.expect_ident()?;
+                    let span = new_type.span.to(&name_span);
+                    new_type = Expression::new(
+                        ExpressionKind::Field {
+                            object: Box::new(new_type),
+                            access,
+                            name,
+                        },
+                        span,
+                    );
+                }
+                Some(new_type)
+            }
+            _ => None,
+        };
+
+        let args = if self.at_punct("(") {
+            self.parse_arguments()?
+        } else {
+            vec![]
+        };
+        Ok(Expression::new(
+            ExpressionKind::New {
+                new_type: new_type.map(Box::new),
+                args,
+            },
+            start.to(&self.previous_span()),
+        ))
+    }
+
+    /// A path literal with optional `{var = value; ...}` modifications.
+    fn parse_path_expression(&mut self) -> Result<Expression, ParseError> {
+        let start = self.span_here();
+        let path = self.parse_path_literal()?;
+
+        let mut vars = vec![];
+        if self.eat_punct("{") {
+            while !self.eat_punct("}") {
+                if self.eat_punct(";") {
+                    continue;
+                }
+                let (name, _) = self.expect_ident()?;
+                self.expect_punct("=")?;
+                let value = self.with_restrictions(Restrictions::default(), Self::parse_ternary)?;
+                vars.push((name, value));
+                if !self.at_punct("}") {
+                    self.expect_punct(";")?;
+                }
+            }
+        }
+
+        Ok(Expression::new(
+            ExpressionKind::Path { path, vars },
+            start.to(&self.previous_span()),
+        ))
+    }
+
+    pub(super) fn parse_path_literal(&mut self) -> Result<PathLiteral, ParseError> {
+        let mut parts = vec![];
+        loop {
+            let op = match self.peek().map(|lexeme| &lexeme.kind) {
+                Some(LexemeKind::Punct("/")) => PathOp::Slash,
+                Some(LexemeKind::Punct(".")) => PathOp::Dot,
+                Some(LexemeKind::Punct(":")) if !self.restrictions.no_colon => PathOp::Colon,
+                _ => break,
+            };
+            let Some(part) = self.peek_nth(1).and_then(Lexeme::ident) else {
+                break;
+            };
+            self.next();
+            self.next();
+            parts.push((op, part));
+        }
+
+        // `/` alone is the root
+        if parts.is_empty() && !self.eat_punct("/") {
+            error!("expected a path, found {}", self.describe_next());
+            return Err(self.error_here(ParseError::INVALID_IDENTIFIER));
+        }
+        Ok(PathLiteral { parts })
+    }
+}
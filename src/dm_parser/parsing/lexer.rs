@@ -0,0 +1,800 @@
+use std::{collections::VecDeque, fmt::Display};
+
+use log::{error, trace, warn};
+
+use crate::{
+    tokens::{dm_token::DmToken, span::Span},
+    util::{is_valid_identifier, symbol::Symbol, ParseError},
+};
+
+/// Every operator and punctuation mark DM knows about, longest first so they can be matched greedily.
+const PUNCTUATION: &[&str] = &[
+    "%%=", "<<=", ">>=", "||=", "&&=", "...", "**", "%%", "<<", ">>", "<=", ">=", "==", "!=", "<>",
+    "~=", "~!", "&&", "||", "++", "--", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", ":=", "?.",
+    "?[", "::", "..", "+", "-", "*", "/", "%", "^", "&", "|", "!", "~", "=", "<", ">", ".", "?",
+    ":", "(", ")", "[", "]", "{", "}", ",", ";", "#", "@", "$", "\\",
+];
+
+const OPERATOR_CHARS: &[char] = &[
+    '+', '-', '*', '/', '%', '^', '&', '|', '!', '~', '=', '<', '>', '.', '?', ':',
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexemeKind {
+    Ident(Symbol),
+    Number(f32),
+    String(Vec<StringPart>),
+    /// A single quoted resource, such as `'icon.dmi'`.
+    Resource(String),
+    Punct(&'static str),
+    Newline,
+    Indent,
+    Dedent,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StringPart {
+    Text(String),
+    /// An embedded `[expression]`.
+    Expression(Vec<Lexeme>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lexeme {
+    pub kind: LexemeKind,
+    pub span: Span,
+}
+
+impl Lexeme {
+    pub fn is_punct(&self, punct: &str) -> bool {
+        matches!(self.kind, LexemeKind::Punct(value) if value == punct)
+    }
+
+    pub fn is_ident(&self, ident: &str) -> bool {
+        matches!(self.kind, LexemeKind::Ident(value) if value == ident)
+    }
+
+    pub fn ident(&self) -> Option<Symbol> {
+        match self.kind {
+            LexemeKind::Ident(ident) => Some(ident),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Lexeme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            LexemeKind::Ident(ident) => write!(f, "{ident}"),
+            LexemeKind::Number(number) => write!(f, "{number}"),
+            LexemeKind::String(_) => write!(f, "string"),
+            LexemeKind::Resource(resource) => write!(f, "'{resource}'"),
+            LexemeKind::Punct(punct) => write!(f, "{punct}"),
+            LexemeKind::Newline => write!(f, "newline"),
+            LexemeKind::Indent => write!(f, "indent"),
+            LexemeKind::Dedent => write!(f, "dedent"),
+        }
+    }
+}
+
+pub fn located_error(error: ParseError, span: &Span) -> ParseError {
+    let mut error = error;
+    if let Some(path) = span.path() {
+        error = error.with_file_path(path.display().to_string());
+    }
+    if let Some(line) = span.line() {
+        error = error.with_line_number(line);
+    }
+    error
+}
+
+/// The tokens the lexer has pulled but not read yet. The source stops at its first error, which
+/// is kept to be returned once lexing stops.
+struct Lookahead<I: Iterator<Item = Result<DmToken, ParseError>>> {
+    source: I,
+    buffered: VecDeque<DmToken>,
+    error: Option<ParseError>,
+}
+
+impl<I: Iterator<Item = Result<DmToken, ParseError>>> Lookahead<I> {
+    fn fill(&mut self, count: usize) {
+        while self.buffered.len() < count && self.error.is_none() {
+            match self.source.next() {
+                Some(Ok(token)) => self.buffered.push_back(token),
+                Some(Err(error)) => self.error = Some(error),
+                None => break,
+            }
+        }
+    }
+
+    fn next(&mut self) -> Option<DmToken> {
+        self.fill(1);
+        self.buffered.pop_front()
+    }
+
+    fn peek(&mut self) -> Option<&DmToken> {
+        self.fill(1);
+        self.buffered.front()
+    }
+
+    fn peek_second(&mut self) -> Option<&DmToken> {
+        self.fill(2);
+        self.buffered.get(1)
+    }
+}
+
+/// Turns preprocessed tokens into lexemes.
+/// Operators and numbers split up by the tokenizer are glued back together, strings are collected
+/// into a single lexeme and indentation is turned into `Indent` and `Dedent` lexemes.
+pub struct Lexer<I: Iterator<Item = Result<DmToken, ParseError>>> {
+    tokens: Lookahead<I>,
+    /// Span of the last token read, used for synthesized tokens that have no span of their own.
+    last_span: Span,
+    /// Open `(` and `[`. Line breaks inside of them do not end the line.
+    bracket_depth: usize,
+    /// Open `{`. Indentation inside of braces does not nest blocks.
+    brace_depth: usize,
+    /// The indentation of each open indented block, innermost last.
+    indent_stack: Vec<String>,
+    at_line_start: bool,
+    lexemes: Vec<Lexeme>,
+}
+
+impl<I: Iterator<Item = Result<DmToken, ParseError>>> Lexer<I> {
+    /// Pulls the tokens as they are lexed, such as from the preprocessor.
+    pub fn new(tokens: I) -> Self {
+        Self {
+            tokens: Lookahead {
+                source: tokens,
+                buffered: VecDeque::new(),
+                error: None,
+            },
+            last_span: Span::unknown(),
+            bracket_depth: 0,
+            brace_depth: 0,
+            indent_stack: vec![],
+            at_line_start: true,
+            lexemes: vec![],
+        }
+    }
+
+    pub fn lex(mut self) -> Result<Vec<Lexeme>, ParseError> {
+        while self.tokens.peek().is_some() {
+            if self.at_line_start {
+                self.consume_line_start()?;
+                continue;
+            }
+            self.lex_token()?;
+        }
+        if let Some(error) = self.tokens.error.take() {
+            return Err(error);
+        }
+
+        self.end_line();
+        let span = self.last_span.clone();
+        for _ in 0..self.indent_stack.len() {
+            self.push(LexemeKind::Dedent, span.clone());
+        }
+        Ok(self.lexemes)
+    }
+
+    fn next_token(&mut self) -> Option<DmToken> {
+        let token = self.tokens.next()?;
+        let span = token.span();
+        if span.is_known() {
+            self.last_span = span;
+        }
+        Some(token)
+    }
+
+    fn token_span(&self, token: &DmToken) -> Span {
+        let span = token.span();
+        if span.is_known() {
+            span
+        } else {
+            self.last_span.clone()
+        }
+    }
+
+    fn push(&mut self, kind: LexemeKind, span: Span) {
+        trace!("lexeme: {kind:?}");
+        self.lexemes.push(Lexeme { kind, span });
+    }
+
+    fn end_line(&mut self) {
+        if self
+            .lexemes
+            .last()
+            .is_some_and(|last| !matches!(last.kind, LexemeKind::Newline | LexemeKind::Dedent))
+        {
+            let span = self.last_span.clone();
+            self.push(LexemeKind::Newline, span);
+        }
+        self.at_line_start = true;
+    }
+
+    /// Reads the indentation of a line and emits the layout lexemes for it.
+    /// Blank lines are skipped entirely.
+    fn consume_line_start(&mut self) -> Result<(), ParseError> {
+        let indent = self.consume_indentation();
+        match self.tokens.peek() {
+            None => return Ok(()),
+            Some(token) if token.value() == "\n" => {
+                self.next_token();
+                return Ok(());
+            }
+            _ => {}
+        }
+        self.at_line_start = false;
+        if self.brace_depth > 0 {
+            return Ok(());
+        }
+
+        let span = self
+            .tokens
+            .peek()
+            .map(DmToken::span)
+            .filter(Span::is_known)
+            .unwrap_or_else(|| self.last_span.clone());
+        if indent.contains(' ') && indent.contains('\t') {
+            warn!("{span}: indentation mixes tabs and spaces");
+        }
+
+        let current = self.indent_stack.last().map_or("", String::as_str);
+        if indent == current {
+            return Ok(());
+        }
+        if indent.starts_with(current) {
+            self.indent_stack.push(indent);
+            self.push(LexemeKind::Indent, span);
+            return Ok(());
+        }
+        if !current.starts_with(&indent) {
+            error!("indentation is inconsistent with the enclosing block's");
+            return Err(located_error(
+                ParseError::MISMATCHED_INDENTATION_COUNT,
+                &span,
+            ));
+        }
+        while self
+            .indent_stack
+            .last()
+            .is_some_and(|level| level.len() > indent.len())
+        {
+            self.indent_stack.pop();
+            self.push(LexemeKind::Dedent, span.clone());
+        }
+        if self.indent_stack.last().map_or("", String::as_str) != indent {
+            error!("dedent does not match any outer indentation level");
+            return Err(located_error(
+                ParseError::MISMATCHED_INDENTATION_COUNT,
+                &span,
+            ));
+        }
+        Ok(())
+    }
+
+    /// The whitespace at the start of a line.
+    fn consume_indentation(&mut self) -> String {
+        let mut indent = String::new();
+        while let Some(front) = self.tokens.peek() {
+            if front.value() == "\n" || !front.value().chars().all(|c| matches!(c, ' ' | '\t')) {
+                break;
+            }
+            indent.push_str(self.next_token().unwrap().value());
+        }
+        indent
+    }
+
+    fn lex_token(&mut self) -> Result<(), ParseError> {
+        let token = self.tokens.peek().unwrap();
+        let value = token.value();
+
+        if value == "\n" {
+            self.next_token();
+            if self.bracket_depth == 0 {
+                self.end_line();
+            } else {
+                // skip the indentation of a continued line
+                self.consume_indentation();
+            }
+            return Ok(());
+        }
+
+        if value.chars().all(char::is_whitespace) {
+            self.next_token();
+            return Ok(());
+        }
+
+        if value == "\"" {
+            let token = self.next_token().unwrap();
+            let span = self.token_span(&token);
+            let (parts, end_span) = self.lex_string_body(&span)?;
+            self.push(LexemeKind::String(parts), span.to(&end_span));
+            return Ok(());
+        }
+
+        if value == "'" {
+            return self.lex_resource();
+        }
+
+        if value == "{" {
+            let token = self.next_token().unwrap();
+            if self.tokens.peek().is_some_and(|next| next.value() == "\"") {
+                return self.lex_multiline_string(token);
+            }
+            let span = self.token_span(&token);
+            return self.lex_punctuation("{", &span);
+        }
+
+        if value.len() > 1 && value.ends_with('@') {
+            return self.lex_raw_string();
+        }
+
+        if value.starts_with(|c: char| c.is_ascii_digit()) {
+            return self.lex_number();
+        }
+
+        if is_valid_identifier(value) {
+            let token = self.next_token().unwrap();
+            let span = self.token_span(&token);
+            self.push(LexemeKind::Ident(Symbol::intern(token.value())), span);
+            return Ok(());
+        }
+
+        if value.chars().all(|c| OPERATOR_CHARS.contains(&c)) {
+            return self.lex_operators();
+        }
+
+        let token = self.next_token().unwrap();
+        let span = self.token_span(&token);
+        self.lex_punctuation(token.value(), &span)
+    }
+
+    /// Splits a run of punctuation into the longest punctuation marks possible.
+    fn lex_punctuation(&mut self, mut text: &str, span: &Span) -> Result<(), ParseError> {
+        while !text.is_empty() {
+            let Some(punct) = PUNCTUATION.iter().find(|punct| text.starts_with(*punct)) else {
+                error!("unknown punctuation `{}`", text.escape_debug());
+                return Err(located_error(ParseError::EXPECTED_DIFFERENT_TOKEN, span));
+            };
+            match *punct {
+                "(" | "[" | "?[" => self.bracket_depth += 1,
+                ")" | "]" => self.bracket_depth = self.bracket_depth.saturating_sub(1),
+                "{" => self.brace_depth += 1,
+                "}" => self.brace_depth = self.brace_depth.saturating_sub(1),
+                _ => {}
+            }
+            self.push(LexemeKind::Punct(punct), span.clone());
+            text = &text[punct.len()..];
+        }
+        Ok(())
+    }
+
+    /// Operators are split and merged inconsistently by the tokenizer, so adjacent operator
+    /// characters are joined back together before being split again.
+    fn lex_operators(&mut self) -> Result<(), ParseError> {
+        let first = self.next_token().unwrap();
+        let mut span = self.token_span(&first);
+        let mut text = first.value().to_string();
+        while let Some(next) = self.tokens.peek() {
+            if next.value().is_empty() || !next.value().chars().all(|c| OPERATOR_CHARS.contains(&c))
+            {
+                break;
+            }
+            let next = self.next_token().unwrap();
+            span = span.to(&self.token_span(&next));
+            text.push_str(next.value());
+        }
+        // `?[` is split since `[` is not an operator character
+        if text.ends_with('?') && self.tokens.peek().is_some_and(|next| next.value() == "[") {
+            let bracket = self.next_token().unwrap();
+            span = span.to(&self.token_span(&bracket));
+            text.push('[');
+        }
+        self.lex_punctuation(&text, &span)
+    }
+
+    fn lex_number(&mut self) -> Result<(), ParseError> {
+        let first = self.next_token().unwrap();
+        let mut span = self.token_span(&first);
+        let mut text = first.value().to_string();
+
+        // `1.5` is tokenized as `1` `.` `5`
+        if !text.starts_with("0x")
+            && self.tokens.peek().is_some_and(|next| next.value() == ".")
+            && self
+                .tokens
+                .peek_second()
+                .is_some_and(|next| next.value().starts_with(|c: char| c.is_ascii_digit()))
+        {
+            self.next_token();
+            let fraction = self.next_token().unwrap();
+            span = span.to(&self.token_span(&fraction));
+            text.push('.');
+            text.push_str(fraction.value());
+        }
+
+        // `1e-5` is tokenized as `1e` `-` `5`
+        if !text.starts_with("0x")
+            && text.ends_with(['e', 'E'])
+            && self
+                .tokens
+                .peek()
+                .is_some_and(|next| matches!(next.value(), "-" | "+"))
+        {
+            let sign = self.next_token().unwrap();
+            text.push_str(sign.value());
+            if let Some(exponent) = self.next_token() {
+                span = span.to(&self.token_span(&exponent));
+                text.push_str(exponent.value());
+            }
+        }
+
+        let number = if let Some(hex) = text.strip_prefix("0x") {
+            i64::from_str_radix(hex, 16).map(|value| value as f32).ok()
+        } else {
+            text.parse::<f32>().ok()
+        };
+        let Some(number) = number else {
+            error!("invalid number `{text}`");
+            return Err(located_error(ParseError::INVALID_IDENTIFIER, &span));
+        };
+        self.push(LexemeKind::Number(number), span);
+        Ok(())
+    }
+
+    /// Reads everything after an opening `"` up to and including the closing `"`.
+    fn lex_string_body(&mut self, start: &Span) -> Result<(Vec<StringPart>, Span), ParseError> {
+        let mut parts = vec![];
+        let mut text = String::new();
+        loop {
+            let Some(token) = self.next_token() else {
+                return Err(located_error(ParseError::UNEXPECTED_EOL, start));
+            };
+            match token.value() {
+                "\"" => {
+                    push_text(&mut parts, &mut text);
+                    return Ok((parts, self.token_span(&token)));
+                }
+                "[" => {
+                    push_text(&mut parts, &mut text);
+                    parts.push(StringPart::Expression(self.lex_embedded_expression(start)?));
+                }
+                value => unescape_into(&mut text, value),
+            }
+        }
+    }
+
+    fn lex_multiline_string(&mut self, open_brace: DmToken) -> Result<(), ParseError> {
+        let span = self.token_span(&open_brace);
+        self.next_token(); // the `"`
+        let mut parts = vec![];
+        let mut text = String::new();
+        loop {
+            let Some(token) = self.next_token() else {
+                return Err(located_error(ParseError::UNEXPECTED_EOL, &span));
+            };
+            match token.value() {
+                "}" if text.ends_with('"') => {
+                    text.pop();
+                    push_text(&mut parts, &mut text);
+                    let end_span = self.token_span(&token);
+                    self.push(LexemeKind::String(parts), span.to(&end_span));
+                    return Ok(());
+                }
+                "[" => {
+                    push_text(&mut parts, &mut text);
+                    parts.push(StringPart::Expression(self.lex_embedded_expression(&span)?));
+                }
+                value => unescape_into(&mut text, value),
+            }
+        }
+    }
+
+    /// `@"raw"`, the `@` ends up at the end of whatever token came before it.
+    fn lex_raw_string(&mut self) -> Result<(), ParseError> {
+        let token = self.next_token().unwrap();
+        let span = self.token_span(&token);
+        let prefix = token.value().trim_end_matches('@').trim();
+        if !prefix.is_empty() {
+            self.lex_punctuation(prefix, &span)?;
+        }
+
+        let Some(content) = self.next_token() else {
+            return Err(located_error(ParseError::UNEXPECTED_EOL, &span));
+        };
+        let mut text = String::new();
+        let mut end_span = self.token_span(&content);
+        if content.value() != "\"" {
+            text.push_str(content.value());
+            if let Some(delimiter) = self.next_token() {
+                end_span = self.token_span(&delimiter);
+            }
+        }
+        self.push(
+            LexemeKind::String(vec![StringPart::Text(text)]),
+            span.to(&end_span),
+        );
+        Ok(())
+    }
+
+    fn lex_resource(&mut self) -> Result<(), ParseError> {
+        let open = self.next_token().unwrap();
+        let span = self.token_span(&open);
+        let mut resource = String::new();
+        loop {
+            let Some(token) = self.next_token() else {
+                return Err(located_error(ParseError::UNEXPECTED_EOL, &span));
+            };
+            if token.value() == "'" {
+                let end_span = self.token_span(&token);
+                self.push(LexemeKind::Resource(resource), span.to(&end_span));
+                return Ok(());
+            }
+            resource.push_str(token.value());
+        }
+    }
+
+    /// Lexes the inside of a `[...]` in a string, up to and including the closing `]`.
+    fn lex_embedded_expression(&mut self, start: &Span) -> Result<Vec<Lexeme>, ParseError> {
+        let outer_lexemes = std::mem::take(&mut self.lexemes);
+        let outer_depth = self.bracket_depth;
+        self.bracket_depth = 1;
+
+        let result = loop {
+            let Some(token) = self.tokens.peek() else {
+                break Err(located_error(ParseError::UNEXPECTED_EOL, start));
+            };
+            if token.value() == "]" && self.bracket_depth == 1 {
+                self.next_token();
+                break Ok(());
+            }
+            if let Err(err) = self.lex_token() {
+                break Err(err);
+            }
+        };
+
+        self.bracket_depth = outer_depth;
+        let inner = std::mem::replace(&mut self.lexemes, outer_lexemes);
+        result.map(|_| inner)
+    }
+}
+
+fn push_text(parts: &mut Vec<StringPart>, text: &mut String) {
+    if !text.is_empty() {
+        parts.push(StringPart::Text(std::mem::take(text)));
+    }
+}
+
+/// Resolves the escapes that change the text of a string. Text macros such as `\the` are kept.
+fn unescape_into(text: &mut String, value: &str) {
+    let mut chars = value.chars();
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            text.push(char);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => text.push('\n'),
+            Some('t') => text.push('\t'),
+            Some(escaped @ ('"' | '\'' | '\\' | '[' | ']')) => text.push(escaped),
+            Some(other) => {
+                text.push('\\');
+                text.push(other);
+            }
+            None => text.push('\\'),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::{dm_preprocessor::lib::DmPreProcessor, tokens::tokenize::DmTokenizer};
+
+    fn try_lex(lines: &[&str]) -> Result<Vec<Lexeme>, ParseError> {
+        let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        let mut preprocessor = DmPreProcessor::new();
+        Lexer::new(preprocessor.preprocess_tokens(DmTokenizer::new(Path::new("test.dm"), &lines)))
+            .lex()
+    }
+
+    fn lex(lines: &[&str]) -> Vec<LexemeKind> {
+        try_lex(lines)
+            .unwrap()
+            .into_iter()
+            .map(|lexeme| without_spans(lexeme.kind))
+            .collect()
+    }
+
+    /// Spans inside of strings are not compared, embedded expressions get unknown spans.
+    fn without_spans(kind: LexemeKind) -> LexemeKind {
+        let LexemeKind::String(parts) = kind else {
+            return kind;
+        };
+        let parts = parts
+            .into_iter()
+            .map(|part| match part {
+                StringPart::Expression(lexemes) => StringPart::Expression(
+                    lexemes
+                        .into_iter()
+                        .map(|lexeme| Lexeme {
+                            kind: without_spans(lexeme.kind),
+                            span: Span::unknown(),
+                        })
+                        .collect(),
+                ),
+                text => text,
+            })
+            .collect();
+        LexemeKind::String(parts)
+    }
+
+    fn ident(value: &str) -> LexemeKind {
+        LexemeKind::Ident(Symbol::intern(value))
+    }
+
+    #[test]
+    fn lex_glues_operators_and_numbers() {
+        assert_eq!(
+            lex(&["x-=-1.5e-3 ?. a::b"]),
+            vec![
+                ident("x"),
+                LexemeKind::Punct("-="),
+                LexemeKind::Punct("-"),
+                LexemeKind::Number(1.5e-3),
+                LexemeKind::Punct("?."),
+                ident("a"),
+                LexemeKind::Punct("::"),
+                ident("b"),
+                LexemeKind::Newline,
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_strings() {
+        assert_eq!(
+            lex(&["x = \"a[b]\\\"c\" + 'icon.dmi' + {\"d", "e\"}"]),
+            vec![
+                ident("x"),
+                LexemeKind::Punct("="),
+                LexemeKind::String(vec![
+                    StringPart::Text("a".into()),
+                    StringPart::Expression(vec![Lexeme {
+                        kind: ident("b"),
+                        span: Span::unknown(),
+                    }]),
+                    StringPart::Text("\"c".into()),
+                ]),
+                LexemeKind::Punct("+"),
+                LexemeKind::Resource("icon.dmi".into()),
+                LexemeKind::Punct("+"),
+                LexemeKind::String(vec![StringPart::Text("d\ne".into())]),
+                LexemeKind::Newline,
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_indentation() {
+        assert_eq!(
+            lex(&["/obj", "\tvar", "\t\tx = list(", "\t\t\t1)", "", "/mob"]),
+            vec![
+                LexemeKind::Punct("/"),
+                ident("obj"),
+                LexemeKind::Newline,
+                LexemeKind::Indent,
+                ident("var"),
+                LexemeKind::Newline,
+                LexemeKind::Indent,
+                ident("x"),
+                LexemeKind::Punct("="),
+                ident("list"),
+                LexemeKind::Punct("("),
+                LexemeKind::Number(1.0),
+                LexemeKind::Punct(")"),
+                LexemeKind::Newline,
+                LexemeKind::Dedent,
+                LexemeKind::Dedent,
+                LexemeKind::Punct("/"),
+                ident("mob"),
+                LexemeKind::Newline,
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_define_at_statement_start() {
+        assert_eq!(
+            lex(&[
+                "#define X 1",
+                "#define SIGNAL_HANDLER  SHOULD_NOT_SLEEP(TRUE) ",
+                "#define SHOULD_NOT_SLEEP(x) set SpacemanDMM_should_not_sleep = x",
+                "/proc/test()",
+                "  SIGNAL_HANDLER",
+                "  X",
+                "  return",
+            ]),
+            vec![
+                LexemeKind::Punct("/"),
+                ident("proc"),
+                LexemeKind::Punct("/"),
+                ident("test"),
+                LexemeKind::Punct("("),
+                LexemeKind::Punct(")"),
+                LexemeKind::Newline,
+                LexemeKind::Indent,
+                ident("set"),
+                ident("SpacemanDMM_should_not_sleep"),
+                LexemeKind::Punct("="),
+                LexemeKind::Number(1.0),
+                LexemeKind::Newline,
+                LexemeKind::Number(1.0),
+                LexemeKind::Newline,
+                ident("return"),
+                LexemeKind::Newline,
+                LexemeKind::Dedent,
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_returns_preprocessor_errors() {
+        assert!(try_lex(&["x = 1", "#error stop", "y = 2"]).is_err());
+    }
+
+    #[test]
+    fn lex_negative_stddef_defines() {
+        assert_eq!(
+            lex(&["layer = FLOAT_LAYER"]),
+            vec![
+                ident("layer"),
+                LexemeKind::Punct("="),
+                LexemeKind::Punct("-"),
+                LexemeKind::Number(1.0),
+                LexemeKind::Newline,
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_uneven_indentation() {
+        let layout: Vec<LexemeKind> = lex(&["/obj", "  a", "      b", "  c", "d"])
+            .into_iter()
+            .filter(|kind| matches!(kind, LexemeKind::Indent | LexemeKind::Dedent))
+            .collect();
+        assert_eq!(
+            layout,
+            vec![
+                LexemeKind::Indent,
+                LexemeKind::Indent,
+                LexemeKind::Dedent,
+                LexemeKind::Dedent,
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_mismatched_indentation() {
+        let error = try_lex(&["/obj", "    a", "      b", "  c"]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            ParseError::MISMATCHED_INDENTATION_COUNT.to_string()
+        );
+        assert_eq!(error.line_number(), Some(4));
+
+        let error = try_lex(&["/obj", "\ta", "    b"]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            ParseError::MISMATCHED_INDENTATION_COUNT.to_string()
+        );
+        assert_eq!(error.line_number(), Some(3));
+
+        // tabs and spaces mixed the same way on every line only warn
+        assert!(try_lex(&["/obj", "\t  a", "\t  b"]).is_ok());
+    }
+}
@@ -0,0 +1,669 @@
+use log::error;
+
+use crate::{
+    dm_parser::ast::{
+        declaration::VarModifiers,
+        expression::{AssignOp, Expression, ExpressionKind},
+        statement::{
+            Block, CaseValue, Catch, ForVar, LocalVar, Statement, StatementKind, SwitchCase,
+        },
+    },
+    tokens::span::Span,
+    util::{symbol::Symbol, ParseError},
+};
+
+use super::{
+    lexer::{located_error, LexemeKind},
+    syntax::{Restrictions, SyntaxParser},
+    type_path::DmTypePath,
+};
+
+/// Words that start a statement, which are never the name of a label.
+const STATEMENT_KEYWORDS: &[&str] = &[
+    "if", "else", "while", "do", "for", "switch", "spawn", "try", "throw", "return", "break",
+    "continue", "goto", "del", "set", "var",
+];
+
+/// The first clause of a `for`, which decides what kind of loop it is.
+enum ForInit {
+    Var(LocalVar),
+    Expression(Expression),
+}
+
+impl SyntaxParser {
+    /// Parses the body of a proc or a control flow statement. That is a `{}` block, an indented
+    /// block or a single statement on the same line.
+    pub(super) fn parse_body(&mut self) -> Result<Block, ParseError> {
+        if self.at_any_block() {
+            let mut body = vec![];
+            self.parse_block_entries(|parser| {
+                body.push(parser.parse_statement()?);
+                Ok(())
+            })?;
+            return Ok(body);
+        }
+        if self.at_line_end() {
+            return Ok(vec![]);
+        }
+        Ok(vec![self.parse_statement()?])
+    }
+
+    pub fn parse_statement(&mut self) -> Result<Statement, ParseError> {
+        let start = self.span_here();
+        let keyword = self.peek().and_then(|lexeme| lexeme.ident());
+        let kind = match keyword.map(|keyword| keyword.as_str()) {
+            Some("if") => self.parse_if()?,
+            Some("while") => {
+                self.next();
+                let condition = self.parse_condition()?;
+                StatementKind::While {
+                    condition,
+                    body: self.parse_body()?,
+                }
+            }
+            Some("do") => {
+                self.next();
+                let body = self.parse_body()?;
+                self.eat_newline_before("while");
+                if !self.eat_ident("while") {
+                    error!(
+                        "expected `while` after `do`, found {}",
+                        self.describe_next()
+                    );
+                    return Err(self.error_here(ParseError::EXPECTED_DIFFERENT_TOKEN));
+                }
+                StatementKind::DoWhile {
+                    body,
+                    condition: self.parse_condition()?,
+                }
+            }
+            Some("for") => self.parse_for()?,
+            Some("switch") => self.parse_switch()?,
+            Some("spawn") => {
+                self.next();
+                let mut delay = None;
+                if self.eat_punct("(") && !self.eat_punct(")") {
+                    delay = Some(self.parse_parenthesized_rest()?);
+                }
+                StatementKind::Spawn {
+                    delay,
+                    body: self.parse_body()?,
+                }
+            }
+            Some("try") => {
+                self.next();
+                let body = self.parse_body()?;
+                StatementKind::Try {
+                    body,
+                    catch: self.parse_catch()?,
+                }
+            }
+            Some("throw") => {
+                self.next();
+                StatementKind::Throw(self.parse_expression()?)
+            }
+            Some("return") => {
+                self.next();
+                StatementKind::Return(self.parse_optional_expression()?)
+            }
+            Some("break") => {
+                self.next();
+                StatementKind::Break(self.parse_optional_label())
+            }
+            Some("continue") => {
+                self.next();
+                StatementKind::Continue(self.parse_optional_label())
+            }
+            Some("goto") => {
+                self.next();
+                StatementKind::Goto(self.expect_ident()?.0)
+            }
+            Some("del") => {
+                self.next();
+                StatementKind::Del(self.parse_expression()?)
+            }
+            Some("set") => self.parse_set()?,
+            Some("var") if self.at_local_var() => StatementKind::Var(self.parse_local_vars()?),
+            Some("else") => {
+                error!("`else` without an `if`");
+                return Err(self.error_here(ParseError::EXPECTED_DIFFERENT_TOKEN));
+            }
+            Some(_) if self.at_label() => {
+                let (name, _) = self.expect_ident()?;
+                self.eat_punct(":");
+                StatementKind::Label {
+                    name,
+                    body: self.parse_body()?,
+                }
+            }
+            _ if self.at_punct("{") => StatementKind::Block(self.parse_body()?),
+            _ => StatementKind::Expression(self.parse_expression()?),
+        };
+        Ok(Statement {
+            kind,
+            span: start.to(&self.previous_span()),
+        })
+    }
+
+    /// `name:` at the end of a line or before a `{}` block, or a lone `name` followed by an
+    /// indented block.
+    fn at_label(&self) -> bool {
+        if self
+            .peek()
+            .and_then(|lexeme| lexeme.ident())
+            .is_some_and(|name| STATEMENT_KEYWORDS.contains(&name.as_str()))
+        {
+            return false;
+        }
+        let Some(next) = self.peek_nth(1) else {
+            return false;
+        };
+        if next.is_punct(":") {
+            return match self.peek_nth(2) {
+                None => true,
+                Some(after) => {
+                    matches!(
+                        after.kind,
+                        LexemeKind::Newline | LexemeKind::Indent | LexemeKind::Dedent
+                    ) || after.is_punct("}")
+                        || after.is_punct(";")
+                        || after.is_punct("{")
+                }
+            };
+        }
+        next.kind == LexemeKind::Newline
+            && self
+                .peek_nth(2)
+                .is_some_and(|after| after.kind == LexemeKind::Indent)
+    }
+
+    /// `var/` followed by a declaration, or `var` followed by a block of them.
+    fn at_local_var(&self) -> bool {
+        match self.peek_nth(1) {
+            Some(next) if next.is_punct("/") || next.is_punct("{") => true,
+            Some(next) if next.kind == LexemeKind::Newline => self
+                .peek_nth(2)
+                .is_some_and(|after| after.kind == LexemeKind::Indent || after.is_punct("{")),
+            _ => false,
+        }
+    }
+
+    /// `(expression)`
+    fn parse_condition(&mut self) -> Result<Expression, ParseError> {
+        self.expect_punct("(")?;
+        self.parse_parenthesized_rest()
+    }
+
+    /// The rest of `(expression)` after the `(`.
+    fn parse_parenthesized_rest(&mut self) -> Result<Expression, ParseError> {
+        let expression = self.with_restrictions(Restrictions::default(), Self::parse_expression)?;
+        self.expect_punct(")")?;
+        Ok(expression)
+    }
+
+    fn parse_optional_expression(&mut self) -> Result<Option<Expression>, ParseError> {
+        if self.at_line_end() {
+            return Ok(None);
+        }
+        Ok(Some(self.parse_expression()?))
+    }
+
+    fn parse_optional_label(&mut self) -> Option<Symbol> {
+        let label = self.peek().and_then(|lexeme| lexeme.ident())?;
+        self.next();
+        Some(label)
+    }
+
+    /// Skips the line break before `keyword` if the next line starts with it, as with `else`
+    /// after an indented block.
+    fn eat_newline_before(&mut self, keyword: &str) {
+        if self
+            .peek()
+            .is_some_and(|lexeme| lexeme.kind == LexemeKind::Newline)
+            && self
+                .peek_nth(1)
+                .is_some_and(|lexeme| lexeme.is_ident(keyword))
+        {
+            self.next();
+        }
+    }
+
+    fn parse_if(&mut self) -> Result<StatementKind, ParseError> {
+        self.next(); // if
+        let condition = self.parse_condition()?;
+        let mut arms = vec![(condition, self.parse_body()?)];
+        let mut else_arm = None;
+
+        loop {
+            self.eat_newline_before("else");
+            if !self.eat_ident("else") {
+                break;
+            }
+            if self.eat_ident("if") {
+                let condition = self.parse_condition()?;
+                arms.push((condition, self.parse_body()?));
+                continue;
+            }
+            else_arm = Some(self.parse_body()?);
+            break;
+        }
+        Ok(StatementKind::If { arms, else_arm })
+    }
+
+    fn parse_for(&mut self) -> Result<StatementKind, ParseError> {
+        self.next(); // for
+        let open = self.expect_punct("(")?;
+        if self.eat_punct(")") {
+            return Ok(StatementKind::For {
+                init: None,
+                condition: None,
+                increment: None,
+                body: self.parse_body()?,
+            });
+        }
+
+        let restrictions = Restrictions {
+            no_in: true,
+            ..Default::default()
+        };
+        let init = if self.at_punct(";") || self.at_punct(",") {
+            None
+        } else if self.at_ident("var") {
+            Some(ForInit::Var(
+                self.with_restrictions(restrictions, Self::parse_local_var)?,
+            ))
+        } else {
+            Some(ForInit::Expression(
+                self.with_restrictions(restrictions, Self::parse_expression)?,
+            ))
+        };
+
+        if self.eat_ident("in") {
+            let var = Self::for_var(init, &open)?.0;
+            let list = self.with_restrictions(Restrictions::default(), Self::parse_expression)?;
+            if self.eat_ident("to") {
+                let (end, step) = self.parse_range_rest()?;
+                return Ok(StatementKind::ForRange {
+                    var,
+                    start: list,
+                    end,
+                    step,
+                    body: self.parse_body()?,
+                });
+            }
+            self.expect_punct(")")?;
+            return Ok(StatementKind::ForIn {
+                var,
+                list: Some(list),
+                body: self.parse_body()?,
+            });
+        }
+
+        if self.eat_ident("to") {
+            let (var, start) = Self::for_var(init, &open)?;
+            let Some(start) = start else {
+                error!("`to` in a `for` needs a starting value");
+                return Err(self.error_here(ParseError::EXPECTED_DIFFERENT_TOKEN));
+            };
+            let (end, step) = self.parse_range_rest()?;
+            return Ok(StatementKind::ForRange {
+                var,
+                start,
+                end,
+                step,
+                body: self.parse_body()?,
+            });
+        }
+
+        if self.at_punct(")") {
+            if let Some(ForInit::Var(local)) = &init {
+                if local.value.is_none() {
+                    let var = Self::for_var(init, &open)?.0;
+                    self.next();
+                    return Ok(StatementKind::ForIn {
+                        var,
+                        list: None,
+                        body: self.parse_body()?,
+                    });
+                }
+            }
+        }
+
+        let init = init.map(|init| {
+            let (kind, span) = match init {
+                ForInit::Var(local) => {
+                    let span = local.span.clone();
+                    (StatementKind::Var(vec![local]), span)
+                }
+                ForInit::Expression(expression) => {
+                    let span = expression.span.clone();
+                    (StatementKind::Expression(expression), span)
+                }
+            };
+            Box::new(Statement { kind, span })
+        });
+        self.expect_for_separator()?;
+        let condition = if self.at_punct(";") || self.at_punct(",") {
+            None
+        } else {
+            Some(self.parse_expression()?)
+        };
+        self.expect_for_separator()?;
+        let increment = if self.at_punct(")") {
+            None
+        } else {
+            let expression = self.parse_expression()?;
+            let span = expression.span.clone();
+            Some(Box::new(Statement {
+                kind: StatementKind::Expression(expression),
+                span,
+            }))
+        };
+        self.expect_punct(")")?;
+
+        Ok(StatementKind::For {
+            init,
+            condition,
+            increment,
+            body: self.parse_body()?,
+        })
+    }
+
+    fn expect_for_separator(&mut self) -> Result<(), ParseError> {
+        if self.eat_punct(";") || self.eat_punct(",") {
+            return Ok(());
+        }
+        error!(
+            "expected `;` or `,` in `for`, found {}",
+            self.describe_next()
+        );
+        Err(self.error_here(ParseError::EXPECTED_DIFFERENT_TOKEN))
+    }
+
+    /// The loop variable of a `for` and the value it was assigned, if any.
+    fn for_var(
+        init: Option<ForInit>,
+        open: &Span,
+    ) -> Result<(ForVar, Option<Expression>), ParseError> {
+        match init {
+            Some(ForInit::Var(local)) => Ok((
+                ForVar {
+                    name: local.name,
+                    var_type: local.var_type,
+                    declared: true,
+                    span: local.span,
+                },
+                local.value,
+            )),
+            Some(ForInit::Expression(Expression {
+                kind: ExpressionKind::Ident(name),
+                span,
+            })) => Ok((
+                ForVar {
+                    name,
+                    var_type: None,
+                    declared: false,
+                    span,
+                },
+                None,
+            )),
+            Some(ForInit::Expression(Expression {
+                kind:
+                    ExpressionKind::Assign {
+                        op: AssignOp::Assign,
+                        target,
+                        value,
+                    },
+                span,
+            })) if matches!(target.kind, ExpressionKind::Ident(_)) => {
+                let ExpressionKind::Ident(name) = target.kind else {
+                    unreachable!()
+                };
+                Ok((
+                    ForVar {
+                        name,
+                        var_type: None,
+                        declared: false,
+                        span,
+                    },
+                    Some(*value),
+                ))
+            }
+            _ => {
+                error!("expected a loop variable in `for`");
+                Err(located_error(ParseError::INVALID_IDENTIFIER, open))
+            }
+        }
+    }
+
+    /// The rest of `start to end step n)` after the `to`, including the `)`.
+    fn parse_range_rest(&mut self) -> Result<(Expression, Option<Expression>), ParseError> {
+        let end = self.parse_expression()?;
+        let step = if self.eat_ident("step") {
+            Some(self.parse_expression()?)
+        } else {
+            None
+        };
+        self.expect_punct(")")?;
+        Ok((end, step))
+    }
+
+    fn parse_switch(&mut self) -> Result<StatementKind, ParseError> {
+        self.next(); // switch
+        let value = self.parse_condition()?;
+        let mut cases = vec![];
+        let mut default = None;
+        self.parse_block_entries(|parser| {
+            let start = parser.span_here();
+            if parser.eat_ident("else") {
+                default = Some(parser.parse_body()?);
+                return Ok(());
+            }
+            if !parser.eat_ident("if") {
+                error!(
+                    "expected `if` or `else` in `switch`, found {}",
+                    parser.describe_next()
+                );
+                return Err(parser.error_here(ParseError::EXPECTED_DIFFERENT_TOKEN));
+            }
+
+            parser.expect_punct("(")?;
+            let mut values = vec![];
+            loop {
+                let value = parser.parse_expression()?;
+                if parser.eat_ident("to") {
+                    values.push(CaseValue::Range(value, parser.parse_expression()?));
+                } else {
+                    values.push(CaseValue::Single(value));
+                }
+                if !parser.eat_punct(",") {
+                    break;
+                }
+            }
+            parser.expect_punct(")")?;
+            let span = start.to(&parser.previous_span());
+            cases.push(SwitchCase {
+                values,
+                body: parser.parse_body()?,
+                span,
+            });
+            Ok(())
+        })?;
+        Ok(StatementKind::Switch {
+            value,
+            cases,
+            default,
+        })
+    }
+
+    fn parse_catch(&mut self) -> Result<Option<Catch>, ParseError> {
+        self.eat_newline_before("catch");
+        if !self.eat_ident("catch") {
+            return Ok(None);
+        }
+
+        let mut var = None;
+        if self.eat_punct("(") && !self.eat_punct(")") {
+            if self.at_ident("var") {
+                var = Some(self.parse_local_var()?);
+            } else {
+                let (name, span) = self.expect_ident()?;
+                var = Some(LocalVar {
+                    name,
+                    modifiers: VarModifiers::default(),
+                    var_type: None,
+                    dimensions: vec![],
+                    value: None,
+                    span,
+                });
+            }
+            self.expect_punct(")")?;
+        }
+        Ok(Some(Catch {
+            var,
+            body: self.parse_body()?,
+        }))
+    }
+
+    /// `set name = "Say"` or `set src in view()`
+    fn parse_set(&mut self) -> Result<StatementKind, ParseError> {
+        self.next(); // set
+        let (name, _) = self.expect_ident()?;
+        let is_in = if self.eat_ident("in") {
+            true
+        } else {
+            self.expect_punct("=")?;
+            false
+        };
+        Ok(StatementKind::Set {
+            name,
+            value: self.parse_expression()?,
+            is_in,
+        })
+    }
+
+    /// `var/a = 1, b = 2` or a block of declarations below `var` or a path after it, such as
+    ///
+    /// ```text
+    /// var/obj/item
+    ///     sword = null
+    ///     list/spares[2]
+    /// ```
+    fn parse_local_vars(&mut self) -> Result<Vec<LocalVar>, ParseError> {
+        let start = self.span_here();
+        self.next(); // var
+        let mut path = vec![];
+        while self.eat_punct("/") {
+            path.push(self.expect_ident()?.0);
+        }
+        let mut vars = vec![];
+        if self.at_any_block() {
+            self.parse_local_var_block(&path, &mut vars)?;
+            return Ok(vars);
+        }
+        vars.push(self.parse_local_var_rest(start, &path)?);
+        self.parse_more_local_vars(&[], &mut vars)?;
+        Ok(vars)
+    }
+
+    /// The entries of a `var` block, each a path below `prefix` that may have a block of its own.
+    fn parse_local_var_block(
+        &mut self,
+        prefix: &[Symbol],
+        vars: &mut Vec<LocalVar>,
+    ) -> Result<(), ParseError> {
+        self.parse_block_entries(|parser| {
+            let start = parser.span_here();
+            let path = parser.parse_local_var_path(prefix)?;
+            if parser.at_any_block() {
+                return parser.parse_local_var_block(&path, vars);
+            }
+            vars.push(parser.parse_local_var_rest(start, &path)?);
+            parser.parse_more_local_vars(prefix, vars)
+        })
+    }
+
+    /// The declarations after a `,`, with paths below `prefix`.
+    fn parse_more_local_vars(
+        &mut self,
+        prefix: &[Symbol],
+        vars: &mut Vec<LocalVar>,
+    ) -> Result<(), ParseError> {
+        while self.eat_punct(",") {
+            let start = self.span_here();
+            let path = self.parse_local_var_path(prefix)?;
+            vars.push(self.parse_local_var_rest(start, &path)?);
+        }
+        Ok(())
+    }
+
+    /// `obj/item/sword` appended to `prefix`.
+    fn parse_local_var_path(&mut self, prefix: &[Symbol]) -> Result<Vec<Symbol>, ParseError> {
+        let mut path = prefix.to_vec();
+        self.eat_punct("/");
+        path.push(self.expect_ident()?.0);
+        while self.eat_punct("/") {
+            path.push(self.expect_ident()?.0);
+        }
+        Ok(path)
+    }
+
+    /// `var/const/obj/item/I[2] = value`
+    fn parse_local_var(&mut self) -> Result<LocalVar, ParseError> {
+        let start = self.span_here();
+        self.next(); // var
+        let mut path = vec![];
+        while self.eat_punct("/") {
+            path.push(self.expect_ident()?.0);
+        }
+        self.parse_local_var_rest(start, &path)
+    }
+
+    /// The declaration after its path, which holds the modifiers, the type and the name.
+    fn parse_local_var_rest(
+        &mut self,
+        start: Span,
+        path: &[Symbol],
+    ) -> Result<LocalVar, ParseError> {
+        let mut modifiers = VarModifiers::default();
+        let mut rest = path;
+        while let Some((first, remaining)) = rest.split_first() {
+            if remaining.is_empty() || !modifiers.set(first.as_str()) {
+                break;
+            }
+            rest = remaining;
+        }
+        let Some((name, var_type)) = rest.split_last() else {
+            error!("`var` without a name");
+            return Err(self.error_here(ParseError::INVALID_IDENTIFIER));
+        };
+
+        let mut dimensions = vec![];
+        while self.eat_punct("[") {
+            if self.eat_punct("]") {
+                dimensions.push(None);
+                continue;
+            }
+            dimensions.push(Some(
+                self.with_restrictions(Restrictions::default(), Self::parse_expression)?,
+            ));
+            self.expect_punct("]")?;
+        }
+
+        let mut value = None;
+        if self.eat_punct("=") {
+            value = Some(self.parse_expression()?);
+        }
+        // `as` on a local var only matters to input(), the types are not kept
+        self.parse_input_types();
+
+        Ok(LocalVar {
+            name: *name,
+            modifiers,
+            var_type: (!var_type.is_empty()).then(|| DmTypePath::from_parts(var_type)),
+            dimensions,
+            value,
+            span: start.to(&self.previous_span()),
+        })
+    }
+}
@@ -0,0 +1,166 @@
+use log::error;
+
+use crate::{
+    tokens::span::Span,
+    util::{symbol::Symbol, ParseError},
+};
+
+use super::lexer::{located_error, Lexeme, LexemeKind};
+
+/// Operators an expression may not use because they mean something else where it appears.
+#[derive(Debug, Clone, Copy, Default)]
+pub(super) struct Restrictions {
+    /// `in` ends the expression, as in a parameter's `in` list or after `locate()`.
+    pub no_in: bool,
+    /// `:` ends the expression instead of being a field access, as in the true branch of `?:`.
+    pub no_colon: bool,
+}
+
+/// Walks the lexemes of a single file and builds its AST.
+pub struct SyntaxParser {
+    lexemes: Vec<Lexeme>,
+    position: usize,
+    pub(super) restrictions: Restrictions,
+}
+
+impl SyntaxParser {
+    pub fn new(lexemes: Vec<Lexeme>) -> Self {
+        Self {
+            lexemes,
+            position: 0,
+            restrictions: Restrictions::default(),
+        }
+    }
+
+    /// Runs `parse` with `restrictions`, restoring the previous ones afterwards.
+    pub(super) fn with_restrictions<T>(
+        &mut self,
+        restrictions: Restrictions,
+        parse: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        let previous = std::mem::replace(&mut self.restrictions, restrictions);
+        let result = parse(self);
+        self.restrictions = previous;
+        result
+    }
+
+    pub fn is_at_end(&self) -> bool {
+        self.position >= self.lexemes.len()
+    }
+
+    pub(super) fn peek(&self) -> Option<&Lexeme> {
+        self.lexemes.get(self.position)
+    }
+
+    pub(super) fn peek_nth(&self, n: usize) -> Option<&Lexeme> {
+        self.lexemes.get(self.position + n)
+    }
+
+    pub(super) fn next(&mut self) -> Option<Lexeme> {
+        let lexeme = self.lexemes.get(self.position).cloned();
+        if lexeme.is_some() {
+            self.position += 1;
+        }
+        lexeme
+    }
+
+    pub(super) fn at_punct(&self, punct: &str) -> bool {
+        self.peek().is_some_and(|lexeme| lexeme.is_punct(punct))
+    }
+
+    pub(super) fn at_ident(&self, ident: &str) -> bool {
+        self.peek().is_some_and(|lexeme| lexeme.is_ident(ident))
+    }
+
+    /// True at a line break, a `;` or at the end of a block.
+    pub(super) fn at_line_end(&self) -> bool {
+        match self.peek() {
+            None => true,
+            Some(lexeme) => {
+                matches!(
+                    lexeme.kind,
+                    LexemeKind::Newline | LexemeKind::Indent | LexemeKind::Dedent
+                ) || lexeme.is_punct(";")
+                    || lexeme.is_punct("}")
+            }
+        }
+    }
+
+    pub(super) fn eat_punct(&mut self, punct: &str) -> bool {
+        if self.at_punct(punct) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    pub(super) fn eat_ident(&mut self, ident: &str) -> bool {
+        if self.at_ident(ident) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    pub(super) fn expect_punct(&mut self, punct: &str) -> Result<Span, ParseError> {
+        if self.at_punct(punct) {
+            return Ok(self.next().unwrap().span);
+        }
+        error!("expected `{punct}`, found {}", self.describe_next());
+        Err(self.error_here(ParseError::EXPECTED_DIFFERENT_TOKEN))
+    }
+
+    pub(super) fn expect_ident(&mut self) -> Result<(Symbol, Span), ParseError> {
+        if let Some(ident) = self.peek().and_then(Lexeme::ident) {
+            let span = self.next().unwrap().span;
+            return Ok((ident, span));
+        }
+        error!("expected an identifier, found {}", self.describe_next());
+        Err(self.error_here(ParseError::INVALID_IDENTIFIER))
+    }
+
+    pub(super) fn describe_next(&self) -> String {
+        self.peek()
+            .map(|lexeme| format!("`{lexeme}`"))
+            .unwrap_or_else(|| "end of file".into())
+    }
+
+    /// The span of the next lexeme, or of the last one at the end of the file.
+    pub(super) fn span_here(&self) -> Span {
+        self.peek()
+            .or_else(|| self.lexemes.last())
+            .map(|lexeme| lexeme.span.clone())
+            .unwrap_or_default()
+    }
+
+    /// The span of the last lexeme consumed.
+    pub(super) fn previous_span(&self) -> Span {
+        self.position
+            .checked_sub(1)
+            .and_then(|index| self.lexemes.get(index))
+            .map(|lexeme| lexeme.span.clone())
+            .unwrap_or_default()
+    }
+
+    /// True right after an indented or `{}` block was closed, which also ends the line of its
+    /// statement.
+    pub(super) fn after_block_end(&self) -> bool {
+        self.position
+            .checked_sub(1)
+            .and_then(|index| self.lexemes.get(index))
+            .is_some_and(|lexeme| lexeme.kind == LexemeKind::Dedent || lexeme.is_punct("}"))
+    }
+
+    pub(super) fn error_here(&self, error: ParseError) -> ParseError {
+        located_error(error, &self.span_here())
+    }
+
+    /// True when the line ends and the next line is indented further.
+    pub(super) fn at_block_start(&self) -> bool {
+        self.peek()
+            .is_some_and(|lexeme| lexeme.kind == LexemeKind::Newline)
+            && self
+                .peek_nth(1)
+                .is_some_and(|lexeme| lexeme.kind == LexemeKind::Indent)
+    }
+}
@@ -1,15 +1,13 @@
-use std::{fmt::Display, iter::Peekable};
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
 
 use log::error;
 
-use crate::{
-    tokens::dm_token::DmToken,
-    util::{is_valid_identifier, ParseError},
-};
+use crate::util::{is_valid_identifier, symbol::Symbol, ParseError};
 
-#[derive(Clone)]
+/// Parts are interned, so comparing and hashing paths only compares symbol ids.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub struct DmTypePath {
-    parts: Vec<String>,
+    parts: Vec<Symbol>,
 }
 
 impl From<&String> for DmTypePath {
@@ -21,72 +19,274 @@ impl From<&String> for DmTypePath {
 impl From<&str> for DmTypePath {
     fn from(value: &str) -> Self {
         Self {
-            parts: value.split('/').map(|str| str.to_string()).collect(),
+            parts: value
+                .split('/')
+                .filter(|part| !part.is_empty())
+                .map(Symbol::intern)
+                .collect(),
         }
     }
 }
 
+impl FromStr for DmTypePath {
+    type Err = ParseError;
+
+    /// Parses `/obj/item`, `obj/item` or `/`, every part must be a valid identifier.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let trimmed = value.strip_prefix('/').unwrap_or(value);
+        if trimmed.is_empty() {
+            return Ok(Self::default());
+        }
+        let mut parts = vec![];
+        for part in trimmed.split('/') {
+            if !is_valid_identifier(part) {
+                error!(
+                    "`{}` in type path `{value}` is not a valid ident",
+                    part.escape_debug()
+                );
+                return Err(ParseError::INVALID_IDENTIFIER);
+            }
+            parts.push(Symbol::intern(part));
+        }
+        Ok(Self { parts })
+    }
+}
+
 impl Display for DmTypePath {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "/{}", self.parts.join("/"))
+        if self.is_root() {
+            return write!(f, "/");
+        }
+        for part in &self.parts {
+            write!(f, "/{part}")?;
+        }
+        Ok(())
     }
 }
 
-impl DmTypePath {
-    pub fn parts(&self) -> &Vec<String> {
-        &self.parts
+/// Paths sort by their parts' text, so a type comes right before its subtypes.
+impl Ord for DmTypePath {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.parts
+            .iter()
+            .map(|part| part.as_str())
+            .cmp(other.parts.iter().map(|part| part.as_str()))
+    }
+}
+
+impl PartialOrd for DmTypePath {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The segments of a declaration path that change what the parts after them mean.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathKeyword {
+    Var,
+    Proc,
+    Verb,
+}
+
+impl PathKeyword {
+    pub fn from_part(part: Symbol) -> Option<Self> {
+        match part.as_str() {
+            "var" => Some(Self::Var),
+            "proc" => Some(Self::Proc),
+            "verb" => Some(Self::Verb),
+            _ => None,
+        }
+    }
+}
+
+/// How to get from one path to another, going up `up` levels and then down through `down`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelativeTypePath {
+    pub up: usize,
+    pub down: Vec<Symbol>,
+}
+
+impl Display for RelativeTypePath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut segments: Vec<&str> = vec![".."; self.up];
+        segments.extend(self.down.iter().map(|part| part.as_str()));
+        if segments.is_empty() {
+            return write!(f, ".");
+        }
+        write!(f, "{}", segments.join("/"))
     }
+}
 
-    pub fn parent(&self) -> Self {
+impl DmTypePath {
+    pub fn from_parts(parts: impl Into<Vec<Symbol>>) -> Self {
         Self {
-            parts: self.parts.iter().cloned().rev().skip(1).rev().collect(),
+            parts: parts.into(),
         }
     }
 
+    pub fn parts(&self) -> &[Symbol] {
+        &self.parts
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.parts.is_empty()
+    }
+
+    /// The number of parts, `0` for the root.
+    pub fn depth(&self) -> usize {
+        self.parts.len()
+    }
+
+    /// The last part, `None` for the root.
+    pub fn name(&self) -> Option<Symbol> {
+        self.parts.last().copied()
+    }
+
+    /// The path one level up, `None` for the root.
+    pub fn parent(&self) -> Option<Self> {
+        self.split_last().map(|(parent, _)| parent)
+    }
+
+    /// The parent and the last part, `None` for the root.
+    pub fn split_last(&self) -> Option<(Self, Symbol)> {
+        let (name, parent) = self.parts.split_last()?;
+        Some((Self::from_parts(parent), *name))
+    }
+
     pub fn join(&self, other: &Self) -> Self {
+        let mut parts = Vec::with_capacity(self.parts.len() + other.parts.len());
+        parts.extend_from_slice(&self.parts);
+        parts.extend_from_slice(&other.parts);
+        Self { parts }
+    }
+
+    pub fn child(&self, part: Symbol) -> Self {
         let mut parts = self.parts.clone();
-        parts.extend(other.parts.iter().cloned());
+        parts.push(part);
         Self { parts }
     }
-}
 
-impl DmTypePath {
-    pub fn consume_from_tokens(
-        tokens: &mut Peekable<impl Iterator<Item = DmToken>>,
-    ) -> Result<DmTypePath, ParseError> {
-        let mut parts = vec![];
+    /// True if this path is `ancestor` or below it, judged by the parts alone. `parent_type` is
+    /// only known to the object tree, see `ObjectTree::is_subtype_of`.
+    pub fn is_subtype_of(&self, ancestor: &Self) -> bool {
+        self.parts.starts_with(&ancestor.parts)
+    }
 
-        // important to note that typepaths CAN start with `/` but are not required to
-        if tokens.peek().is_some_and(|t| t.value() == "/") {
-            tokens.next();
-        }
+    /// The deepest path both paths are below.
+    pub fn common_ancestor(&self, other: &Self) -> Self {
+        let shared = self
+            .parts
+            .iter()
+            .zip(&other.parts)
+            .take_while(|(a, b)| a == b)
+            .count();
+        Self::from_parts(&self.parts[..shared])
+    }
 
-        loop {
-            let token = tokens.peek();
-            if token.is_none() {
-                return Err(ParseError::UNEXPECTED_EOL);
-            }
-            let token = tokens.next().unwrap().value().to_string();
-            if !is_valid_identifier(&token) {
-                error!(
-                    "failed to consume type path. `{}` is not a valid ident.",
-                    token.escape_debug()
-                );
-                error!("next 7 tokens: {:#?}", tokens.take(7).collect::<Vec<_>>());
-                return Err(ParseError::INVALID_IDENTIFIER);
-            }
-            parts.push(token);
+    /// The parts after `ancestor`, `None` if this path is not below it.
+    pub fn strip_prefix(&self, ancestor: &Self) -> Option<Self> {
+        self.parts
+            .strip_prefix(ancestor.parts.as_slice())
+            .map(Self::from_parts)
+    }
 
-            if !tokens
-                .peek()
-                .is_some_and(|tok| matches!(tok.value(), "/" | "."))
-            {
-                break;
-            }
-            tokens.next(); // consume the path seperator
+    /// How to get to this path from `from`.
+    pub fn relative_to(&self, from: &Self) -> RelativeTypePath {
+        let common = self.common_ancestor(from);
+        RelativeTypePath {
+            up: from.depth() - common.depth(),
+            down: self.parts[common.depth()..].to_vec(),
         }
+    }
+
+    /// The first `var`, `proc` or `verb` part and its index.
+    pub fn find_keyword(&self) -> Option<(usize, PathKeyword)> {
+        self.parts
+            .iter()
+            .enumerate()
+            .find_map(|(index, part)| Some((index, PathKeyword::from_part(*part)?)))
+    }
+
+    /// True if the path goes through `var`, `proc` or `verb`, so it is not a type.
+    pub fn has_keyword(&self) -> bool {
+        self.find_keyword().is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn type_path_from_str_interns_parts() {
+        let path = DmTypePath::from("/obj/item");
+        assert_eq!(
+            path.parts(),
+            &[Symbol::intern("obj"), Symbol::intern("item")]
+        );
+        assert_eq!(path, DmTypePath::from("obj/item"));
+        assert_eq!(path.to_string(), "/obj/item");
+    }
+
+    #[test]
+    fn type_path_join() {
+        let joined = DmTypePath::from("/obj").join(&DmTypePath::from("item/weapon"));
+        assert_eq!(joined, DmTypePath::from("/obj/item/weapon"));
+        assert_eq!(joined.parent(), Some(DmTypePath::from("/obj/item")));
+        assert_eq!(DmTypePath::default().parent(), None);
+    }
+
+    #[test]
+    fn type_path_display_and_parse() {
+        assert_eq!(DmTypePath::default().to_string(), "/");
+        assert_eq!("/".parse::<DmTypePath>().unwrap(), DmTypePath::default());
+        assert_eq!(
+            "obj/item".parse::<DmTypePath>().unwrap(),
+            DmTypePath::from("/obj/item")
+        );
+        assert!("/obj//item".parse::<DmTypePath>().is_err());
+        assert!("/obj/1item".parse::<DmTypePath>().is_err());
+        assert!("/obj/it em".parse::<DmTypePath>().is_err());
+    }
+
+    #[test]
+    fn type_path_algebra() {
+        let sword = DmTypePath::from("/obj/item/weapon/sword");
+        let machine = DmTypePath::from("/obj/machine");
+        assert!(sword.is_subtype_of(&DmTypePath::from("/obj/item")));
+        assert!(sword.is_subtype_of(&sword));
+        assert!(sword.is_subtype_of(&DmTypePath::default()));
+        assert!(!DmTypePath::from("/obj/itemized").is_subtype_of(&DmTypePath::from("/obj/item")));
+
+        assert_eq!(sword.common_ancestor(&machine), DmTypePath::from("/obj"));
+        assert_eq!(
+            sword.strip_prefix(&DmTypePath::from("/obj")),
+            Some(DmTypePath::from("/item/weapon/sword"))
+        );
+        assert_eq!(sword.strip_prefix(&machine), None);
+        assert_eq!(
+            sword.relative_to(&machine).to_string(),
+            "../item/weapon/sword"
+        );
+        assert_eq!(machine.relative_to(&sword).to_string(), "../../../machine");
+        assert_eq!(sword.relative_to(&sword).to_string(), ".");
+
+        let mut paths = [
+            DmTypePath::from("/obj/item"),
+            DmTypePath::from("/mob"),
+            DmTypePath::default(),
+            DmTypePath::from("/obj"),
+        ];
+        paths.sort();
+        let paths: Vec<String> = paths.iter().map(ToString::to_string).collect();
+        assert_eq!(paths, vec!["/", "/mob", "/obj", "/obj/item"]);
+    }
 
-        assert!(!parts.is_empty(), "empty parts after loop");
-        Ok(DmTypePath { parts })
+    #[test]
+    fn type_path_keywords() {
+        let path = DmTypePath::from("/obj/item/proc/attack");
+        assert_eq!(path.find_keyword(), Some((2, PathKeyword::Proc)));
+        assert!(DmTypePath::from("/obj/var/x").has_keyword());
+        assert!(!DmTypePath::from("/obj/verbose").has_keyword());
     }
 }
@@ -0,0 +1,177 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{channel, sync_channel, Receiver, Sender, SyncSender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+use log::{debug, trace};
+
+use crate::{
+    tokens::{dm_token::DmToken, tokenize::DmTokenizer},
+    util::{dm_file::DmFile, ParseError},
+};
+
+type TokenizeResult = Result<Vec<DmToken>, ParseError>;
+
+struct TokenizeJob {
+    path: PathBuf,
+    result: SyncSender<TokenizeResult>,
+}
+
+/// Reads and tokenizes upcoming files on worker threads.
+/// Tokenizing does not depend on the define state, so it can run ahead of the preprocessor.
+pub struct TokenizePrefetcher {
+    jobs: Option<Sender<TokenizeJob>>,
+    workers: Vec<JoinHandle<()>>,
+    /// Files that were requested but not handed to a worker yet, in the order they will be needed.
+    queued: VecDeque<PathBuf>,
+    in_flight: HashMap<PathBuf, Receiver<TokenizeResult>>,
+    /// How many files may be tokenized ahead of the preprocessor at once.
+    window: usize,
+}
+
+impl TokenizePrefetcher {
+    /// A prefetcher with no threads does nothing and every file is tokenized when it is loaded.
+    pub fn new(environment_directory: &Path, threads: usize) -> Self {
+        let (sender, receiver) = channel::<TokenizeJob>();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        let workers = (0..threads)
+            .map(|index| {
+                let receiver = receiver.clone();
+                let environment_directory = environment_directory.to_path_buf();
+                thread::Builder::new()
+                    .name(format!("tokenize-{index}"))
+                    .spawn(move || Self::work(&environment_directory, &receiver))
+                    .expect("failed to spawn tokenize worker")
+            })
+            .collect::<Vec<_>>();
+        debug!("tokenize prefetch with {} threads", workers.len());
+
+        Self {
+            jobs: (threads > 0).then_some(sender),
+            workers,
+            queued: VecDeque::new(),
+            in_flight: HashMap::new(),
+            window: threads * 4,
+        }
+    }
+
+    fn work(environment_directory: &Path, jobs: &Mutex<Receiver<TokenizeJob>>) {
+        loop {
+            let job = jobs.lock().expect("tokenize job queue poisoned").recv();
+            let Ok(job) = job else {
+                return; // the prefetcher was dropped
+            };
+
+            trace!("prefetching `{}`", job.path.display());
+            let tokenized = catch_unwind(AssertUnwindSafe(|| {
+                let file = DmFile::new(environment_directory, &job.path)?;
+                Ok(DmTokenizer::from_file(&file).collect())
+            }));
+            // a failed tokenize drops the sender, the file is then tokenized again on the
+            // parsing thread so the failure is reported in order
+            if let Ok(tokenized) = tokenized {
+                job.result.send(tokenized).ok();
+            }
+        }
+    }
+
+    /// Queues files to be tokenized ahead of time. They will be needed before anything queued earlier.
+    pub fn request(&mut self, paths: impl IntoIterator<Item = PathBuf>) {
+        if self.jobs.is_none() {
+            return;
+        }
+
+        let paths: Vec<PathBuf> = paths
+            .into_iter()
+            .filter(|path| !self.in_flight.contains_key(path) && !self.queued.contains(path))
+            .collect();
+        for path in paths.into_iter().rev() {
+            self.queued.push_front(path);
+        }
+        self.dispatch();
+    }
+
+    fn dispatch(&mut self) {
+        let Some(jobs) = &self.jobs else {
+            return;
+        };
+
+        while self.in_flight.len() < self.window {
+            let Some(path) = self.queued.pop_front() else {
+                break;
+            };
+            let (sender, receiver) = sync_channel(1);
+            let job = TokenizeJob {
+                path: path.clone(),
+                result: sender,
+            };
+            if jobs.send(job).is_err() {
+                break;
+            }
+            self.in_flight.insert(path, receiver);
+        }
+    }
+
+    /// Takes the tokens of a prefetched file, waiting for them if they are still being tokenized.
+    /// Returns `None` if the file was not prefetched.
+    pub fn take(&mut self, path: &Path) -> Option<TokenizeResult> {
+        if let Some(index) = self.queued.iter().position(|queued| queued == path) {
+            self.queued.remove(index);
+        }
+
+        let receiver = self.in_flight.remove(path);
+        self.dispatch();
+        receiver?.recv().ok()
+    }
+}
+
+impl Drop for TokenizePrefetcher {
+    fn drop(&mut self) {
+        self.jobs = None;
+        self.in_flight.clear();
+        for worker in self.workers.drain(..) {
+            worker.join().ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn prefetched_tokens_match_direct_tokenizing() {
+        let directory = std::env::temp_dir().join(format!("lies_prefetch_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let files = ["a.dm", "b.dm", "c.dm"];
+        for (index, name) in files.iter().enumerate() {
+            fs::write(
+                directory.join(name),
+                format!("/obj/var/v{index} = \"[{index}]\"\n"),
+            )
+            .unwrap();
+        }
+
+        let mut prefetcher = TokenizePrefetcher::new(&directory, 2);
+        prefetcher.request(files.iter().map(PathBuf::from));
+        for name in files {
+            let prefetched = prefetcher.take(Path::new(name)).unwrap().unwrap();
+            let file = DmFile::new(&directory, name).unwrap();
+            let direct: Vec<DmToken> = DmTokenizer::from_file(&file).collect();
+            assert_eq!(prefetched, direct);
+        }
+        assert!(prefetcher.take(Path::new("never_requested.dm")).is_none());
+
+        drop(prefetcher);
+        fs::remove_dir_all(&directory).ok();
+    }
+}
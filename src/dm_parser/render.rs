@@ -0,0 +1,219 @@
+use std::{collections::BTreeMap, fmt::Write, sync::Arc};
+
+use crate::tokens::{source_text::SourceText, span::Span};
+
+use super::diagnostics::{Diagnostic, Severity};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+/// The gutter and the labels of secondary spans.
+const BLUE: &str = "\x1b[1;34m";
+
+/// A span to underline, with the text written after the underline.
+struct Label<'a> {
+    span: &'a Span,
+    text: &'a str,
+    primary: bool,
+}
+
+/// Renders a diagnostic the way rustc does, with the source lines of its span and of its notes:
+///
+/// ```text
+/// warning[shadowed_local]: `count` shadows a param
+///  --> test.dm:5:9
+///   |
+/// 1 | /proc/test(obj/item/I, count)
+///   |                        ----- declared here
+/// ...
+/// 5 |     var/count = 3
+///   |         ^^^^^
+/// ```
+///
+/// Notes without a known span and the help are listed below the snippets. With `color` the
+/// output uses ANSI colours.
+pub fn render(diagnostic: &Diagnostic, color: bool) -> String {
+    let style = Style { color };
+    let severity_color = severity_color(diagnostic.severity);
+    let mut output = format!(
+        "{}{}",
+        style.paint(
+            severity_color,
+            &format!("{}[{}]", diagnostic.severity, diagnostic.code)
+        ),
+        style.paint(BOLD, &format!(": {}", diagnostic.message)),
+    );
+
+    // code expanded from a define is reported where the define is used
+    let mut labels = vec![Label {
+        span: diagnostic.location(),
+        text: "",
+        primary: true,
+    }];
+    if diagnostic.span.expansion().is_some() {
+        labels.push(Label {
+            span: &diagnostic.span,
+            text: "macro defined here",
+            primary: false,
+        });
+    }
+    let mut notes = vec![];
+    for (span, note) in &diagnostic.notes {
+        if span.is_known() {
+            labels.push(Label {
+                span,
+                text: note,
+                primary: false,
+            });
+        } else {
+            notes.push(note.as_str());
+        }
+    }
+
+    // the labels of every file, the file of the primary span first
+    let mut files: Vec<(&Arc<SourceText>, Vec<Label>)> = vec![];
+    for label in labels {
+        let Some(source) = label.span.source() else {
+            if !label.text.is_empty() {
+                notes.push(label.text);
+            }
+            continue;
+        };
+        match files.iter_mut().find(|(file, _)| Arc::ptr_eq(file, source)) {
+            Some((_, labels)) => labels.push(label),
+            None => files.push((source, vec![label])),
+        }
+    }
+    let gutter_width = files
+        .iter()
+        .flat_map(|(_, labels)| labels)
+        .filter_map(|label| label.span.line())
+        .max()
+        .unwrap_or_default()
+        .to_string()
+        .len();
+    let gutter = " ".repeat(gutter_width);
+    let bar = style.paint(BLUE, "|");
+
+    for (index, (source, labels)) in files.iter().enumerate() {
+        let arrow = if index == 0 { "-->" } else { ":::" };
+        let location = labels
+            .iter()
+            .find(|label| label.primary)
+            .or_else(|| labels.iter().min_by_key(|label| label.span.start()))
+            .map(|label| label.span.to_string())
+            .unwrap_or_default();
+        write!(output, "\n{gutter}{} {location}", style.paint(BLUE, arrow)).unwrap();
+        write!(output, "\n{gutter} {bar}").unwrap();
+
+        let mut lines: BTreeMap<usize, Vec<&Label>> = BTreeMap::new();
+        for label in labels {
+            if let Some(line) = label.span.line() {
+                lines.entry(line).or_default().push(label);
+            }
+        }
+        let mut previous_line = None;
+        for (line, mut labels) in lines {
+            let Some(text) = source.original_line_text(line) else {
+                continue;
+            };
+            if previous_line.is_some_and(|previous| line > previous + 1) {
+                output.push_str(&format!("\n{}", style.paint(BLUE, "...")));
+            }
+            previous_line = Some(line);
+            let line_number = style.paint(BLUE, &format!("{line:>gutter_width$}"));
+            write!(output, "\n{line_number} {bar} {}", expand_tabs(text)).unwrap();
+
+            labels.sort_by_key(|label| label.span.start());
+            for label in labels {
+                let (offset, width) = underline(label.span, text);
+                let (mark, color) = if label.primary {
+                    ('^', severity_color)
+                } else {
+                    ('-', BLUE)
+                };
+                let underline = mark.to_string().repeat(width);
+                let label_text = if label.text.is_empty() {
+                    underline
+                } else {
+                    format!("{underline} {}", label.text)
+                };
+                write!(
+                    output,
+                    "\n{gutter} {bar} {}{}",
+                    " ".repeat(offset),
+                    style.paint(color, &label_text)
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    if !notes.is_empty() || !diagnostic.help.is_empty() {
+        if !files.is_empty() {
+            write!(output, "\n{gutter} {bar}").unwrap();
+        }
+        let equals = style.paint(BLUE, "=");
+        for note in notes {
+            write!(
+                output,
+                "\n{gutter} {equals} {}: {note}",
+                style.paint(BOLD, "note")
+            )
+            .unwrap();
+        }
+        for help in &diagnostic.help {
+            write!(
+                output,
+                "\n{gutter} {equals} {}: {help}",
+                style.paint(BOLD, "help")
+            )
+            .unwrap();
+        }
+    }
+    output
+}
+
+struct Style {
+    color: bool,
+}
+
+impl Style {
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.color {
+            format!("{color}{text}{RESET}")
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+fn severity_color(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "\x1b[1;31m",
+        Severity::Warning => "\x1b[1;33m",
+        Severity::Info => "\x1b[1;32m",
+        Severity::Hint => "\x1b[1;36m",
+    }
+}
+
+/// Tabs are shown as four spaces so that underlines line up in any terminal.
+fn expand_tabs(text: &str) -> String {
+    text.replace('\t', "    ")
+}
+
+fn display_width(text: &str) -> usize {
+    text.chars().map(|c| if c == '\t' { 4 } else { 1 }).sum()
+}
+
+/// Where the underline of `span` starts on its line and how wide it is, in columns of the line
+/// as rendered. A span over several lines is underlined to the end of its first line.
+fn underline(span: &Span, line_text: &str) -> (usize, usize) {
+    let column = span.line_column().map_or(1, |(_, column)| column);
+    let prefix: String = line_text.chars().take(column - 1).collect();
+    let text = span.text().unwrap_or_default();
+    let first_line = text.split('\n').next().unwrap_or_default();
+    // a `\` continuation joins the next line without a line break
+    let rest_of_line = display_width(line_text) - display_width(&prefix);
+    let width = display_width(first_line).min(rest_of_line);
+    (display_width(&prefix), width.max(1))
+}
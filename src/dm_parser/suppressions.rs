@@ -0,0 +1,152 @@
+use std::path::Path;
+
+use crate::tokens::{span::Span, tokenize::DIRECTIVE_COMMENT_PREFIX};
+
+use super::diagnostics::Diagnostic;
+
+pub const STALE_SUPPRESSION: &str = "stale_suppression";
+pub const INVALID_SUPPRESSION: &str = "invalid_suppression";
+
+/// What a suppression comment applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuppressionScope {
+    /// `// lies:ignore` at the end of the line, or `// lies:ignore-next-line` above it.
+    Line(usize),
+    /// `// lies:ignore-file(...)` anywhere in the file.
+    File,
+}
+
+/// A `// lies:` comment that silences diagnostics.
+#[derive(Debug, Clone)]
+pub struct Suppression {
+    pub span: Span,
+    pub scope: SuppressionScope,
+    /// The codes it silences, every code when empty.
+    pub codes: Vec<String>,
+}
+
+impl Suppression {
+    /// Reads a comment such as `// lies:ignore(unused_var, shadowed_local)`. Text after the
+    /// closing parenthesis is free to explain why.
+    pub fn parse(comment: &Span) -> Result<Self, String> {
+        let text = comment.text().unwrap_or_default();
+        let directive = text
+            .trim_start_matches('/')
+            .trim_start()
+            .strip_prefix(DIRECTIVE_COMMENT_PREFIX)
+            .ok_or_else(|| format!("`{text}` is not a `{DIRECTIVE_COMMENT_PREFIX}` comment"))?;
+        let name_end = directive
+            .find(|c: char| c == '(' || c.is_whitespace())
+            .unwrap_or(directive.len());
+        let (name, rest) = directive.split_at(name_end);
+        let codes = match rest.strip_prefix('(') {
+            Some(args) => {
+                let args = args
+                    .split_once(')')
+                    .map(|(args, _)| args)
+                    .ok_or_else(|| format!("unclosed `(` in `{DIRECTIVE_COMMENT_PREFIX}{name}`"))?;
+                args.split(',')
+                    .map(str::trim)
+                    .filter(|code| !code.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            }
+            None => vec![],
+        };
+
+        let line = comment.line().unwrap_or_default();
+        let scope = match name {
+            "ignore" => SuppressionScope::Line(line),
+            "ignore-next-line" => SuppressionScope::Line(line + 1),
+            "ignore-file" if codes.is_empty() => {
+                return Err(format!(
+                    "`{DIRECTIVE_COMMENT_PREFIX}ignore-file` needs the codes to ignore"
+                ))
+            }
+            "ignore-file" => SuppressionScope::File,
+            _ => {
+                return Err(format!(
+                    "unknown directive `{DIRECTIVE_COMMENT_PREFIX}{name}`"
+                ))
+            }
+        };
+        Ok(Self {
+            span: comment.clone(),
+            scope,
+            codes,
+        })
+    }
+
+    pub fn suppresses(&self, diagnostic: &Diagnostic) -> bool {
+        let location = diagnostic.location();
+        if !same_file(self.span.path(), location.path()) {
+            return false;
+        }
+        let in_scope = match self.scope {
+            SuppressionScope::Line(line) => location.line() == Some(line),
+            SuppressionScope::File => true,
+        };
+        in_scope && (self.codes.is_empty() || self.codes.iter().any(|code| code == diagnostic.code))
+    }
+}
+
+fn same_file(a: Option<&Path>, b: Option<&Path>) -> bool {
+    a.is_some() && a == b
+}
+
+/// Removes the diagnostics that `comments` suppress. Comments that are not valid suppressions
+/// and suppressions that no longer silence anything are reported in turn. A suppression of a lint
+/// in `disabled` is not stale, the lint being off is why it has nothing to silence.
+pub fn apply(
+    diagnostics: Vec<Diagnostic>,
+    comments: &[Span],
+    disabled: &[&str],
+) -> Vec<Diagnostic> {
+    let mut reported = vec![];
+    let mut suppressions = vec![];
+    for comment in comments {
+        match Suppression::parse(comment) {
+            Ok(suppression) => suppressions.push((suppression, false)),
+            Err(message) => {
+                reported.push(Diagnostic::warning(INVALID_SUPPRESSION, comment, message))
+            }
+        }
+    }
+
+    for diagnostic in diagnostics {
+        let mut suppressed = false;
+        for (suppression, used) in &mut suppressions {
+            if suppression.suppresses(&diagnostic) {
+                *used = true;
+                suppressed = true;
+            }
+        }
+        if !suppressed {
+            reported.push(diagnostic);
+        }
+    }
+
+    for (suppression, used) in suppressions {
+        let lint_off = suppression
+            .codes
+            .iter()
+            .any(|code| disabled.contains(&code.as_str()));
+        if !used && !lint_off {
+            let text = suppression
+                .span
+                .text()
+                .unwrap_or_default()
+                .trim_start_matches('/')
+                .trim();
+            reported.push(
+                Diagnostic::warning(
+                    STALE_SUPPRESSION,
+                    &suppression.span,
+                    format!("`{text}` does not suppress anything"),
+                )
+                .with_help("remove the comment"),
+            );
+        }
+    }
+    reported
+}
@@ -0,0 +1,82 @@
+use crate::dm_parser::analysis::annotations::{
+    PRIVATE_PROC, PROTECTED_PROC, SHOULD_BE_PURE, SHOULD_CALL_PARENT, SHOULD_NOT_OVERRIDE,
+};
+
+use super::object_tree::{describe, parse_files};
+
+#[test]
+fn test_annotations() {
+    let parser = parse_files(&[(
+        "test.dm",
+        &[
+            "#define SHOULD_CALL_PARENT(X) set SpacemanDMM_should_call_parent = X",
+            "/datum/proc/Destroy()",
+            "  SHOULD_CALL_PARENT(TRUE)",
+            "/datum/thing/Destroy()",
+            "  return",
+            "/datum/good/Destroy()",
+            "  . = ..()",
+            "/datum/opt_out/Destroy()",
+            "  SHOULD_CALL_PARENT(FALSE)",
+            "/datum/proc/finalize()",
+            "  set SpacemanDMM_should_not_override = TRUE",
+            "/datum/thing/finalize()",
+            "/datum/proc/secret()",
+            "  set SpacemanDMM_private_proc = TRUE",
+            "/datum/proc/family()",
+            "  set SpacemanDMM_protected_proc = TRUE",
+            "/datum/proc/insider()",
+            "  secret()",
+            "  family()",
+            "/datum/thing/proc/child()",
+            "  family()",
+            "  secret()",
+            "/client/proc/outsider(datum/D)",
+            "  D.family()",
+            "/datum/var/count = 0",
+            "/datum/proc/total()",
+            "  set SpacemanDMM_should_be_pure = TRUE",
+            "  var/sum = 1",
+            "  sum += 1",
+            "  return sum + bump()",
+            "/datum/proc/bump()",
+            "  count++",
+        ],
+    )]);
+    let mut diagnostics = parser.check();
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span.start());
+    let messages = describe(&diagnostics);
+    assert_eq!(
+        messages,
+        [
+            format!("4 {SHOULD_CALL_PARENT} `/datum/thing/proc/Destroy` does not call `..()`"),
+            format!(
+                "12 {SHOULD_NOT_OVERRIDE} `/datum/thing/proc/finalize` overrides \
+                 `/datum/proc/finalize`, which should not be overridden"
+            ),
+            format!("22 {PRIVATE_PROC} `/datum/proc/secret` is private to `/datum`"),
+            format!(
+                "24 {PROTECTED_PROC} `/datum/proc/family` is protected, only `/datum` and its \
+                 subtypes can call it"
+            ),
+            format!("26 {SHOULD_BE_PURE} `/datum/proc/total` should be pure but changes state"),
+        ]
+    );
+
+    let notes: Vec<(Option<usize>, &str)> = diagnostics[4]
+        .notes
+        .iter()
+        .map(|(span, note)| (span.line(), note.as_str()))
+        .collect();
+    assert_eq!(
+        notes,
+        [
+            (Some(30), "calls `/datum/proc/bump`, which is impure"),
+            (Some(32), "assigns to `count`"),
+            (
+                Some(26),
+                "the annotation on `/datum/proc/total` requires it"
+            ),
+        ]
+    );
+}
@@ -0,0 +1,128 @@
+use crate::dm_parser::{baseline::Baseline, diagnostics::Diagnostic};
+
+use super::object_tree::parse_files;
+
+fn diagnostics(lines: &[&str]) -> Vec<Diagnostic> {
+    parse_files(&[("code/test.dm", lines)]).diagnostics()
+}
+
+/// The messages of the diagnostics the baseline does not hide, and the number of fixed ones.
+fn new_diagnostics(baseline: &Baseline, lines: &[&str]) -> (Vec<String>, usize) {
+    let (new, fixed) = baseline.filter(diagnostics(lines));
+    let messages = new
+        .iter()
+        .map(|diagnostic| {
+            format!(
+                "{} {}",
+                diagnostic.location().line().unwrap(),
+                diagnostic.message
+            )
+        })
+        .collect();
+    (messages, fixed)
+}
+
+#[test]
+fn test_baseline_survives_line_shifts() {
+    let baseline = Baseline::from_diagnostics(&diagnostics(&[
+        "/proc/test()",
+        "  var/unused = 1",
+        "  return",
+    ]));
+    assert_eq!(baseline.len(), 1);
+
+    let (new, fixed) = new_diagnostics(
+        &baseline,
+        &[
+            "/proc/helper()",
+            "  return",
+            "",
+            "/proc/test()",
+            "    var/unused   = 1",
+            "    var/other = 2",
+            "    return",
+        ],
+    );
+    assert_eq!(new, vec!["6 unused local var `other`"]);
+    assert_eq!(fixed, 0);
+}
+
+#[test]
+fn test_baseline_counts_duplicates() {
+    let lines = [
+        "/proc/test()",
+        "  if(TRUE)",
+        "    var/unused = 1",
+        "  if(TRUE)",
+        "    var/unused = 1",
+        "  return",
+    ];
+    let baseline = Baseline::from_diagnostics(&diagnostics(&lines));
+    assert_eq!(baseline.len(), 2);
+    assert_eq!(new_diagnostics(&baseline, &lines), (vec![], 0));
+
+    let (new, fixed) = new_diagnostics(
+        &baseline,
+        &[
+            "/proc/test()",
+            "  if(TRUE)",
+            "    var/unused = 1",
+            "  if(TRUE)",
+            "    var/unused = 1",
+            "  if(TRUE)",
+            "    var/unused = 1",
+            "  return",
+        ],
+    );
+    assert_eq!(new, vec!["7 unused local var `unused`"]);
+    assert_eq!(fixed, 0);
+
+    let (new, fixed) = new_diagnostics(&baseline, &["/proc/test()", "  return"]);
+    assert!(new.is_empty());
+    assert_eq!(fixed, 2);
+}
+
+#[test]
+fn test_baseline_of_macro_use() {
+    let baseline = Baseline::from_diagnostics(&diagnostics(&[
+        "#define BAD missing",
+        "/proc/test()",
+        "  return BAD",
+    ]));
+    assert_eq!(baseline.len(), 1);
+
+    let (new, fixed) = new_diagnostics(
+        &baseline,
+        &[
+            "#define BAD missing",
+            "/proc/test()",
+            "  var/a = BAD",
+            "  return a",
+        ],
+    );
+    assert_eq!(new, vec!["3 undefined var `missing`"]);
+    assert_eq!(fixed, 1);
+}
+
+#[test]
+fn test_baseline_file_format() {
+    let baseline = Baseline::from_diagnostics(&diagnostics(&[
+        "/proc/test()",
+        "  var/unused = 1",
+        "  var/unused_too = 1",
+    ]));
+    let text = baseline.to_string();
+    let entries: Vec<&str> = text.lines().skip(1).collect();
+    assert_eq!(entries.len(), 2);
+    for entry in entries {
+        let fields: Vec<&str> = entry.split('\t').collect();
+        assert_eq!(fields[..2], ["code/test.dm", "unused_var"]);
+        assert_eq!(fields[2].len(), 16);
+    }
+    assert_eq!(Baseline::parse(&text).unwrap(), baseline);
+
+    assert_eq!(
+        Baseline::parse("code/test.dm\tunused_var"),
+        Err("invalid baseline entry on line 1: `code/test.dm\tunused_var`".to_string())
+    );
+}
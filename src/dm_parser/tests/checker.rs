@@ -0,0 +1,178 @@
+use crate::dm_parser::analysis::{
+    checker::{
+        CONST_ASSIGNMENT, TOO_MANY_ARGUMENTS, UNDEFINED_PROC, UNDEFINED_TYPE, UNDEFINED_VAR,
+        UNKNOWN_ARGUMENT,
+    },
+    types::COLON_ACCESS,
+};
+
+use super::object_tree::{describe, parse_files};
+
+fn check(lines: &[&str]) -> Vec<String> {
+    describe(&parse_files(&[("test.dm", lines)]).check())
+}
+
+#[test]
+fn test_clean_code_has_no_diagnostics() {
+    let diagnostics = check(&[
+        "var/global_count = 0",
+        "/proc/helper(a, b = 2)",
+        "  return a + b",
+        "/obj/item",
+        "  var/force = 1",
+        "  var/list/stats = list(speed = 1)",
+        "  proc/attack(mob/target, power)",
+        "    var/damage = force * power",
+        "    for(var/i in 1 to 3)",
+        "      damage += i",
+        "    try",
+        "      target.Move(loc, dir)",
+        "    catch(var/exception/e)",
+        "      world.log << e.name",
+        "    global_count++",
+        "    return helper(damage, b = usr.key)",
+        "/obj/item/sword",
+        "  force = 5",
+        "  icon_state = \"sword\"",
+        "  attack(mob/target, power)",
+        "    . = ..()",
+        "    new /obj/item(src.loc)",
+        "    stats.Add(\"sharp\")",
+        "/proc/var_block()",
+        "  var",
+        "    a = 1",
+        "    b",
+        "  var/c = a, d = c",
+        "  return a + b + d",
+    ]);
+    assert_eq!(diagnostics, Vec::<String>::new());
+}
+
+#[test]
+fn test_undefined_names() {
+    let diagnostics = check(&[
+        "/obj/item",
+        "  var/obj/item/other",
+        "  missing_var = 1",
+        "  proc/use()",
+        "    if(TRUE)",
+        "      var/inner = 1",
+        "    world.log << inner",
+        "    undefined_proc()",
+        "    other.no_such_proc()",
+        "    other:no_such_proc()",
+        "    new /obj/nothing",
+        "    var/path = /obj/item.proc/nothing",
+    ]);
+    assert_eq!(
+        diagnostics,
+        vec![
+            format!("3 {UNDEFINED_VAR} `/obj/item` has no var `missing_var` to override"),
+            format!("7 {UNDEFINED_VAR} undefined var `inner`"),
+            format!("8 {UNDEFINED_PROC} undefined proc `undefined_proc`"),
+            format!("9 {UNDEFINED_PROC} `/obj/item` has no proc `no_such_proc`"),
+            format!("10 {COLON_ACCESS} `other` is a `/obj/item` here, which has no `no_such_proc`"),
+            format!("11 {UNDEFINED_TYPE} `/obj/nothing` does not exist"),
+            format!("12 {UNDEFINED_TYPE} `/obj/item.proc/nothing` does not exist"),
+        ]
+    );
+}
+
+#[test]
+fn test_const_assignments() {
+    let diagnostics = check(&[
+        "var/const/LIMIT = 10",
+        "/obj/item",
+        "  var/const/SIZE = 2",
+        "/obj/item/big",
+        "  SIZE = 3",
+        "  proc/grow()",
+        "    var/const/step = 1",
+        "    step = 2",
+        "    LIMIT++",
+        "    src.SIZE += step",
+    ]);
+    assert_eq!(
+        diagnostics,
+        vec![
+            format!("5 {CONST_ASSIGNMENT} cannot override const var `SIZE`"),
+            format!("8 {CONST_ASSIGNMENT} cannot assign to `step`, it is a const var"),
+            format!("9 {CONST_ASSIGNMENT} cannot assign to `LIMIT`, it is a const var"),
+            format!("10 {CONST_ASSIGNMENT} cannot assign to `src.SIZE`, it is a const var"),
+        ]
+    );
+}
+
+#[test]
+fn test_call_arguments() {
+    let diagnostics = check(&[
+        "/proc/two(a, b)",
+        "/proc/anything()",
+        "  return args.len",
+        "/proc/variadic(a, ...)",
+        "/obj/item/proc/hit(target)",
+        "/obj/item/sword/hit(target, strength)",
+        "/proc/test(obj/item/I, obj/item/sword/S)",
+        "  two(1, 2, 3)",
+        "  two(1, c = 2)",
+        "  anything(1, 2, 3)",
+        "  variadic(1, 2, 3)",
+        "  two(arglist(list(1, 2, 3)))",
+        "  I.hit(1, 2)",
+        "  S.hit(1, strength = 2)",
+        "  S.hit(1, 2, 3)",
+        "  sleep(1, 2)",
+        "  max(1, 2, 3, 4)",
+    ]);
+    assert_eq!(
+        diagnostics,
+        vec![
+            format!("8 {TOO_MANY_ARGUMENTS} `two` takes at most 2 arguments but 3 were given"),
+            format!("9 {UNKNOWN_ARGUMENT} `two` has no parameter named `c`"),
+            format!("13 {TOO_MANY_ARGUMENTS} `hit` takes at most 1 argument but 2 were given"),
+            format!("15 {TOO_MANY_ARGUMENTS} `hit` takes at most 2 arguments but 3 were given"),
+            format!("16 {TOO_MANY_ARGUMENTS} `sleep` takes at most 1 argument but 2 were given"),
+        ]
+    );
+}
+
+#[test]
+fn test_member_access_types() {
+    let diagnostics = check(&[
+        "/obj/item",
+        "  var/force = 1",
+        "  var/obj/item/holder",
+        "  proc/get() as /obj/item",
+        "/obj/item/sword",
+        "  var/sharpness = 2",
+        "/proc/test(obj/item/I, atom/movable/thing, list/items)",
+        "  world.log << I.force",
+        "  world.log << I.sharpness",
+        "  world.log << I.holder.force",
+        "  world.log << I.get().missing",
+        "  world.log << new /obj/item/sword().sharpness",
+        "  if(istype(I, /obj/item/sword))",
+        "    world.log << I.sharpness",
+        "  else",
+        "    world.log << I.sharpness",
+        "  if(ismob(thing) && istype(I, /obj/item/sword))",
+        "    world.log << thing.key + I.sharpness",
+        "  for(var/obj/item/sword/S in items)",
+        "    world.log << S.sharpness",
+        "  world.log << I:sharpness",
+        "  world.log << I:nothing",
+        "  if(!istype(I, /obj/item/sword))",
+        "    return",
+        "  world.log << I.sharpness",
+    ]);
+    assert_eq!(
+        diagnostics,
+        vec![
+            format!("9 {UNDEFINED_VAR} `/obj/item` has no var `sharpness`"),
+            format!("11 {UNDEFINED_VAR} `/obj/item` has no var `missing`"),
+            format!("16 {UNDEFINED_VAR} `/obj/item` has no var `sharpness`"),
+            format!("21 {COLON_ACCESS} `I` is a `/obj/item` here, which has no `sharpness`"),
+            format!("22 {COLON_ACCESS} `I` is a `/obj/item` here, which has no `nothing`"),
+        ]
+    );
+}
@@ -0,0 +1,268 @@
+use std::error::Error;
+
+use crate::{
+    dm_parser::{
+        ast::{
+            declaration::{Declaration, ProcDeclaration, ProcKind, VarDeclaration},
+            DmAst,
+        },
+        lib::DmParser,
+        parsing::type_path::DmTypePath,
+    },
+    util::{dm_file::DmFile, symbol::Symbol},
+};
+
+pub(super) fn parse(lines: &[&str]) -> Result<DmAst, Box<dyn Error>> {
+    let mut parser = DmParser::default();
+    let file = DmFile {
+        path: "test.dm".into(),
+        lines: lines.iter().map(|s| s.to_string()).collect(),
+    };
+    parser.load_file(file)?;
+    Ok(parser.asts().last().expect("no ast was parsed").clone())
+}
+
+fn procs(ast: &DmAst) -> Vec<&ProcDeclaration> {
+    ast.iter_declarations()
+        .filter_map(|declaration| match declaration {
+            Declaration::Proc(proc) => Some(proc.as_ref()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn vars(ast: &DmAst) -> Vec<&VarDeclaration> {
+    ast.iter_declarations()
+        .filter_map(|declaration| match declaration {
+            Declaration::Var(var) => Some(var.as_ref()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_parse_scopes() -> Result<(), Box<dyn Error>> {
+    let ast = parse(&[
+        "/proc/global_proc(param, var/byond_style_param, atom/typed_param = new, var/atom/byond_style_typed_param = new /atom/gay)",
+        "  return",
+        "/obj",
+        "  var",
+        "    obj_var = 2",
+        "  proc",
+        "    obj_proc()",
+        "      return",
+        "  subtype",
+        "    var",
+        "      subtype_var = 5",
+        "    proc/do_thing()",
+        "      return",
+        "    proc",
+        "      subtype_proc()",
+        "        return",
+        "    obj_proc()",
+        "      return",
+        "/turf/icon_state = \"state_here[2]\" + 45",
+    ])?;
+
+    let procs: Vec<(String, &str, Option<ProcKind>)> = procs(&ast)
+        .into_iter()
+        .map(|proc| {
+            (
+                proc.owner.to_string(),
+                proc.name.as_str(),
+                proc.declared_kind,
+            )
+        })
+        .collect();
+    assert_eq!(
+        procs,
+        vec![
+            ("/".into(), "global_proc", Some(ProcKind::Proc)),
+            ("/obj".into(), "obj_proc", Some(ProcKind::Proc)),
+            ("/obj/subtype".into(), "do_thing", Some(ProcKind::Proc)),
+            ("/obj/subtype".into(), "subtype_proc", Some(ProcKind::Proc)),
+            ("/obj/subtype".into(), "obj_proc", None),
+        ]
+    );
+
+    let vars: Vec<(String, &str, bool)> = vars(&ast)
+        .into_iter()
+        .map(|var| (var.owner.to_string(), var.name.as_str(), var.is_declaration))
+        .collect();
+    assert_eq!(
+        vars,
+        vec![
+            ("/obj".into(), "obj_var", true),
+            ("/obj/subtype".into(), "subtype_var", true),
+            ("/turf".into(), "icon_state", false),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_parse_proc_params() -> Result<(), Box<dyn Error>> {
+    let ast = parse(&[
+        "/proc/global_proc(param, var/byond_style_param, atom/typed_param = new, var/atom/byond_style_typed_param = new /atom/gay)",
+        "/mob/verb/say(message as text, mob/target in view(src), ...)",
+        "  set name = \"Say\"",
+        "/mob/proc/get_item() as /obj/item",
+        "  return",
+    ])?;
+    let procs = procs(&ast);
+
+    let params: Vec<(&str, Option<String>, bool)> = procs[0]
+        .params
+        .iter()
+        .map(|param| {
+            (
+                param.name.as_str(),
+                param.var_type.as_ref().map(ToString::to_string),
+                param.default.is_some(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        params,
+        vec![
+            ("param", None, false),
+            ("byond_style_param", None, false),
+            ("typed_param", Some("/atom".into()), true),
+            ("byond_style_typed_param", Some("/atom".into()), true),
+        ]
+    );
+
+    let say = procs[1];
+    assert_eq!(say.declared_kind, Some(ProcKind::Verb));
+    assert_eq!(say.params.len(), 2);
+    assert_eq!(say.params[0].input_types, vec![Symbol::intern("text")]);
+    assert!(say.params[1].input_list.is_some());
+    assert!(!say.body.is_empty());
+
+    assert_eq!(procs[2].return_type, Some(DmTypePath::from("/obj/item")));
+    Ok(())
+}
+
+#[test]
+fn test_parse_var_declarations() -> Result<(), Box<dyn Error>> {
+    let ast = parse(&[
+        "/obj/item",
+        "  parent_type = /obj/base",
+        "  var/global/count = 0",
+        "  var/static/list/cache",
+        "  var/tmp/obj/item/held",
+        "  var/const/MAX = 10",
+        "  var/final/id",
+        "  var/L[10]",
+        "  var/grid[2][]",
+        "  var",
+        "    tmp/a",
+        "    b = list(",
+        "      1,",
+        "      2)",
+        "var/global_thing = 1",
+    ])?;
+
+    let Declaration::Type(item) = &ast.declarations[0] else {
+        panic!("expected a type block");
+    };
+    assert_eq!(item.path, DmTypePath::from("/obj/item"));
+    let Declaration::ParentType(parent_type) = &item.declarations[0] else {
+        panic!("expected parent_type");
+    };
+    assert_eq!(parent_type.parent, DmTypePath::from("/obj/base"));
+
+    let vars = vars(&ast);
+    let names: Vec<&str> = vars.iter().map(|var| var.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "count",
+            "cache",
+            "held",
+            "MAX",
+            "id",
+            "L",
+            "grid",
+            "a",
+            "b",
+            "global_thing"
+        ]
+    );
+    assert!(vars[0].modifiers.global);
+    assert!(vars[1].modifiers.is_static);
+    assert_eq!(vars[1].var_type, Some(DmTypePath::from("/list")));
+    assert!(vars[2].modifiers.tmp);
+    assert_eq!(vars[2].var_type, Some(DmTypePath::from("/obj/item")));
+    assert!(vars[3].modifiers.is_const);
+    assert!(vars[4].modifiers.is_final);
+    assert_eq!(vars[5].dimensions.len(), 1);
+    assert!(vars[5].dimensions[0].is_some());
+    assert_eq!(vars[6].dimensions.len(), 2);
+    assert!(vars[6].dimensions[1].is_none());
+    assert!(vars[7].modifiers.tmp);
+    assert_eq!(vars[7].owner, DmTypePath::from("/obj/item"));
+    assert!(vars[8].value.is_some());
+    assert!(vars[9].owner.is_root());
+    Ok(())
+}
+
+#[test]
+fn test_parse_brace_blocks() -> Result<(), Box<dyn Error>> {
+    let ast = parse(&[
+        "/obj { var/x = 1; proc/foo() { return } }",
+        "/mob",
+        "{",
+        "  var",
+        "  {",
+        "    a; b = 2",
+        "  }",
+        "  proc/bar()",
+        "    return",
+        "  item { proc/baz() return }",
+        "}",
+        "/turf/var/y = 1; /turf/var/z = 2",
+        "/area",
+        "  proc/qux() {",
+        "    return",
+        "  }",
+        "  var/w",
+    ])?;
+
+    let procs: Vec<(String, &str)> = procs(&ast)
+        .into_iter()
+        .map(|proc| (proc.owner.to_string(), proc.name.as_str()))
+        .collect();
+    assert_eq!(
+        procs,
+        vec![
+            ("/obj".into(), "foo"),
+            ("/mob".into(), "bar"),
+            ("/mob/item".into(), "baz"),
+            ("/area".into(), "qux"),
+        ]
+    );
+
+    let vars: Vec<(String, &str)> = vars(&ast)
+        .into_iter()
+        .map(|var| (var.owner.to_string(), var.name.as_str()))
+        .collect();
+    assert_eq!(
+        vars,
+        vec![
+            ("/obj".into(), "x"),
+            ("/mob".into(), "a"),
+            ("/mob".into(), "b"),
+            ("/turf".into(), "y"),
+            ("/turf".into(), "z"),
+            ("/area".into(), "w"),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_unclosed_brace_is_an_error() {
+    assert!(parse(&["/obj {", "  var/x = 1"]).is_err());
+    assert!(parse(&["/obj", "  var/x = 1 }"]).is_err());
+}
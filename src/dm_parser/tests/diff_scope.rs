@@ -0,0 +1,119 @@
+use std::fs;
+
+use crate::dm_parser::diff_scope::DiffScope;
+
+use super::object_tree::parse_files;
+
+const DIFF: &str = "\
+diff --git a/code/test.dm b/code/test.dm
+index 1111111..2222222 100644
+--- a/code/test.dm
++++ b/code/test.dm
+@@ -2,3 +2,4 @@ /proc/test()
+   var/a = 1
+-  var/b = 2
++  var/b = 3
++  var/c = 4
+   return
+@@ -10,2 +11,0 @@
+-  var/gone = 1
+-  var/gone_too = 1
+diff --git a/code/removed.dm b/code/removed.dm
+deleted file mode 100644
+--- a/code/removed.dm
++++ /dev/null
+@@ -1 +0,0 @@
+-/obj
+";
+
+#[test]
+fn test_unified_diff() {
+    let scope = DiffScope::from_unified_diff(DIFF).unwrap();
+    assert_eq!(scope.changed_lines("code/test.dm"), [(3, 4), (12, 12)]);
+    assert_eq!(scope.changed_lines("code/removed.dm"), []);
+
+    assert_eq!(
+        DiffScope::from_unified_diff("+++ b/test.dm\n@@ -1 +a @@\n"),
+        Err("invalid hunk header on line 2".to_string())
+    );
+}
+
+#[test]
+fn test_filter_to_changed_lines() {
+    let parser = parse_files(&[(
+        "test.dm",
+        &[
+            "/proc/test()",
+            "  var/old = 1",
+            "  var/changed = 2",
+            "  var/also_old = 3",
+        ],
+    )]);
+    let scope = DiffScope::from_unified_diff(
+        "--- a/code/test.dm\n+++ b/code/test.dm\n@@ -3 +3 @@\n-  var/changed = 1\n+  var/changed = 2\n",
+    )
+    .unwrap();
+    let messages: Vec<String> = scope
+        .filter(parser.diagnostics())
+        .into_iter()
+        .map(|diagnostic| diagnostic.message)
+        .collect();
+    assert_eq!(messages, vec!["unused local var `changed`"]);
+}
+
+#[test]
+fn test_filter_by_macro_call_site() {
+    let parser = parse_files(&[
+        (
+            "defines.dm",
+            &["#define DECLARE_UNUSED(name) var/##name = 1"],
+        ),
+        (
+            "test.dm",
+            &[
+                "/proc/test()",
+                "  DECLARE_UNUSED(first)",
+                "  DECLARE_UNUSED(second)",
+            ],
+        ),
+    ]);
+    let diagnostics = parser.diagnostics();
+    assert_eq!(diagnostics.len(), 2);
+    let call_site = diagnostics[1].span.expansion().unwrap();
+    assert_eq!(call_site.to_string(), "test.dm:3:3");
+
+    let scope =
+        DiffScope::from_unified_diff("+++ b/test.dm\n@@ -2,0 +3 @@\n+  DECLARE_UNUSED(second)\n")
+            .unwrap();
+    let messages: Vec<String> = scope
+        .filter(diagnostics)
+        .into_iter()
+        .map(|diagnostic| diagnostic.message)
+        .collect();
+    assert_eq!(messages, vec!["unused local var `second`"]);
+}
+
+#[test]
+fn test_compare_trees() {
+    let directory = std::env::temp_dir().join(format!("lies_diff_scope_{}", std::process::id()));
+    let (old, new) = (directory.join("old"), directory.join("new"));
+    fs::create_dir_all(old.join("code")).unwrap();
+    fs::create_dir_all(new.join("code")).unwrap();
+    fs::write(old.join("code/same.dm"), "/obj\n").unwrap();
+    fs::write(new.join("code/same.dm"), "/obj\n").unwrap();
+    fs::write(old.join("code/test.dm"), "a\nb\nc\nd\ne\nf\n").unwrap();
+    fs::write(new.join("code/test.dm"), "a\nB\nc\nd\nf\ng\n").unwrap();
+    fs::write(new.join("code/added.dm"), "x\ny\n").unwrap();
+    fs::write(old.join("code/replaced.dm"), "a\nb\nc\nd\n").unwrap();
+    fs::write(new.join("code/replaced.dm"), "a\nB\nc\nD\n").unwrap();
+    fs::write(new.join("icons.dmi"), [0x89, b'P', b'N', b'G']).unwrap();
+
+    let scope = DiffScope::from_trees(&old, &new).unwrap();
+    assert_eq!(scope.changed_lines("code/same.dm"), []);
+    assert_eq!(scope.changed_lines("code/test.dm"), [(2, 2), (5, 6)]);
+    assert_eq!(scope.changed_lines("code/added.dm"), [(1, 2)]);
+    assert_eq!(scope.changed_lines("code/replaced.dm"), [(2, 2), (4, 4)]);
+    assert_eq!(scope.changed_lines("icons.dmi"), []);
+
+    fs::remove_dir_all(&directory).ok();
+}
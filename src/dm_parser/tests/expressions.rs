@@ -0,0 +1,242 @@
+use std::path::Path;
+
+use crate::{
+    dm_parser::{
+        ast::{
+            declaration::Declaration,
+            expression::{Argument, Expression, ExpressionKind, FieldAccess, StringSegment},
+        },
+        parsing::{lexer::Lexer, syntax::SyntaxParser},
+    },
+    dm_preprocessor::lib::DmPreProcessor,
+    tokens::tokenize::DmTokenizer,
+};
+
+use super::declarations::parse;
+
+pub(super) fn parse_expression(line: &str) -> Expression {
+    let lines = vec![line.to_string()];
+    let mut preprocessor = DmPreProcessor::new();
+    let tokens = preprocessor.preprocess_tokens(DmTokenizer::new(Path::new("test.dm"), &lines));
+    let mut lexemes = Lexer::new(tokens).lex().unwrap();
+    lexemes.pop(); // newline
+    let mut parser = SyntaxParser::new(lexemes);
+    let expression = parser.parse_expression().unwrap();
+    assert!(parser.is_at_end(), "`{line}` was not fully parsed");
+    expression
+}
+
+/// Renders an expression with explicit grouping so tests can compare structure.
+fn render(expression: &Expression) -> String {
+    let args = |args: &[Argument]| {
+        args.iter()
+            .map(|arg| match &arg.key {
+                Some(key) => format!("{}={}", render(key), render(&arg.value)),
+                None => render(&arg.value),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    match &expression.kind {
+        ExpressionKind::Null => "null".into(),
+        ExpressionKind::Number(number) => number.to_string(),
+        ExpressionKind::String(segments) => {
+            let segments: Vec<String> = segments
+                .iter()
+                .map(|segment| match segment {
+                    StringSegment::Text(text) => text.clone(),
+                    StringSegment::Expression(expression) => format!("[{}]", render(expression)),
+                })
+                .collect();
+            format!("\"{}\"", segments.join(""))
+        }
+        ExpressionKind::Resource(resource) => format!("'{resource}'"),
+        ExpressionKind::Ident(ident) => ident.to_string(),
+        ExpressionKind::Path { path, vars } if vars.is_empty() => path.to_string(),
+        ExpressionKind::Path { path, vars } => {
+            let vars: Vec<String> = vars
+                .iter()
+                .map(|(name, value)| format!("{name}={}", render(value)))
+                .collect();
+            format!("{path}{{{}}}", vars.join("; "))
+        }
+        ExpressionKind::Dot => ".".into(),
+        ExpressionKind::ParentProc => "..".into(),
+        ExpressionKind::Unary { op, operand } => format!("({op:?} {})", render(operand)),
+        ExpressionKind::Binary { op, lhs, rhs } => {
+            format!("({} {} {})", render(lhs), op.as_str(), render(rhs))
+        }
+        ExpressionKind::Assign { op, target, value } => {
+            format!("({} {op:?} {})", render(target), render(value))
+        }
+        ExpressionKind::Ternary {
+            condition,
+            if_true,
+            if_false,
+        } => format!(
+            "({} ? {} : {})",
+            render(condition),
+            render(if_true),
+            render(if_false)
+        ),
+        ExpressionKind::Field {
+            object,
+            access,
+            name,
+        } => {
+            let access = match access {
+                FieldAccess::Dot => ".",
+                FieldAccess::SafeDot => "?.",
+                FieldAccess::Colon => ":",
+            };
+            format!("{}{access}{name}", render(object))
+        }
+        ExpressionKind::StaticField { object, name } => format!("{}::{name}", render(object)),
+        ExpressionKind::Index {
+            object,
+            index,
+            safe,
+        } => format!(
+            "{}{}[{}]",
+            render(object),
+            if *safe { "?" } else { "" },
+            render(index)
+        ),
+        ExpressionKind::Call {
+            callee,
+            args: call_args,
+        } => {
+            format!("{}({})", render(callee), args(call_args))
+        }
+        ExpressionKind::New {
+            new_type,
+            args: new_args,
+        } => format!(
+            "new {}({})",
+            new_type.as_ref().map(|t| render(t)).unwrap_or_default(),
+            args(new_args)
+        ),
+        ExpressionKind::List {
+            associative_only,
+            args: list_args,
+        } => format!(
+            "{}({})",
+            if *associative_only { "alist" } else { "list" },
+            args(list_args)
+        ),
+        ExpressionKind::Locate {
+            args: locate_args,
+            in_list,
+        } => format!(
+            "locate({}){}",
+            args(locate_args),
+            in_list
+                .as_ref()
+                .map(|list| format!(" in {}", render(list)))
+                .unwrap_or_default()
+        ),
+        ExpressionKind::Input {
+            args: input_args,
+            input_types,
+            in_list,
+        } => format!(
+            "input({}) as {:?}{}",
+            args(input_args),
+            input_types.iter().map(|t| t.as_str()).collect::<Vec<_>>(),
+            in_list
+                .as_ref()
+                .map(|list| format!(" in {}", render(list)))
+                .unwrap_or_default()
+        ),
+        ExpressionKind::DynamicCall {
+            external,
+            target,
+            args: call_args,
+        } => format!(
+            "{}({})({})",
+            if *external { "call_ext" } else { "call" },
+            args(target),
+            args(call_args)
+        ),
+    }
+}
+
+fn assert_parses(line: &str, expected: &str) {
+    assert_eq!(
+        render(&parse_expression(line)),
+        expected,
+        "parsing `{line}`"
+    );
+}
+
+#[test]
+fn test_operator_precedence() {
+    assert_parses("1 + 2 * 3", "(1 + (2 * 3))");
+    assert_parses("a - b - c", "((a - b) - c)");
+    assert_parses("2 ** 3 ** 2", "(2 ** (3 ** 2))");
+    assert_parses("a || b && c", "(a || (b && c))");
+    assert_parses("a & b == c", "(a & (b == c))");
+    assert_parses("a < b << 1", "(a < (b << 1))");
+    assert_parses("!a && -b", "((Not a) && (Negate b))");
+    assert_parses("x in L || y", "(x in (L || y))");
+    assert_parses("a = b += 1", "(a Assign (b Compound(Add) 1))");
+    assert_parses("i++ + --j", "((PostIncrement i) + (PreDecrement j))");
+    assert_parses("(1 + 2) * 3", "((1 + 2) * 3)");
+}
+
+#[test]
+fn test_ternary_and_colon() {
+    assert_parses("a ? b : c", "(a ? b : c)");
+    assert_parses("a ? b : c:d", "(a ? b : c:d)");
+    assert_parses("x ? 1 : y ? 2 : 3", "(x ? 1 : (y ? 2 : 3))");
+    assert_parses("src:loc:x", "src:loc:x");
+}
+
+#[test]
+fn test_field_access_and_calls() {
+    assert_parses("a.b?.c[1]?[2]", "a.b?.c[1]?[2]");
+    // NORTH is defined by stddef
+    assert_parses("src.Move(loc, dir = NORTH)", "src.Move(loc, dir=1)");
+    assert_parses("..()", "..()");
+    assert_parses(". = x", "(. Assign x)");
+    assert_parses("/obj/item::name", "/obj/item::name");
+    assert_parses("f(, 2)", "f(null, 2)");
+}
+
+#[test]
+fn test_special_forms() {
+    assert_parses(
+        "new /obj/item{name = \"x\"; force = 5}(loc)",
+        "new /obj/item{name=\"x\"; force=5}(loc)",
+    );
+    assert_parses("new src.type(loc)", "new src.type(loc)");
+    assert_parses("new()", "new ()");
+    assert_parses(
+        "list(1, \"a\" = 2, /obj = list())",
+        "list(1, \"a\"=2, /obj=list())",
+    );
+    assert_parses("alist(a = 1)", "alist(a=1)");
+    assert_parses("locate(/obj) in view()", "locate(/obj) in view()");
+    assert_parses("locate(1, 2, 3)", "locate(1, 2, 3)");
+    assert_parses(
+        "input(usr, \"Pick\") as null|anything in choices",
+        "input(usr, \"Pick\") as [\"null\", \"anything\"] in choices",
+    );
+    assert_parses("call(src, .proc/foo)(1)", "call(src, .proc/foo)(1)");
+    assert_parses("call_ext(\"lib\", \"f\")()", "call_ext(\"lib\", \"f\")()");
+    assert_parses("/obj:widget", "/obj:widget");
+    assert_parses("'icon.dmi'", "'icon.dmi'");
+    assert_parses("\"a[b + 1]c\"", "\"a[(b + 1)]c\"");
+}
+
+#[test]
+fn test_var_values_are_parsed() {
+    let ast = parse(&["/turf/icon_state = \"state_here[2]\" + 45"]).unwrap();
+    let Declaration::Var(var) = &ast.declarations[0] else {
+        panic!("expected a var override");
+    };
+    assert_eq!(
+        render(var.value.as_ref().unwrap()),
+        "(\"state_here[2]\" + 45)"
+    );
+}
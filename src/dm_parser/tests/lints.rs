@@ -0,0 +1,125 @@
+use crate::dm_parser::diagnostics::Severity;
+
+use super::object_tree::{describe, parse_files};
+
+fn lint(lines: &[&str]) -> Vec<String> {
+    describe(&parse_files(&[("test.dm", lines)]).lint())
+}
+
+#[test]
+fn test_clean_code_has_no_lints() {
+    let diagnostics = lint(&[
+        "/obj/item",
+        "  var/force = 1",
+        "/obj/item/proc/use(mob/user)",
+        "  var/total = force",
+        "  for(var/i in 1 to 3)",
+        "    total += i",
+        "  if(total > 3)",
+        "    return total",
+        "  else if(user)",
+        "    total = 0",
+        "  try",
+        "    user.Move(null)",
+        "  catch(var/exception/e)",
+        "  return total",
+    ]);
+    assert_eq!(diagnostics, Vec::<String>::new());
+}
+
+#[test]
+fn test_locals() {
+    let diagnostics = lint(&[
+        "/proc/test(obj/item/I, count)",
+        "  var/unused = 1",
+        "  var/used = 2",
+        "  if(used)",
+        "    var/count = 3",
+        "    var/used = count",
+        "    return used",
+        "  if(istype(I, /obj/item))",
+        "    return I",
+        "  if(istype(I, /obj/item/sword))",
+        "    return I",
+        "/obj/item/sword",
+    ]);
+    assert_eq!(
+        diagnostics,
+        [
+            "2 unused_var unused local var `unused`",
+            "5 shadowed_local `count` shadows a param",
+            "6 shadowed_local `used` shadows a local var",
+            "8 always_true_istype `I` is declared as `/obj/item`, this only checks that it is not \
+             null",
+        ]
+    );
+}
+
+#[test]
+fn test_control_flow() {
+    let diagnostics = lint(&[
+        "/proc/test(x, y)",
+        "  if(x = y)",
+        "    x = x",
+        "  while(y)",
+        "    break",
+        "    y--",
+        "  if(x)",
+        "  else",
+        "    return",
+        "  return x",
+        "  world.log << x",
+        "  end:",
+        "    world.log << y",
+    ]);
+    assert_eq!(
+        diagnostics,
+        [
+            "2 assignment_in_condition assignment in condition, did you mean `==`?",
+            "3 self_assignment `x` is assigned to itself",
+            "6 unreachable_code unreachable code after `break`",
+            "7 empty_if empty `if` body",
+            "11 unreachable_code unreachable code after `return`",
+        ]
+    );
+}
+
+#[test]
+fn test_duplicate_vars() {
+    let diagnostics = lint(&[
+        "/obj/item",
+        "  var/force = 1",
+        "  force = 2",
+        "/obj/item/var/force = 3",
+    ]);
+    assert_eq!(
+        diagnostics,
+        ["4 duplicate_var `/obj/item` already declares var `force`"]
+    );
+}
+
+#[test]
+fn test_lint_configuration() {
+    let lines = [
+        "/proc/test()",
+        "  var/unused = 1",
+        "  return",
+        "  world.log << 1",
+    ];
+    let mut parser = parse_files(&[("test.dm", &lines)]);
+    assert!(parser.lints_mut().configure("unused_var", None));
+    assert!(parser
+        .lints_mut()
+        .configure("unreachable_code", Some(Severity::Error)));
+    assert!(!parser.lints_mut().configure("no_such_lint", None));
+
+    let diagnostics = parser.lint();
+    let findings: Vec<(&str, Severity)> = diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.code, diagnostic.severity))
+        .collect();
+    assert_eq!(findings, [("unreachable_code", Severity::Error)]);
+
+    let infos: Vec<&str> = parser.lints_mut().infos().map(|info| info.id).collect();
+    assert!(infos.contains(&"self_assignment"));
+}
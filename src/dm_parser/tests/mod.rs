@@ -0,0 +1,12 @@
+mod annotations;
+mod baseline;
+mod checker;
+mod declarations;
+mod diff_scope;
+mod expressions;
+mod lints;
+mod object_tree;
+mod render;
+mod sleep;
+mod statements;
+mod suppressions;
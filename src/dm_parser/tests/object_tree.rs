@@ -0,0 +1,513 @@
+use std::path::Path;
+
+use crate::{
+    dm_parser::{
+        ast::expression::ExpressionKind,
+        diagnostics::Diagnostic,
+        lib::DmParser,
+        object_tree::{
+            builtins::{BUILTINS, BUILTINS_PATH, BUILTINS_VERSION},
+            constants::InitialValue,
+            procs::ProcRef,
+            resolve::{PathError, ResolvedPath},
+            ObjectTree,
+        },
+        parsing::type_path::DmTypePath,
+    },
+    util::{dm_file::DmFile, symbol::Symbol},
+};
+
+use super::expressions::parse_expression;
+
+pub(super) fn parse_files(files: &[(&str, &[&str])]) -> DmParser {
+    let mut parser = DmParser::default();
+    for (path, lines) in files {
+        let file = DmFile {
+            path: path.into(),
+            lines: lines.iter().map(|s| s.to_string()).collect(),
+        };
+        parser.load_file(file).unwrap();
+    }
+    parser
+}
+
+/// Every diagnostic as `line code message`, in source order. Code expanded from a define is on the
+/// line the define is used on.
+pub(super) fn describe(diagnostics: &[Diagnostic]) -> Vec<String> {
+    let mut diagnostics: Vec<&Diagnostic> = diagnostics.iter().collect();
+    diagnostics.sort_by_key(|diagnostic| diagnostic.location().start());
+    diagnostics
+        .iter()
+        .map(|diagnostic| {
+            format!(
+                "{} {} {}",
+                diagnostic.location().line().unwrap(),
+                diagnostic.code,
+                diagnostic.message
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_builtin_types() {
+    let parser = parse_files(&[]);
+    let tree = parser.object_tree();
+
+    assert!(tree.root().is_builtin());
+    for path in ["/datum", "/atom/movable", "/obj", "/client", "/regex"] {
+        assert!(
+            tree.get(&DmTypePath::from(path)).unwrap().is_builtin(),
+            "{path} is missing"
+        );
+    }
+    let ancestors: Vec<String> = tree
+        .ancestors(&DmTypePath::from("/mob"))
+        .map(|node| node.path().to_string())
+        .collect();
+    assert_eq!(ancestors, vec!["/mob", "/atom/movable", "/atom", "/datum"]);
+    assert!(tree.parent(&DmTypePath::from("/client")).is_none());
+}
+
+#[test]
+fn test_builtin_definitions() {
+    let parser = parse_files(&[(
+        "a.dm",
+        &[
+            "/mob/player/Login()",
+            "  return ..()",
+            "/obj/item/icon_state = \"item\"",
+        ],
+    )]);
+    let tree = parser.object_tree();
+
+    let linked = tree
+        .iter()
+        .flat_map(|node| {
+            let vars = node.vars().iter().filter_map(|var| var.builtin());
+            let procs = node.procs().iter().filter_map(|proc| proc.builtin());
+            vars.chain(procs).collect::<Vec<_>>()
+        })
+        .count();
+    assert_eq!(
+        linked,
+        BUILTINS.len(),
+        "every builtin is a single declaration"
+    );
+
+    let (owner, icon_state) = tree
+        .find_var(&DmTypePath::from("/obj/item"), Symbol::intern("icon_state"))
+        .unwrap();
+    assert_eq!(owner.path().to_string(), "/atom");
+    assert_eq!(icon_state.span.path(), Some(Path::new(BUILTINS_PATH)));
+
+    let login = tree
+        .get(&DmTypePath::from("/mob"))
+        .unwrap()
+        .proc(Symbol::intern("Login"))
+        .unwrap();
+    assert!(login.builtin().is_some());
+    let player_login = ProcRef {
+        owner: DmTypePath::from("/mob/player"),
+        name: Symbol::intern("Login"),
+        index: 0,
+    };
+    assert_eq!(
+        tree.original_declaration(&player_login)
+            .unwrap()
+            .to_string(),
+        "/mob/proc/Login"
+    );
+
+    let (_, rgb) = tree
+        .find_proc(&DmTypePath::default(), Symbol::intern("rgb"))
+        .unwrap();
+    let params: Vec<String> = rgb.definitions()[0]
+        .params
+        .iter()
+        .map(|param| param.name.to_string())
+        .collect();
+    assert_eq!(params, ["R", "G", "B", "A", "space"]);
+
+    let clamp = tree.root().proc(Symbol::intern("clamp")).unwrap();
+    let clamp = clamp.builtin().unwrap();
+    assert!(!clamp.is_available_in("512.1488".parse().unwrap()));
+    assert!(clamp.is_available_in(BUILTINS_VERSION));
+    assert!(tree
+        .root()
+        .proc(Symbol::intern("max"))
+        .unwrap()
+        .builtin()
+        .unwrap()
+        .is_variadic());
+}
+
+#[test]
+fn test_declarations_are_merged() {
+    let parser = parse_files(&[
+        (
+            "a.dm",
+            &[
+                "/obj/item",
+                "  var/force = 1",
+                "  proc/attack()",
+                "    return",
+                "/thing",
+            ],
+        ),
+        (
+            "b.dm",
+            &[
+                "/obj/item/weapon",
+                "  force = 10",
+                "/obj/item",
+                "  force = 2",
+                "  attack()",
+                "    return",
+                "/mob/verb/look()",
+                "/special",
+                "  parent_type = /obj/item",
+            ],
+        ),
+    ]);
+    let tree = parser.object_tree();
+    let item = tree.get(&DmTypePath::from("/obj/item")).unwrap();
+    assert!(!item.is_builtin());
+
+    let locations: Vec<(String, usize)> = item
+        .locations()
+        .iter()
+        .map(|span| {
+            (
+                span.path().unwrap().display().to_string(),
+                span.line().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(locations, vec![("a.dm".into(), 1), ("b.dm".into(), 3)]);
+
+    let force = item.var(Symbol::intern("force")).unwrap();
+    assert_eq!(force.declarations().len(), 2);
+    assert_eq!(force.declaration().unwrap().span.line(), Some(2));
+
+    let attack = item.proc(Symbol::intern("attack")).unwrap();
+    assert_eq!(attack.definitions().len(), 2);
+    assert!(!attack.is_verb());
+
+    let weapon = DmTypePath::from("/obj/item/weapon");
+    let (owner, _) = tree.find_var(&weapon, Symbol::intern("force")).unwrap();
+    assert_eq!(owner.path(), &DmTypePath::from("/obj/item"));
+    assert!(tree.var_value(&weapon, Symbol::intern("force")).is_some());
+    assert!(tree.is_subtype_of(&weapon, &DmTypePath::from("/atom")));
+
+    let mob = tree.get(&DmTypePath::from("/mob")).unwrap();
+    assert_eq!(mob.verbs().count(), 1);
+
+    let special = DmTypePath::from("/special");
+    assert_eq!(
+        tree.parent(&special).unwrap().path(),
+        &DmTypePath::from("/obj/item")
+    );
+    assert_eq!(
+        tree.parent(&DmTypePath::from("/thing")).unwrap().path(),
+        &DmTypePath::from("/datum")
+    );
+    let children: Vec<String> = tree
+        .children(&DmTypePath::from("/obj/item"))
+        .map(|node| node.path().to_string())
+        .collect();
+    assert_eq!(children, vec!["/obj/item/weapon", "/special"]);
+}
+
+#[test]
+fn test_parent_type_cycles_end() {
+    let parser = parse_files(&[(
+        "a.dm",
+        &["/a", "  parent_type = /b", "/b", "  parent_type = /a"],
+    )]);
+    let tree = parser.object_tree();
+    assert!(tree.ancestors(&DmTypePath::from("/a")).count() <= tree.len());
+    assert!(!tree.is_subtype_of(&DmTypePath::from("/a"), &DmTypePath::from("/datum")));
+}
+
+fn resolve(tree: &ObjectTree, path: &str, context: &str) -> Result<ResolvedPath, PathError> {
+    let ExpressionKind::Path { path, .. } = parse_expression(path).kind else {
+        panic!("`{path}` is not a path");
+    };
+    tree.resolve_path(&path, &DmTypePath::from(context))
+}
+
+#[test]
+fn test_path_operators() {
+    let parser = parse_files(&[(
+        "a.dm",
+        &[
+            "/obj/item",
+            "  proc/attack()",
+            "  weapon/sword",
+            "  widget",
+            "/obj/machine/widget",
+            "/obj/machine/gadget",
+            "/obj/machine/panel/gadget",
+            "/obj/crate/gadget",
+            "/proc/global_proc()",
+        ],
+    )]);
+    let tree = parser.object_tree();
+    let type_path = |path: &str| Ok(ResolvedPath::Type(DmTypePath::from(path)));
+
+    assert_eq!(resolve(tree, "/obj/item", "/"), type_path("/obj/item"));
+    assert_eq!(
+        resolve(tree, "/obj:sword", "/"),
+        type_path("/obj/item/weapon/sword")
+    );
+    // the shallowest match wins
+    assert_eq!(
+        resolve(tree, "/obj/item:widget", "/"),
+        type_path("/obj/item/widget")
+    );
+    assert_eq!(
+        resolve(tree, ".weapon", "/obj/item/widget"),
+        type_path("/obj/item/weapon")
+    );
+    assert_eq!(
+        resolve(tree, "/obj/item/weapon.widget", "/"),
+        type_path("/obj/item/widget")
+    );
+    assert_eq!(
+        resolve(tree, ".proc/attack", "/obj/item/weapon/sword"),
+        Ok(ResolvedPath::Proc {
+            on: DmTypePath::from("/obj/item/weapon/sword"),
+            owner: DmTypePath::from("/obj/item"),
+            name: Symbol::intern("attack"),
+        })
+    );
+    assert_eq!(
+        resolve(tree, "/obj/item/weapon.proc/attack", "/"),
+        resolve(tree, "/obj/item/weapon/proc/attack", "/"),
+    );
+    assert!(matches!(
+        resolve(tree, "/proc/global_proc", "/obj"),
+        Ok(ResolvedPath::Proc { .. })
+    ));
+
+    assert_eq!(
+        resolve(tree, "/obj/itme/weapon", "/"),
+        Err(PathError::NotFound {
+            path: "/obj/itme".into()
+        })
+    );
+    assert!(matches!(
+        resolve(tree, "/obj/item/proc/defend", "/"),
+        Err(PathError::NotFound { .. })
+    ));
+    assert_eq!(
+        resolve(tree, "/obj:gadget", "/"),
+        Err(PathError::Ambiguous {
+            path: "/obj:gadget".into(),
+            candidates: vec![
+                DmTypePath::from("/obj/machine/gadget"),
+                DmTypePath::from("/obj/crate/gadget"),
+            ],
+        })
+    );
+    assert_eq!(
+        resolve(tree, "/obj/machine:gadget", "/"),
+        type_path("/obj/machine/gadget")
+    );
+}
+
+#[test]
+fn test_unresolved_paths_are_reported() {
+    let parser = parse_files(&[(
+        "a.dm",
+        &[
+            "/obj/item",
+            "  var/kind = /obj/item",
+            "  proc/make()",
+            "    return new /obj/itme",
+            "  proc/callback()",
+            "    return .proc/missing",
+        ],
+    )]);
+    assert_eq!(
+        describe(&parser.diagnostics()),
+        vec![
+            "4 undefined_type `/obj/itme` does not exist",
+            "6 undefined_type `.proc/missing` does not exist",
+        ]
+    );
+}
+
+#[test]
+fn test_proc_overrides_and_parent_calls() {
+    let parser = parse_files(&[
+        (
+            "a.dm",
+            &[
+                "/obj/item/proc/attack()",
+                "  return 1",
+                "/obj/item/weapon/attack()",
+                "  . = ..() + 1",
+                "/obj/item/weapon/attack()",
+                "  if(..())",
+                "    return ..()",
+            ],
+        ),
+        (
+            "b.dm",
+            &[
+                "/obj/item/weapon/sword",
+                "  parent_type = /obj/item",
+                "  attack()",
+                "    return ..()",
+                "/obj/item/weapon/axe",
+            ],
+        ),
+    ]);
+    let tree = parser.object_tree();
+    let proc_ref = |owner: &str, index| ProcRef {
+        owner: DmTypePath::from(owner),
+        name: Symbol::intern("attack"),
+        index,
+    };
+    let attack = Symbol::intern("attack");
+
+    let second = proc_ref("/obj/item/weapon", 1);
+    let chain: Vec<String> = tree
+        .override_chain(&second)
+        .map(|proc| proc.to_string())
+        .collect();
+    assert_eq!(
+        chain,
+        vec![
+            "/obj/item/weapon/proc/attack (definition 2)",
+            "/obj/item/weapon/proc/attack",
+            "/obj/item/proc/attack",
+        ]
+    );
+    assert_eq!(
+        tree.original_declaration(&second),
+        Some(proc_ref("/obj/item", 0))
+    );
+
+    // the later definition on the same type wins
+    assert_eq!(
+        tree.implementation(&DmTypePath::from("/obj/item/weapon/axe"), attack),
+        Some(second.clone())
+    );
+    // `parent_type` decides what `..()` calls, not the path
+    assert_eq!(
+        tree.overridden(&proc_ref("/obj/item/weapon/sword", 0)),
+        Some(proc_ref("/obj/item", 0))
+    );
+    assert_eq!(tree.overridden(&proc_ref("/obj/item", 0)), None);
+
+    let calls = tree.parent_calls(&second);
+    assert_eq!(calls.len(), 2);
+    assert!(calls
+        .iter()
+        .all(|call| call.target == Some(proc_ref("/obj/item/weapon", 0))));
+    assert_eq!(calls[1].span.line(), Some(7));
+    assert!(tree.parent_calls(&proc_ref("/obj/item", 0)).is_empty());
+
+    let item = DmTypePath::from("/obj/item");
+    assert_eq!(
+        tree.proc_refs()
+            .filter(|proc| proc.owner.is_subtype_of(&item))
+            .count(),
+        4
+    );
+}
+
+#[test]
+fn test_constant_initial_values() {
+    let parser = parse_files(&[(
+        "a.dm",
+        &[
+            "#define BASE_FORCE 5",
+            "var/const/GLOBAL_LIMIT = 10",
+            "/obj/item",
+            "  var/const/MULTIPLIER = 2",
+            "  var/force = BASE_FORCE * MULTIPLIER + 1",
+            "  var/name = \"item\"",
+            "  var/desc = \"a [BASE_FORCE] kg \" + \"thing\"",
+            "  var/list/stats = list(force = 1, \"speed\", /obj/item)",
+            "  var/limit = GLOBAL_LIMIT % 3",
+            "  var/icon = 'item.dmi'",
+            "  var/owner",
+            "  layer = FLOAT_LAYER",
+            "  var/created = new /obj/item",
+            "  var/broken = 1 / 0",
+            "  var/broken_mod = 5 % 0.5",
+            "  var/loop_a = loop_b",
+            "  var/loop_b = loop_a",
+            "/obj/item/sword",
+            "  force = initial(force) * 2",
+            "  name = /obj/item::name + \" sword\"",
+            "  var/weapon_type = .proc/swing",
+            "  proc/swing()",
+        ],
+    )]);
+    let tree = parser.object_tree();
+    let item = DmTypePath::from("/obj/item");
+    let sword = DmTypePath::from("/obj/item/sword");
+    let value = |path: &DmTypePath, name: &str| match tree.initial_value(path, Symbol::intern(name))
+    {
+        Some(InitialValue::Constant(value)) => value.to_string(),
+        Some(InitialValue::NonConstant(not_constant)) => {
+            format!("not constant: {}", not_constant.reason)
+        }
+        None => "missing".to_string(),
+    };
+
+    assert_eq!(value(&item, "force"), "11");
+    assert_eq!(value(&item, "desc"), "\"a 5 kg thing\"");
+    assert_eq!(
+        value(&item, "stats"),
+        "list(\"force\" = 1, \"speed\", /obj/item)"
+    );
+    assert_eq!(value(&item, "limit"), "1");
+    assert_eq!(value(&item, "icon"), "'item.dmi'");
+    assert_eq!(value(&item, "owner"), "null");
+    assert_eq!(value(&item, "layer"), "-1");
+    assert_eq!(value(&item, "nothing"), "missing");
+    assert_eq!(
+        value(&item, "created"),
+        "not constant: `new` creates the object when its owner is created"
+    );
+    assert_eq!(value(&item, "broken"), "not constant: division by zero");
+    assert_eq!(value(&item, "broken_mod"), "not constant: division by zero");
+    assert_eq!(
+        value(&item, "loop_a"),
+        "not constant: `loop_b` is not a const var"
+    );
+
+    assert_eq!(value(&sword, "force"), "22");
+    assert_eq!(value(&sword, "name"), "\"item sword\"");
+    assert_eq!(value(&sword, "weapon_type"), "/obj/item/sword/proc/swing");
+    assert_eq!(value(&sword, "MULTIPLIER"), "2");
+
+    let names: Vec<String> = tree
+        .initial_values(&sword)
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .collect();
+    assert_eq!(names[..3], ["force", "name", "weapon_type"]);
+    assert!(names.contains(&"MULTIPLIER".to_string()));
+}
+
+#[test]
+fn test_const_cycles_are_not_constant() {
+    let parser = parse_files(&[(
+        "a.dm",
+        &["var/const/A = B + 1", "var/const/B = A + 1", "var/C = A"],
+    )]);
+    let tree = parser.object_tree();
+    let Some(InitialValue::NonConstant(not_constant)) =
+        tree.initial_value(&DmTypePath::default(), Symbol::intern("C"))
+    else {
+        panic!("a const cycle has no value");
+    };
+    assert_eq!(not_constant.reason, "the value of `A` depends on itself");
+}
@@ -0,0 +1,94 @@
+use crate::dm_parser::{diagnostics::Diagnostic, render::render};
+
+use super::object_tree::parse_files;
+
+fn diagnostics(files: &[(&str, &[&str])]) -> Vec<Diagnostic> {
+    let mut diagnostics = parse_files(files).diagnostics();
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span.start());
+    diagnostics
+}
+
+#[test]
+fn test_render_secondary_labels() {
+    let diagnostics = diagnostics(&[(
+        "test.dm",
+        &[
+            "/proc/test(count)",
+            "\tif(count)",
+            "\t\tvar/count = 3",
+            "",
+            "",
+            "\t\treturn count",
+        ],
+    )]);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        render(&diagnostics[0], false),
+        "\
+warning[shadowed_local]: `count` shadows a param
+ --> test.dm:3:3
+  |
+1 | /proc/test(count)
+  |            ----- declared here
+...
+3 |         var/count = 3
+  |         ^^^^^^^^^^^^^"
+    );
+}
+
+#[test]
+fn test_render_macro_expansion() {
+    let diagnostics = diagnostics(&[
+        ("defines.dm", &["#define DECLARE(name) var/##name = 1"]),
+        ("test.dm", &["/proc/test()", "  DECLARE(unused)"]),
+    ]);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        render(&diagnostics[0], false),
+        "\
+warning[unused_var]: unused local var `unused`
+ --> test.dm:2:3
+  |
+2 |   DECLARE(unused)
+  |   ^^^^^^^
+ ::: defines.dm:1:23
+  |
+1 | #define DECLARE(name) var/##name = 1
+  |                       -------------- macro defined here"
+    );
+}
+
+#[test]
+fn test_render_help() {
+    let diagnostics = diagnostics(&[(
+        "test.dm",
+        &["/proc/test() // lies:ignore(unused_var)", "  return"],
+    )]);
+    assert_eq!(diagnostics.len(), 1);
+    let rendered = render(&diagnostics[0], false);
+    assert!(
+        rendered.ends_with("  |\n  = help: remove the comment"),
+        "{rendered}"
+    );
+
+    let colored = render(&diagnostics[0], true);
+    assert!(colored.starts_with("\x1b[1;33mwarning[stale_suppression]\x1b[0m"));
+}
+
+#[test]
+fn test_render_continued_line() {
+    let diagnostics = diagnostics(&[(
+        "test.dm",
+        &["/proc/test()", "  var/unused = \\", "    1 + \\", "    2"],
+    )]);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        render(&diagnostics[0], false),
+        "\
+warning[unused_var]: unused local var `unused`
+ --> test.dm:2:3
+  |
+2 |   var/unused = 
+  |   ^^^^^^^^^^^^^"
+    );
+}
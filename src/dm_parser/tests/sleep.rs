@@ -0,0 +1,137 @@
+use crate::{
+    dm_parser::{
+        analysis::{
+            annotations::SHOULD_NOT_SLEEP, call_graph::CallGraph, call_graph::Cause, sleep,
+        },
+        object_tree::procs::ProcRef,
+        parsing::type_path::DmTypePath,
+    },
+    util::symbol::Symbol,
+};
+
+use super::object_tree::parse_files;
+
+fn proc_ref(owner: &str, name: &str) -> ProcRef {
+    ProcRef {
+        owner: DmTypePath::from(owner),
+        name: Symbol::from(name),
+        index: 0,
+    }
+}
+
+#[test]
+fn test_sleeping_procs() {
+    let parser = parse_files(&[(
+        "test.dm",
+        &[
+            "/proc/wait()",
+            "  sleep(1)",
+            "/proc/ask()",
+            "  return input(\"Name?\")",
+            "/proc/in_background()",
+            "  set waitfor = FALSE",
+            "  wait()",
+            "/proc/spawner()",
+            "  spawn(1)",
+            "    wait()",
+            "/proc/caller()",
+            "  in_background()",
+            "  spawner()",
+            "/obj/item/proc/use()",
+            "/obj/item/sword/use()",
+            "  wait()",
+            "/mob/proc/act(obj/item/I)",
+            "  I.use()",
+            "/mob/proc/make()",
+            "  new /obj/thing",
+            "/obj/thing/New()",
+            "  ..()",
+            "  ask()",
+        ],
+    )]);
+    let tree = parser.object_tree();
+    let analysis = sleep::analyze(tree, &CallGraph::build(tree));
+
+    for (owner, name) in [
+        ("/", "wait"),
+        ("/", "ask"),
+        ("/", "in_background"),
+        ("/obj/item/sword", "use"),
+        ("/mob", "act"),
+        ("/mob", "make"),
+    ] {
+        assert!(
+            analysis.can_sleep(&proc_ref(owner, name)),
+            "{owner} {name} should sleep"
+        );
+    }
+    for (owner, name) in [("/", "spawner"), ("/", "caller"), ("/obj/item", "use")] {
+        assert!(
+            !analysis.can_sleep(&proc_ref(owner, name)),
+            "{owner} {name} should not sleep"
+        );
+    }
+
+    let chain = analysis.sleep_chain(&proc_ref("/mob", "act"));
+    let procs: Vec<String> = chain.iter().map(|(proc, _)| proc.to_string()).collect();
+    assert_eq!(
+        procs,
+        ["/mob/proc/act", "/obj/item/sword/proc/use", "/proc/wait"]
+    );
+    match chain.last().unwrap().1 {
+        Cause::Direct { span, description } => {
+            assert_eq!(description, "`sleep()` sleeps");
+            assert_eq!(span.line(), Some(2));
+        }
+        cause => panic!("unexpected cause {cause:?}"),
+    }
+}
+
+#[test]
+fn test_should_not_sleep() {
+    let parser = parse_files(&[(
+        "test.dm",
+        &[
+            "/proc/wait()",
+            "  sleep(1)",
+            "/datum/proc/process()",
+            "  set SpacemanDMM_should_not_sleep = TRUE",
+            "/datum/thing/process()",
+            "  wait()",
+            "/datum/other/process()",
+            "  spawn()",
+            "    wait()",
+            "/datum/allowed/process()",
+            "  set SpacemanDMM_should_not_sleep = FALSE",
+            "  wait()",
+        ],
+    )]);
+    let diagnostics = parser.check();
+    let messages: Vec<(&str, String)> = diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.code, diagnostic.message.clone()))
+        .collect();
+    assert_eq!(
+        messages,
+        [(
+            SHOULD_NOT_SLEEP,
+            "`/datum/thing/proc/process` must not sleep but can".to_string()
+        )]
+    );
+    let notes: Vec<(Option<usize>, &str)> = diagnostics[0]
+        .notes
+        .iter()
+        .map(|(span, note)| (span.line(), note.as_str()))
+        .collect();
+    assert_eq!(
+        notes,
+        [
+            (Some(6), "calls `/proc/wait`, which can sleep"),
+            (Some(2), "`sleep()` sleeps"),
+            (
+                Some(3),
+                "the annotation on `/datum/proc/process` forbids it"
+            ),
+        ]
+    );
+}
@@ -0,0 +1,292 @@
+use crate::dm_parser::ast::{
+    declaration::Declaration,
+    statement::{Block, CaseValue, Statement, StatementKind},
+};
+
+use super::declarations::parse;
+
+fn proc_body(lines: &[&str]) -> Block {
+    let ast = parse(lines).unwrap();
+    let Some(Declaration::Proc(proc)) = ast.declarations.first() else {
+        panic!("expected a proc");
+    };
+    proc.body.clone()
+}
+
+/// Renders the shape of a block, statement kinds with their nested blocks.
+fn shape(block: &Block) -> String {
+    block.iter().map(shape_of).collect::<Vec<_>>().join(" ")
+}
+
+fn shape_of(statement: &Statement) -> String {
+    match &statement.kind {
+        StatementKind::Expression(_) => "expr".into(),
+        StatementKind::Block(body) => format!("{{{}}}", shape(body)),
+        StatementKind::Var(vars) => vars
+            .iter()
+            .map(|var| {
+                format!(
+                    "var:{}{}",
+                    var.name,
+                    var.var_type
+                        .as_ref()
+                        .map(|var_type| format!(":{var_type}"))
+                        .unwrap_or_default()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(","),
+        StatementKind::Return(value) => {
+            format!("return{}", if value.is_some() { ":value" } else { "" })
+        }
+        StatementKind::Break(label) => {
+            format!(
+                "break{}",
+                label.map(|l| format!(":{l}")).unwrap_or_default()
+            )
+        }
+        StatementKind::Continue(label) => {
+            format!(
+                "continue{}",
+                label.map(|l| format!(":{l}")).unwrap_or_default()
+            )
+        }
+        StatementKind::Goto(label) => format!("goto:{label}"),
+        StatementKind::Label { name, body } => format!("label:{name}({})", shape(body)),
+        StatementKind::Del(_) => "del".into(),
+        StatementKind::If { arms, else_arm } => {
+            let mut rendered: Vec<String> = arms
+                .iter()
+                .map(|(_, body)| format!("if({})", shape(body)))
+                .collect();
+            if let Some(else_arm) = else_arm {
+                rendered.push(format!("else({})", shape(else_arm)));
+            }
+            rendered.join("")
+        }
+        StatementKind::While { body, .. } => format!("while({})", shape(body)),
+        StatementKind::DoWhile { body, .. } => format!("do({})", shape(body)),
+        StatementKind::For { body, .. } => format!("for({})", shape(body)),
+        StatementKind::ForIn { var, list, body } => format!(
+            "for_in:{}{}{}({})",
+            var.name,
+            if var.declared { ":declared" } else { "" },
+            if list.is_some() { "" } else { ":world" },
+            shape(body)
+        ),
+        StatementKind::ForRange {
+            var, step, body, ..
+        } => format!(
+            "for_range:{}{}({})",
+            var.name,
+            if step.is_some() { ":step" } else { "" },
+            shape(body)
+        ),
+        StatementKind::Switch { cases, default, .. } => {
+            let mut rendered: Vec<String> = cases
+                .iter()
+                .map(|case| format!("case{}({})", case.values.len(), shape(&case.body)))
+                .collect();
+            if let Some(default) = default {
+                rendered.push(format!("default({})", shape(default)));
+            }
+            format!("switch[{}]", rendered.join(" "))
+        }
+        StatementKind::Spawn { delay, body } => format!(
+            "spawn{}({})",
+            if delay.is_some() { ":delay" } else { "" },
+            shape(body)
+        ),
+        StatementKind::Try { body, catch } => format!(
+            "try({}){}",
+            shape(body),
+            catch
+                .as_ref()
+                .map(|catch| format!(
+                    "catch{}({})",
+                    catch
+                        .var
+                        .as_ref()
+                        .map(|var| format!(":{}", var.name))
+                        .unwrap_or_default(),
+                    shape(&catch.body)
+                ))
+                .unwrap_or_default()
+        ),
+        StatementKind::Throw(_) => "throw".into(),
+        StatementKind::Set { name, is_in, .. } => {
+            format!("set:{name}{}", if *is_in { ":in" } else { "" })
+        }
+    }
+}
+
+#[test]
+fn test_if_else_chains() {
+    let body = proc_body(&[
+        "/proc/test()",
+        "  if(a)",
+        "    return 1",
+        "  else if(b) return",
+        "  else",
+        "    if(c)",
+        "      x()",
+        "    y()",
+        "  if(d) z()",
+        "  if(e)",
+        "    if(f)",
+        "      x()",
+        "  else",
+        "    y()",
+    ]);
+    assert_eq!(
+        shape(&body),
+        "if(return:value)if(return)else(if(expr) expr) if(expr) if(if(expr))else(expr)"
+    );
+}
+
+#[test]
+fn test_for_loops() {
+    let body = proc_body(&[
+        "/proc/test()",
+        "  for(var/i = 1, i <= 10, i++)",
+        "    x()",
+        "  for(var/j = 0; j < 5; j++) x()",
+        "  for()",
+        "    break",
+        "  for(var/obj/O in view())",
+        "    continue",
+        "  for(x in L)",
+        "    x()",
+        "  for(x in 1 to 10 step 2)",
+        "    x()",
+        "  for(var/k = 1 to 3)",
+        "    x()",
+        "  for(var/mob/M)",
+        "    x()",
+    ]);
+    assert_eq!(
+        shape(&body),
+        "for(expr) for(expr) for(break) for_in:O:declared(continue) for_in:x(expr) \
+         for_range:x:step(expr) for_range:k(expr) for_in:M:declared:world(expr)"
+    );
+
+    let StatementKind::ForIn { var, .. } = &body[3].kind else {
+        panic!("expected a for in");
+    };
+    assert_eq!(var.var_type.as_ref().unwrap().to_string(), "/obj");
+}
+
+#[test]
+fn test_switch() {
+    let body = proc_body(&[
+        "/proc/test()",
+        "  switch(x)",
+        "    if(1, 2)",
+        "      a()",
+        "    if(3 to 5)",
+        "      b()",
+        "    else",
+        "      c()",
+    ]);
+    assert_eq!(
+        shape(&body),
+        "switch[case2(expr) case1(expr) default(expr)]"
+    );
+    let StatementKind::Switch { cases, .. } = &body[0].kind else {
+        panic!("expected a switch");
+    };
+    assert!(matches!(cases[1].values[0], CaseValue::Range(..)));
+}
+
+#[test]
+fn test_other_statements() {
+    let body = proc_body(&[
+        "/mob/verb/test()",
+        "  set name = \"Test\"",
+        "  set src in view(1)",
+        "  var/obj/item/I = locate() in src",
+        "  var/list/L[5]",
+        "  while(x)",
+        "    x--",
+        "  do",
+        "    x++",
+        "  while(x < 5)",
+        "  spawn(10)",
+        "    x()",
+        "  spawn() x()",
+        "  try",
+        "    throw EXCEPTION(\"oops\")",
+        "  catch(var/exception/e)",
+        "    del(e)",
+        "  outer:",
+        "  for(x in L)",
+        "    continue outer",
+        "  goto outer",
+        "  . = ..()",
+    ]);
+    assert_eq!(
+        shape(&body),
+        "set:name set:src:in var:I:/obj/item var:L:/list while(expr) do(expr) spawn:delay(expr) \
+         spawn(expr) try(throw)catch:e(del) label:outer() for_in:x(continue:outer) goto:outer expr"
+    );
+}
+
+#[test]
+fn test_local_var_lists_and_blocks() {
+    let body = proc_body(&[
+        "/proc/test()",
+        "  var/a = 1, b = 2",
+        "  var",
+        "    c = 1",
+        "    d",
+        "    obj/item/I, list/L[2]",
+        "  var/mob",
+        "    M",
+        "    living/player",
+        "  var { e = 3; f }",
+        "  return a + b",
+    ]);
+    assert_eq!(
+        shape(&body),
+        "var:a,var:b var:c,var:d,var:I:/obj/item,var:L:/list var:M:/mob,var:player:/mob/living \
+         var:e,var:f return:value"
+    );
+}
+
+#[test]
+fn test_brace_blocks() {
+    let body = proc_body(&[
+        "/proc/test() {",
+        "  if(a) {",
+        "    x()",
+        "      y()",
+        "  } else {",
+        "    z()",
+        "  }",
+        "  if(b)",
+        "  {",
+        "    x()",
+        "  }",
+        "  for(var/i in L) { x() }",
+        "}",
+    ]);
+    assert_eq!(
+        shape(&body),
+        "if(expr expr)else(expr) if(expr) for_in:i:declared(expr)"
+    );
+}
+
+#[test]
+fn test_semicolons() {
+    let body = proc_body(&[
+        "/proc/test()",
+        "  x = 1; y = 2;",
+        "  if(a) { x(); y() } else { z() }",
+        "  for(var/i = 0; i < 5; i++) { x(); ; }",
+        "  outer: { x(); break outer }",
+    ]);
+    assert_eq!(
+        shape(&body),
+        "expr expr if(expr expr)else(expr) for(expr) label:outer(expr break:outer)"
+    );
+}
@@ -0,0 +1,100 @@
+use super::object_tree::{describe, parse_files};
+
+/// Every diagnostic left after suppression.
+fn diagnostics(lines: &[&str]) -> Vec<String> {
+    describe(&parse_files(&[("test.dm", lines)]).diagnostics())
+}
+
+#[test]
+fn test_unsuppressed() {
+    let diagnostics = diagnostics(&["/proc/test()", "  var/unused = 1", "  var/other = 2"]);
+    assert_eq!(
+        diagnostics,
+        vec![
+            "2 unused_var unused local var `unused`",
+            "3 unused_var unused local var `other`",
+        ]
+    );
+}
+
+#[test]
+fn test_ignore_line() {
+    let diagnostics = diagnostics(&[
+        "/proc/test()",
+        "  var/unused = 1 // lies:ignore(unused_var) kept for the next change",
+        "  // lies:ignore-next-line",
+        "  var/other = 2",
+        "  var/third = 3 // lies:ignore(shadowed_local)",
+    ]);
+    assert_eq!(
+        diagnostics,
+        vec![
+            "5 unused_var unused local var `third`",
+            "5 stale_suppression `lies:ignore(shadowed_local)` does not suppress anything",
+        ]
+    );
+}
+
+#[test]
+fn test_ignore_file() {
+    let diagnostics = diagnostics(&[
+        "// lies:ignore-file(unused_var)",
+        "/proc/test()",
+        "  var/unused = 1",
+        "  var/a = 2",
+        "  if(a)",
+        "  else",
+        "    return",
+    ]);
+    assert_eq!(diagnostics, vec!["5 empty_if empty `if` body"]);
+}
+
+#[test]
+fn test_invalid_suppressions() {
+    let diagnostics = diagnostics(&[
+        "// lies:ignore-file",
+        "// lies:ignroe(unused_var)",
+        "// lies:ignore(unused_var",
+        "/proc/test()",
+        "  return",
+    ]);
+    assert_eq!(
+        diagnostics,
+        vec![
+            "1 invalid_suppression `lies:ignore-file` needs the codes to ignore",
+            "2 invalid_suppression unknown directive `lies:ignroe`",
+            "3 invalid_suppression unclosed `(` in `lies:ignore`",
+        ]
+    );
+}
+
+#[test]
+fn test_ignore_macro_use() {
+    let diagnostics = diagnostics(&[
+        "#define BAD missing",
+        "/proc/test()",
+        "  var/a = BAD // lies:ignore(undefined_var)",
+        "  var/b = BAD",
+        "  return a + b",
+    ]);
+    assert_eq!(diagnostics, vec!["4 undefined_var undefined var `missing`"]);
+}
+
+#[test]
+fn test_suppression_of_lint_that_is_off() {
+    let mut parser = parse_files(&[(
+        "test.dm",
+        &[
+            "/proc/test()",
+            "  var/unused = 1 // lies:ignore(unused_var)",
+            "  var/other = 2 // lies:ignore(shadowed_local)",
+        ],
+    )]);
+    assert!(parser.lints_mut().configure("unused_var", None));
+    let codes: Vec<(usize, &str)> = parser
+        .diagnostics()
+        .iter()
+        .map(|diagnostic| (diagnostic.span.line().unwrap(), diagnostic.code))
+        .collect();
+    assert_eq!(codes, [(3, "stale_suppression")]);
+}
@@ -1,21 +1,27 @@
-use crate::tokens::dm_token::DmToken;
+use std::sync::Arc;
+
+use crate::{tokens::dm_token::DmToken, util::symbol::Symbol};
+
+use super::macro_template::MacroTemplate;
 
 #[derive(Debug, Clone)]
 pub struct DmDefineDefinition {
-    name: String,
-    body: Vec<DmToken>,
+    name: Symbol,
+    /// Shared with every expansion, tokens are cloned out of it one at a time.
+    body: Arc<[DmToken]>,
     macro_param_info: Option<MacroParamInfo>,
+    macro_template: Option<Arc<MacroTemplate>>,
 }
 
 #[derive(Debug, Clone)]
 pub struct MacroParamInfo {
-    args: Vec<String>,
+    args: Vec<Symbol>,
     arg_count: usize,
     last_arg_is_catch_all: bool,
 }
 
 impl MacroParamInfo {
-    pub fn new(args: Vec<String>, arg_count: usize, last_arg_is_catch_all: bool) -> Self {
+    pub fn new(args: Vec<Symbol>, arg_count: usize, last_arg_is_catch_all: bool) -> Self {
         Self {
             args,
             arg_count,
@@ -23,7 +29,7 @@ impl MacroParamInfo {
         }
     }
 
-    pub fn args(&self) -> &Vec<String> {
+    pub fn args(&self) -> &Vec<Symbol> {
         &self.args
     }
 
@@ -38,7 +44,11 @@ impl MacroParamInfo {
 
 impl DmDefineDefinition {
     pub fn name(&self) -> &str {
-        &self.name
+        self.name.as_str()
+    }
+
+    pub fn symbol(&self) -> Symbol {
+        self.name
     }
 
     pub fn body(&self) -> &[DmToken] {
@@ -53,27 +63,35 @@ impl DmDefineDefinition {
         self.macro_param_info.as_ref().unwrap()
     }
 
+    pub fn macro_template(&self) -> &MacroTemplate {
+        self.macro_template.as_ref().unwrap()
+    }
+
     pub fn new_flag(name: &str) -> Self {
         Self {
-            name: name.into(),
-            body: vec![],
+            name: Symbol::intern(name),
+            body: Arc::new([]),
             macro_param_info: None,
+            macro_template: None,
         }
     }
 
     pub fn new_basic_replace(name: &str, body: &[DmToken]) -> Self {
         Self {
-            name: name.into(),
-            body: body.to_owned(),
+            name: Symbol::intern(name),
+            body: body.into(),
             macro_param_info: None,
+            macro_template: None,
         }
     }
 
     pub fn new_macro(name: &str, body: Vec<DmToken>, macro_args: MacroParamInfo) -> Self {
+        let macro_template = MacroTemplate::new(&body, &macro_args);
         Self {
-            name: name.into(),
-            body,
+            name: Symbol::intern(name),
+            body: body.into(),
             macro_param_info: Some(macro_args),
+            macro_template: Some(Arc::new(macro_template)),
         }
     }
 }
@@ -8,7 +8,7 @@ use crate::{
         lib::DmPreProcessor,
     },
     tokens::dm_token::DmToken,
-    util::{is_valid_identifier, ParseError},
+    util::{is_valid_identifier, symbol::Symbol, ParseError},
 };
 
 impl DmPreProcessor {
@@ -32,9 +32,9 @@ impl DmPreProcessor {
             return self.handle_macro(name, define_args);
         }
 
-        let body: Vec<_> = args.iter().skip(1).cloned().collect();
-        trace!("define body: {:?}", &body);
-        self.add_define(DmDefineDefinition::new_basic_replace(name, &body));
+        let body = trim_whitespace(define_args);
+        trace!("define body: {:?}", body);
+        self.add_define(DmDefineDefinition::new_basic_replace(name, body));
 
         Ok(())
     }
@@ -69,9 +69,7 @@ impl DmPreProcessor {
         }
 
         args.remove(0);
-        if args.first().is_some_and(|x| x.value() == " ") {
-            args.remove(0);
-        }
+        let args = trim_whitespace(&args).to_vec();
 
         // verify all arg names are alphanumeric, except last one which has special behavior
         if arg_names.len() > 1 {
@@ -106,6 +104,7 @@ impl DmPreProcessor {
         }
 
         let arg_count = arg_names.len();
+        let arg_names = arg_names.iter().map(|arg| Symbol::intern(arg)).collect();
         self.add_define(DmDefineDefinition::new_macro(
             _name,
             args,
@@ -114,3 +113,17 @@ impl DmPreProcessor {
         Ok(())
     }
 }
+
+/// The body of a define without the whitespace around it, which would otherwise end up in every
+/// expansion, such as indentation in front of a statement.
+fn trim_whitespace(body: &[DmToken]) -> &[DmToken] {
+    let start = body
+        .iter()
+        .position(|token| !token.is_only_whitespace(false))
+        .unwrap_or(body.len());
+    let end = body
+        .iter()
+        .rposition(|token| !token.is_only_whitespace(false))
+        .map_or(start, |index| index + 1);
+    &body[start..end]
+}
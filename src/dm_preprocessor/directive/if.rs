@@ -46,7 +46,7 @@ impl DmPreProcessor {
             }
 
             let defined_name = current_run[index + 2].value();
-            let is_defined = self.defines.contains_key(defined_name);
+            let is_defined = self.is_defined(defined_name);
             trace!("defined({}) = {}", defined_name, is_defined);
             current_run.remove(index);
             current_run.remove(index);
@@ -10,7 +10,7 @@ impl DmPreProcessor {
         }
 
         let define_name = args[0].value();
-        if !self.defines.contains_key(define_name) {
+        if !self.is_defined(define_name) {
             self.increment_logical_skip_level();
         }
         Ok(())
@@ -1,6 +1,7 @@
 use std::{
     collections::{HashMap, VecDeque},
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
 use log::{debug, error};
@@ -8,19 +9,23 @@ use log::{debug, error};
 #[cfg(test)]
 use once_cell::sync::Lazy;
 
-use crate::{tokens::dm_token::DmToken, util::ParseError};
+use crate::{
+    tokens::{dm_token::DmToken, span::Span},
+    util::{symbol::Symbol, ParseError},
+};
 
-use super::{define_definition::DmDefineDefinition, tokenize_state::TokenizeState};
+use super::{
+    define_definition::DmDefineDefinition, macro_template::TemplatePiece, token_stream::TokenBuffer,
+};
 
 /**
  * The preprocessor is responsible for handling all preprocessor directives.
  * When a file gets preprocessed it is converted into a list of tokens.
  */
 pub struct DmPreProcessor {
-    pub defines: HashMap<String, DmDefineDefinition>,
+    pub defines: HashMap<Symbol, DmDefineDefinition>,
     logical_skip_levels: usize, // if this somehow gets too big, find the nearest bar
     pub pending_includes: Vec<PathBuf>,
-    pub tokenize_state: TokenizeState,
     include_order: Vec<PathBuf>,
 }
 
@@ -36,7 +41,6 @@ impl DmPreProcessor {
             defines: HashMap::new(),
             logical_skip_levels: 0,
             pending_includes: vec![],
-            tokenize_state: TokenizeState::default(),
             include_order: vec![],
         };
         for define in Self::initial_defines() {
@@ -83,20 +87,23 @@ impl DmPreProcessor {
             assert!(!define.body().first().unwrap().value().is_empty());
             assert!(!define.body().last().unwrap().value().is_empty());
         }
-        self.defines.insert(define.name().to_string(), define);
+        self.defines.insert(define.symbol(), define);
     }
 
     pub fn remove_define(&mut self, name: &str) {
         debug!("Removing define `{}`", name);
-        self.defines.remove(name);
+        if let Some(name) = Symbol::lookup(name) {
+            self.defines.remove(&name);
+        }
     }
 
     pub fn is_defined(&self, name: &str) -> bool {
-        self.defines.contains_key(name)
+        self.get_define(name).is_some()
     }
 
+    /// Names that were never interned can't be defined, so this never interns `name`.
     pub fn get_define(&self, name: &str) -> Option<&DmDefineDefinition> {
-        self.defines.get(name)
+        self.defines.get(&Symbol::lookup(name)?)
     }
 
     pub fn get_base_file_dir(&self) -> PathBuf {
@@ -136,22 +143,17 @@ impl DmPreProcessor {
 
     fn do_macro_replacement(
         macro_definition: &DmDefineDefinition,
-        tokens: &mut VecDeque<DmToken>,
+        name: &DmToken,
+        tokens: &mut impl TokenBuffer,
     ) -> Result<Option<DmToken>, ParseError> {
         if !tokens.pop_front().is_some_and(|tok| tok.value() == "(") {
             return Err(ParseError::EXPECTED_DIFFERENT_TOKEN);
         }
 
         let param_info = macro_definition.macro_param_info();
-        let mut raw_args = vec![];
-        let mut arg_split_points = vec![];
+        let mut args: Vec<Vec<DmToken>> = vec![vec![]];
         let mut paren_count = 1; // account for the one we popped out
-        while !tokens.is_empty() {
-            let token = tokens.pop_front();
-            if token.is_none() {
-                return Err(ParseError::ERROR_MACRO_MALFORMED_CALL);
-            }
-            let token = token.unwrap();
+        while let Some(token) = tokens.pop_front() {
             if token.value() == ")" && !token.is_in_string() {
                 paren_count -= 1;
                 if paren_count == 0 {
@@ -160,76 +162,63 @@ impl DmPreProcessor {
             } else if token.value() == "(" && !token.is_in_string() {
                 paren_count += 1;
             } else if token.value() == "," && paren_count == 1 && !token.is_in_string() {
-                arg_split_points.push(raw_args.len());
+                args.push(vec![]);
                 continue;
             } else if token.value() == " " && paren_count == 1 && !token.is_in_string() {
                 continue;
             }
-            raw_args.push(token);
+            args.last_mut().unwrap().push(token);
         }
 
-        let mut args = vec![];
-        for split_point in arg_split_points.iter().rev() {
-            let arg = raw_args.split_off(*split_point);
-            args.push(arg);
-        }
-        args.push(raw_args);
-        args.reverse();
-
-        let mut final_args = HashMap::new();
-        let arg_names = param_info.args();
-
-        for (arg_name, arg) in arg_names.iter().zip(args.iter()) {
-            final_args.insert(arg_name.to_string(), arg.clone());
-        }
-        if param_info.last_arg_is_catch_all() {
-            let last_arg = args
-                .iter()
-                .skip(arg_names.len() - 1)
-                .flatten()
-                .cloned()
-                .collect();
-            final_args.insert(arg_names.last().unwrap().to_string(), last_arg);
-        }
+        // the catch-all argument swallows every argument after it
+        let arg_count = param_info.args().len();
+        let catch_all = if param_info.last_arg_is_catch_all() {
+            let rest = args.get(arg_count - 1..).unwrap_or_default();
+            Some(rest.concat())
+        } else {
+            None
+        };
+        let get_arg = |slot: usize| -> Option<&[DmToken]> {
+            if slot + 1 == arg_count {
+                if let Some(catch_all) = &catch_all {
+                    return Some(catch_all);
+                }
+            }
+            args.get(slot).map(|arg| arg.as_slice())
+        };
 
-        let mut replacement_tokens = macro_definition.body().to_vec();
-        let mut new_tokens = VecDeque::new();
-        while !replacement_tokens.is_empty() {
-            let token = replacement_tokens.remove(0);
-            match token.value() {
-                "##" => {
-                    let name = replacement_tokens.remove(0);
-                    let name = name.value();
-                    if !final_args.contains_key(name) {
-                        if param_info.last_arg_is_catch_all()
-                            && param_info.args().last() == Some(&format!("{}...", name))
-                        {
-                            continue;
-                        }
+        // the arguments keep their own spans, which are at the call site already
+        let call_site = name.call_site();
+        let mut new_tokens = Vec::with_capacity(macro_definition.body().len());
+        for piece in macro_definition.macro_template().pieces() {
+            match piece {
+                TemplatePiece::Literal(token) => new_tokens.push(expanded(token, &call_site)),
+                TemplatePiece::Param { slot, token } => match get_arg(*slot) {
+                    Some(arg) => new_tokens.extend_from_slice(arg),
+                    None => new_tokens.push(expanded(token, &call_site)),
+                },
+                TemplatePiece::Paste { slot, name } => match slot.and_then(get_arg) {
+                    Some(arg) => new_tokens.extend_from_slice(arg),
+                    None if param_info.last_arg_is_catch_all()
+                        && param_info.args().last().map(|arg| arg.as_str())
+                            == Some(format!("{}...", name.value()).as_str()) => {}
+                    None => {
                         error!("`##` operator used on undefined argument `{}`", name);
                         return Err(ParseError::ERROR_MACRO_MALFORMED_CALL);
                     }
-                    let arg = final_args.get(name).unwrap();
-                    new_tokens.extend(arg.clone());
-                }
-                "#" => {
-                    let name = replacement_tokens.remove(0);
-                    let name = name.value();
-                    if !final_args.contains_key(name) {
+                },
+                TemplatePiece::Stringify { slot, name } => {
+                    let Some(arg) = slot.and_then(get_arg) else {
                         error!("`#` operator used on undefined argument `{}`", name);
                         return Err(ParseError::ERROR_MACRO_MALFORMED_CALL);
-                    }
-                    let arg = final_args.get(name).unwrap();
-                    new_tokens.push_back(DmToken::from("\""));
-                    new_tokens.extend(arg.clone());
-                    new_tokens.push_back(DmToken::from("\""));
-                }
-                token if final_args.contains_key(token) => {
-                    let arg = final_args.get(token).unwrap();
-                    new_tokens.extend(arg.clone());
+                    };
+                    new_tokens.push(DmToken::from("\""));
+                    new_tokens.extend_from_slice(arg);
+                    new_tokens.push(DmToken::from("\""));
                 }
-                _ => {
-                    new_tokens.push_back(token);
+                TemplatePiece::MissingOperand(token) => {
+                    error!("`{}` operator is missing its argument", token);
+                    return Err(ParseError::ERROR_MACRO_MALFORMED_CALL);
                 }
             }
         }
@@ -279,7 +268,7 @@ impl DmPreProcessor {
     pub fn do_define_replacement(
         &self,
         token: DmToken,
-        next_tokens: &mut VecDeque<DmToken>,
+        next_tokens: &mut impl TokenBuffer,
     ) -> Result<Option<DmToken>, ParseError> {
         let define = self.get_define(token.value());
         if define.is_none() {
@@ -293,19 +282,23 @@ impl DmPreProcessor {
                 return Ok(Some(token));
             }
             debug!("macro `{}`", define.name());
-            return Self::do_macro_replacement(define, next_tokens);
-        }
-
-        let tokens = define.body().to_vec();
-        debug!("define replacement: `{}` `{:?}`", define.name(), &tokens);
-        if tokens.is_empty() {
-            return Ok(None);
+            return Self::do_macro_replacement(define, &token, next_tokens);
         }
 
-        next_tokens.reserve(tokens.len());
-        for token in tokens.into_iter().rev() {
-            next_tokens.push_front(token);
+        let tokens = define.body();
+        debug!("define replacement: `{}` `{:?}`", define.name(), tokens);
+        let call_site = token.call_site();
+        for token in tokens.iter().rev() {
+            next_tokens.push_front(expanded(token, &call_site));
         }
         Ok(None)
     }
 }
+
+/// A token of the body of a define, copied to where the define is used.
+fn expanded(token: &DmToken, call_site: &Option<Arc<Span>>) -> DmToken {
+    match call_site {
+        Some(call_site) => token.clone().with_expansion(call_site),
+        None => token.clone(),
+    }
+}
@@ -0,0 +1,73 @@
+use crate::{tokens::dm_token::DmToken, util::symbol::Symbol};
+
+use super::define_definition::MacroParamInfo;
+
+/// A macro body split into literal tokens and argument slots.
+/// Built once per macro so expanding it only has to copy tokens.
+#[derive(Debug, Clone)]
+pub struct MacroTemplate {
+    pieces: Vec<TemplatePiece>,
+}
+
+#[derive(Debug, Clone)]
+pub enum TemplatePiece {
+    Literal(DmToken),
+    /// A parameter used as-is. The token is kept in case the call did not pass the argument.
+    Param {
+        slot: usize,
+        token: DmToken,
+    },
+    /// `##name`
+    Paste {
+        slot: Option<usize>,
+        name: DmToken,
+    },
+    /// `#name`
+    Stringify {
+        slot: Option<usize>,
+        name: DmToken,
+    },
+    /// `#` or `##` at the end of the body.
+    MissingOperand(DmToken),
+}
+
+impl MacroTemplate {
+    pub fn new(body: &[DmToken], param_info: &MacroParamInfo) -> Self {
+        let slot_of = |token: &DmToken| {
+            let name = Symbol::lookup(token.value())?;
+            param_info.args().iter().position(|arg| *arg == name)
+        };
+
+        let mut pieces = Vec::with_capacity(body.len());
+        let mut body = body.iter();
+        while let Some(token) = body.next() {
+            let piece = match token.value() {
+                "##" | "#" => match body.next() {
+                    Some(name) if token.value() == "##" => TemplatePiece::Paste {
+                        slot: slot_of(name),
+                        name: name.clone(),
+                    },
+                    Some(name) => TemplatePiece::Stringify {
+                        slot: slot_of(name),
+                        name: name.clone(),
+                    },
+                    None => TemplatePiece::MissingOperand(token.clone()),
+                },
+                _ => match slot_of(token) {
+                    Some(slot) => TemplatePiece::Param {
+                        slot,
+                        token: token.clone(),
+                    },
+                    None => TemplatePiece::Literal(token.clone()),
+                },
+            };
+            pieces.push(piece);
+        }
+
+        Self { pieces }
+    }
+
+    pub fn pieces(&self) -> &[TemplatePiece] {
+        &self.pieces
+    }
+}
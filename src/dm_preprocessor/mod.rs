@@ -1,8 +1,10 @@
 mod define_definition;
 mod directive;
 pub mod lib;
+mod macro_template;
 mod preprocess_core;
 mod stddef_defines;
+pub mod token_stream;
 pub mod tokenize_state;
 
 #[cfg(test)]
@@ -1,32 +1,47 @@
-use std::collections::VecDeque;
+use std::iter::Filter;
 
 use ::log::{error, trace};
 
 use crate::{
-    tokens::dm_token::DmToken,
+    tokens::{dm_token::DmToken, tokenize::DmTokenizer},
     util::{dm_file::DmFile, ParseError},
 };
 
-use super::lib::DmPreProcessor;
+use super::{
+    lib::DmPreProcessor,
+    token_stream::{TokenBuffer, TokenStream},
+};
 
 impl DmPreProcessor {
-    pub fn preprocess(&mut self, file: &DmFile) -> Result<VecDeque<DmToken>, ParseError> {
-        self.tokenize_state.set_lines(file.lines());
-        let mut tokens: VecDeque<DmToken> = self.start_tokenizing().into();
-        let mut final_tokens: VecDeque<DmToken> = VecDeque::new();
-
-        loop {
-            if tokens.is_empty() {
-                break;
-            }
+    pub fn preprocess(&mut self, file: &DmFile) -> Preprocessed<'_, DmTokenizer> {
+        self.preprocess_tokens(DmTokenizer::from_file(file))
+    }
 
-            let token = tokens.pop_front().unwrap();
+    /// Preprocesses tokens as they are pulled from the given source. Only the lookahead of macro
+    /// calls and define replacements is buffered, the file is never held as a whole.
+    pub fn preprocess_tokens<I: Iterator<Item = DmToken>>(
+        &mut self,
+        source: I,
+    ) -> Preprocessed<'_, I> {
+        Preprocessed {
+            preprocessor: self,
+            tokens: TokenStream::new(source.filter(is_code as fn(&DmToken) -> bool)),
+            failed: false,
+        }
+    }
+
+    /// The next token that is not a directive or skipped, with defines replaced. `None` at the
+    /// end of the source.
+    fn preprocess_next(
+        &mut self,
+        tokens: &mut impl TokenBuffer,
+    ) -> Result<Option<DmToken>, ParseError> {
+        while let Some(token) = tokens.pop_front() {
             trace!("Token: {}", token.value().escape_debug());
             let token = if !token.is_in_string() {
-                self.do_define_replacement(token, &mut tokens)
-                    .map_err(|err| {
-                        err.with_file_path(self.get_current_file().display().to_string())
-                    })?
+                self.do_define_replacement(token, tokens).map_err(|err| {
+                    err.with_file_path(self.get_current_file().display().to_string())
+                })?
             } else {
                 Some(token)
             };
@@ -49,7 +64,7 @@ impl DmPreProcessor {
                 let directive = tokens.pop_front().unwrap();
                 let directive = directive.value(); // needs to be seperate because of borrow checker
 
-                let mut args = Self::take_until_match_any(&mut tokens, &["\n", "//"]);
+                let mut args = Self::take_until_match_any(tokens, &["\n", "//"]);
                 trace!("directive args: {args:?}");
                 if !args.is_empty() {
                     if !args[0].is_only_whitespace(false) {
@@ -68,13 +83,12 @@ impl DmPreProcessor {
                 continue;
             }
 
-            final_tokens.push_back(token);
+            return Ok(Some(token));
         }
-
-        Ok(final_tokens)
+        Ok(None)
     }
 
-    fn take_until_match_any(tokens: &mut VecDeque<DmToken>, patterns: &[&str]) -> Vec<DmToken> {
+    fn take_until_match_any(tokens: &mut impl TokenBuffer, patterns: &[&str]) -> Vec<DmToken> {
         match Self::take_until(tokens, |token| patterns.contains(&token.value())) {
             Some(tokens) => tokens,
             None => {
@@ -84,13 +98,12 @@ impl DmPreProcessor {
         }
     }
     fn take_until(
-        tokens: &mut VecDeque<DmToken>,
+        tokens: &mut impl TokenBuffer,
         check: impl Fn(&DmToken) -> bool,
     ) -> Option<Vec<DmToken>> {
         let mut final_tokens = vec![];
 
-        while !tokens.is_empty() {
-            let token = tokens.pop_front().unwrap();
+        while let Some(token) = tokens.pop_front() {
             if check(&token) {
                 return Some(final_tokens);
             }
@@ -100,3 +113,34 @@ impl DmPreProcessor {
         None
     }
 }
+
+/// `// lies:` comments are only kept for the analysis, they are not part of the code.
+fn is_code(token: &DmToken) -> bool {
+    !token.is_comment()
+}
+
+/// The tokens of a source without the comments.
+type CodeTokens<I> = Filter<I, fn(&DmToken) -> bool>;
+
+/// The preprocessed tokens of a source, produced as they are pulled. Ends after the first error.
+pub struct Preprocessed<'a, I: Iterator<Item = DmToken>> {
+    preprocessor: &'a mut DmPreProcessor,
+    tokens: TokenStream<CodeTokens<I>>,
+    failed: bool,
+}
+
+impl<I: Iterator<Item = DmToken>> Iterator for Preprocessed<'_, I> {
+    type Item = Result<DmToken, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let next = self
+            .preprocessor
+            .preprocess_next(&mut self.tokens)
+            .transpose();
+        self.failed = next.as_ref().is_some_and(Result::is_err);
+        next
+    }
+}
@@ -35,7 +35,7 @@ impl DmPreProcessor {
             DmDefineDefinition::new_basic_replace("LEGACY_MOVEMENT_MODE", &["0".into()]),
             DmDefineDefinition::new_basic_replace("TILE_MOVEMENT_MODE", &["1".into()]),
             DmDefineDefinition::new_basic_replace("PIXEL_MOVEMENT_MODE", &["2".into()]),
-            DmDefineDefinition::new_basic_replace("FLOAT_LAYER", &["-1".into()]),
+            DmDefineDefinition::new_basic_replace("FLOAT_LAYER", &["-".into(), "1".into()]),
             DmDefineDefinition::new_basic_replace("AREA_LAYER", &["1".into()]),
             DmDefineDefinition::new_basic_replace("TURF_LAYER", &["2".into()]),
             DmDefineDefinition::new_basic_replace("OBJ_LAYER", &["3".into()]),
@@ -44,7 +44,7 @@ impl DmPreProcessor {
             DmDefineDefinition::new_basic_replace("EFFECTS_LAYER", &["5000".into()]),
             DmDefineDefinition::new_basic_replace("TOPDOWN_LAYER", &["10000".into()]),
             DmDefineDefinition::new_basic_replace("BACKGROUND_LAYER", &["20000".into()]),
-            DmDefineDefinition::new_basic_replace("FLOAT_PLANE", &["-32767".into()]),
+            DmDefineDefinition::new_basic_replace("FLOAT_PLANE", &["-".into(), "32767".into()]),
             DmDefineDefinition::new_basic_replace("TOPDOWN_MAP", &["0".into()]),
             DmDefineDefinition::new_basic_replace("ISOMETRIC_MAP", &["1".into()]),
             DmDefineDefinition::new_basic_replace("SIDE_MAP", &["2".into()]),
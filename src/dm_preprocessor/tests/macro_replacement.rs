@@ -0,0 +1,41 @@
+use std::path::Path;
+
+use crate::{dm_preprocessor::lib::DmPreProcessor, tokens::tokenize::DmTokenizer};
+
+fn preprocess_lines(lines: &[&str]) -> String {
+    let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+    let mut preprocessor = DmPreProcessor::new();
+    preprocessor
+        .preprocess_tokens(DmTokenizer::new(Path::new("test.dm"), &lines))
+        .map(|token| token.expect("failed to preprocess").value().to_string())
+        .collect()
+}
+
+#[test]
+fn test_macro_replacement() {
+    let output = preprocess_lines(&[
+        "#define ADD(a, b) (a + b)",
+        "#define CONCAT(a, b) a##b",
+        "#define NAME(x) #x",
+        "ADD(1, f(2, 3))",
+        "CONCAT(foo, bar)",
+        "NAME(thing)",
+    ]);
+    assert_eq!(output, "(1 + f(2, 3))\nfoobar\n\"thing\"\n");
+}
+
+#[test]
+fn test_macro_missing_argument_stays_literal() {
+    let output = preprocess_lines(&["#define PAIR(a, b) a:b", "PAIR(1)"]);
+    assert_eq!(output, "1:b\n");
+}
+
+#[test]
+fn test_define_body_ends_before_comment() {
+    let output = preprocess_lines(&[
+        "#define X 1 // lies:ignore(unused_var)",
+        "#define Y(a) (a) // lies:ignore-next-line",
+        "X + Y(2)",
+    ]);
+    assert_eq!(output, "1 + (2)\n");
+}
@@ -1 +1,3 @@
 mod lib;
+mod macro_replacement;
+mod token_stream;
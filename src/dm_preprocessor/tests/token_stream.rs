@@ -0,0 +1,34 @@
+use std::cell::Cell;
+
+use crate::{
+    dm_preprocessor::{
+        define_definition::DmDefineDefinition,
+        lib::DmPreProcessor,
+        token_stream::{TokenBuffer, TokenStream},
+    },
+    tokens::dm_token::DmToken,
+};
+
+#[test]
+fn test_macro_call_only_pulls_needed_tokens() {
+    let mut preprocess = DmPreProcessor::new();
+    preprocess.add_define(DmDefineDefinition::new_basic_replace("FLAG", &["1".into()]));
+
+    let pulled = Cell::new(0);
+    let source = ["FLAG", " ", "after", " ", "never_pulled"]
+        .into_iter()
+        .map(|value| {
+            pulled.set(pulled.get() + 1);
+            DmToken::from(value)
+        });
+    let mut tokens = TokenStream::new(source);
+
+    let first = tokens.pop_front().unwrap();
+    assert!(preprocess
+        .do_define_replacement(first, &mut tokens)
+        .unwrap()
+        .is_none());
+    assert_eq!(tokens.pop_front(), Some(DmToken::from("1")));
+    assert_eq!(tokens.pop_front(), Some(DmToken::from(" ")));
+    assert_eq!(pulled.get(), 2);
+}
@@ -0,0 +1,61 @@
+use std::collections::VecDeque;
+
+use crate::tokens::dm_token::DmToken;
+
+/// Anything the preprocessor can pull tokens from and push replacements back into.
+pub trait TokenBuffer {
+    fn pop_front(&mut self) -> Option<DmToken>;
+    fn front(&mut self) -> Option<&DmToken>;
+    fn push_front(&mut self, token: DmToken);
+
+    fn is_empty(&mut self) -> bool {
+        self.front().is_none()
+    }
+}
+
+impl TokenBuffer for VecDeque<DmToken> {
+    fn pop_front(&mut self) -> Option<DmToken> {
+        VecDeque::pop_front(self)
+    }
+
+    fn front(&mut self) -> Option<&DmToken> {
+        VecDeque::front(self)
+    }
+
+    fn push_front(&mut self, token: DmToken) {
+        VecDeque::push_front(self, token)
+    }
+}
+
+/// Pulls tokens lazily from a tokenizer.
+/// Only define replacements and the lookahead needed for macro calls are buffered.
+pub struct TokenStream<I: Iterator<Item = DmToken>> {
+    lookahead: VecDeque<DmToken>,
+    source: I,
+}
+
+impl<I: Iterator<Item = DmToken>> TokenStream<I> {
+    pub fn new(source: I) -> Self {
+        Self {
+            lookahead: VecDeque::new(),
+            source,
+        }
+    }
+}
+
+impl<I: Iterator<Item = DmToken>> TokenBuffer for TokenStream<I> {
+    fn pop_front(&mut self) -> Option<DmToken> {
+        self.lookahead.pop_front().or_else(|| self.source.next())
+    }
+
+    fn front(&mut self) -> Option<&DmToken> {
+        if self.lookahead.is_empty() {
+            self.lookahead.extend(self.source.next());
+        }
+        self.lookahead.front()
+    }
+
+    fn push_front(&mut self, token: DmToken) {
+        self.lookahead.push_front(token)
+    }
+}
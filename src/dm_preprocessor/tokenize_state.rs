@@ -1,20 +1,29 @@
-use std::collections::VecDeque;
+use std::{collections::VecDeque, sync::Arc};
 
 use log::trace;
 
 use crate::{
-    tokens::dm_token::DmToken,
-    util::{condense_lines::condense_lines, count_backslashes},
+    tokens::{dm_token::DmToken, source_text::SourceText},
+    util::count_backslashes,
 };
 
 #[derive(Debug, Default)]
 pub struct TokenizeState {
-    current_line: String,
-    remaining_lines: VecDeque<String>,
-    remaining_chars: VecDeque<char>,
+    source: Arc<SourceText>,
+    next_line_index: usize,
+    /// Byte offset of the next character to be read.
+    cursor: usize,
+    /// Byte offset of the end of the current line, excluding the `\n`.
+    line_end: usize,
+    /// Byte offset of the start of the current line.
+    line_start: usize,
+    /// Byte offset of the last character returned by `next_char`.
+    last_char_position: usize,
     in_quote: Option<char>,
     in_string_special_escape: bool,
     comment_single: bool,
+    /// Byte offset of the `//` that started the current single-line comment.
+    comment_single_start: usize,
     comment_multi: usize,
     in_preprocessor: bool,
     line_tokens: Vec<DmToken>,
@@ -194,9 +203,19 @@ impl TokenizeState {
         } else {
             trace!("Setting comment single to false");
         }
+        if comment_single && !self.comment_single {
+            // the second `/` was just read
+            self.comment_single_start = self.last_char_position.saturating_sub(1);
+        }
         self.comment_single = comment_single;
     }
 
+    /// The byte range of the single-line comment in the current line, up to the end of the line.
+    pub fn comment_single_range(&self) -> Option<(usize, usize)> {
+        self.comment_single
+            .then_some((self.comment_single_start, self.line_end))
+    }
+
     pub fn increment_comment_multi(&mut self) {
         self.comment_multi += 1;
         trace!("Incrementing comment multi to {}", self.comment_multi);
@@ -224,38 +243,54 @@ impl TokenizeState {
     }
 
     pub fn next_line(&mut self) -> bool {
-        if let Some(line) = self.remaining_lines.pop_front() {
-            self.remaining_chars = line.chars().collect();
-            self.current_line = line;
-            true
-        } else {
-            false
+        if self.next_line_index >= self.source.line_count() {
+            return false;
         }
+        let (start, end) = self.source.line_range(self.next_line_index);
+        self.next_line_index += 1;
+        self.line_start = start;
+        self.cursor = start;
+        self.line_end = end;
+        true
     }
 
-    pub fn current_line(&self) -> &String {
-        &self.current_line
+    pub fn current_line(&self) -> &str {
+        &self.source.text()[self.line_start..self.line_end]
     }
 
     pub fn next_char(&mut self) -> Option<char> {
-        self.remaining_chars.pop_front()
+        let char = self.next_char_peek()?;
+        self.last_char_position = self.cursor;
+        self.cursor += char.len_utf8();
+        Some(char)
+    }
+
+    pub fn next_char_peek(&self) -> Option<char> {
+        self.source.text()[self.cursor..self.line_end]
+            .chars()
+            .next()
     }
 
-    pub fn next_char_peek(&self) -> Option<&char> {
-        self.remaining_chars.front()
+    /// Byte offset of the last character returned by `next_char`.
+    pub fn last_char_position(&self) -> usize {
+        self.last_char_position
     }
 
-    pub fn remaining_chars(&self) -> &VecDeque<char> {
-        &self.remaining_chars
+    /// Token for the line break at the end of the current line.
+    pub fn line_break_token(&self) -> DmToken {
+        DmToken::from_span(&self.source, self.line_end, self.line_end + 1)
     }
 
-    pub fn remaining_lines(&self) -> &VecDeque<String> {
-        &self.remaining_lines
+    pub fn source(&self) -> &Arc<SourceText> {
+        &self.source
     }
 
-    pub fn set_lines(&mut self, lines: &[String]) {
-        let lines = condense_lines(lines);
-        self.remaining_lines = lines.into();
+    pub fn set_source(&mut self, source: Arc<SourceText>) {
+        self.source = source;
+        self.next_line_index = 0;
+        self.line_start = 0;
+        self.cursor = 0;
+        self.line_end = 0;
     }
 
     pub fn set_token_is_in_string(&mut self, token_is_in_string: bool) {
@@ -1,8 +1,19 @@
-use dm_parser::lib::DmParser;
+use dm_parser::{
+    baseline::Baseline,
+    diagnostics::{Diagnostic, Severity},
+    diff_scope::DiffScope,
+    lib::DmParser,
+    render::render,
+};
 use dotenv::dotenv;
 use log::{error, info, log, Level};
-use std::env;
-use util::log::LOGGER;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokens::{source_text::SourceText, span::Span};
+use util::{log::LOGGER, ParseError};
 
 pub mod dm_parser;
 pub mod dm_preprocessor;
@@ -25,42 +36,159 @@ pub fn main() {
         if result { "Done" } else { "Failed" },
         stopwatch.elapsed().as_secs_f32()
     );
+    // opt in, so that checking a codebase that has no baseline yet does not fail
+    if !result && env::args().any(|arg| arg == "--fail-on-error") {
+        std::process::exit(1);
+    }
 }
 
+/// False if parsing failed or an error is left after the baseline and the diff scope. With
+/// `--fail-on-error` that ends the process with a failure status, so that CI fails on it.
 fn lies() -> bool {
     let game_dir = env::var("GAME_DIR").expect("GAME_DIR not set.");
     let dme_file = env::var("DME_FILE").expect("DME_FILE not set.");
     let mut parser = DmParser::new(game_dir);
     let result = parser.load_path(dme_file);
+    let mut has_errors = false;
 
     if result.is_err() {
         let parse_error = result.as_ref().err().unwrap();
         error!("Error while parsing:");
-        error!("\t{}", parse_error.to_string());
-        if let Some(file_path) = parse_error.file_path() {
-            let canonical = parser
-                .environment_directory()
-                .join(file_path)
-                .canonicalize()
-                .unwrap();
-            error!(
-                "\tat {}{}",
-                canonical.display(),
-                parse_error
-                    .line_number()
-                    .map(|num| format!(":{num}"))
-                    .unwrap_or_default()
-            );
-        } else {
-            error!("\\- at unknown location");
-        }
+        let diagnostic = parse_error_diagnostic(parse_error, parser.environment_directory())
+            .unwrap_or_else(|| unlocated_parse_error(parse_error));
+        LOGGER.log_rendered(Level::Error, |color| render(&diagnostic, color));
     } else {
         info!("Success.");
+        info!("Object tree has {} types", parser.object_tree().len());
+        let diagnostics = baseline_diagnostics(parser.diagnostics());
+        for diagnostic in diff_scoped(diagnostics, parser.environment_directory()) {
+            has_errors |= diagnostic.severity == Severity::Error;
+            let level = match diagnostic.severity {
+                Severity::Error => Level::Error,
+                Severity::Warning => Level::Warn,
+                Severity::Info | Severity::Hint => Level::Info,
+            };
+            LOGGER.log_rendered(level, |color| render(&diagnostic, color));
+        }
     };
     info!(
         "Log file can be found at {}",
         LOGGER.get_log_file_full_path().display()
     );
 
-    result.is_ok()
+    result.is_ok() && !has_errors
+}
+
+/// Points to the line a parse error is on, read back from the file as the tokens that were
+/// parsed are gone. `None` when the error has no location.
+fn parse_error_diagnostic(
+    parse_error: &ParseError,
+    environment_directory: &Path,
+) -> Option<Diagnostic> {
+    let file_path = parse_error.file_path()?;
+    let line = parse_error.line_number()?;
+    let text = fs::read(environment_directory.join(file_path)).ok()?;
+    let lines: Vec<String> = String::from_utf8_lossy(&text)
+        .lines()
+        .map(str::to_string)
+        .collect();
+    let source = Arc::new(SourceText::new(file_path, &lines));
+    let (start, end) = source.line_range_of(line)?;
+    let line_text = &source.text()[start..end];
+    let indent = line_text.len() - line_text.trim_start().len();
+    let span = Span::new(&source, start + indent, end.max(start + indent));
+    Some(Diagnostic::error(
+        "parse_error",
+        &span,
+        parse_error.to_string(),
+    ))
+}
+
+/// A parse error whose line cannot be read back, reported without a snippet.
+fn unlocated_parse_error(parse_error: &ParseError) -> Diagnostic {
+    let diagnostic = Diagnostic::error("parse_error", &Span::unknown(), format!("{parse_error}"));
+    match parse_error.file_path() {
+        Some(file_path) => {
+            let line = parse_error
+                .line_number()
+                .map(|line| format!(":{line}"))
+                .unwrap_or_default();
+            diagnostic.with_note(&Span::unknown(), format!("at {file_path}{line}"))
+        }
+        None => diagnostic.with_note(&Span::unknown(), "at an unknown location"),
+    }
+}
+
+/// Leaves out the diagnostics recorded in the baseline file, `LIES_BASELINE_FILE` or
+/// `lies-baseline.txt`. With `--update-baseline` the file is rewritten to record every current
+/// diagnostic instead.
+fn baseline_diagnostics(diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+    let path = PathBuf::from(
+        env::var("LIES_BASELINE_FILE").unwrap_or_else(|_| "lies-baseline.txt".to_string()),
+    );
+    if env::args().any(|arg| arg == "--update-baseline") {
+        let baseline = Baseline::from_diagnostics(&diagnostics);
+        match baseline.save(&path) {
+            Ok(()) => info!(
+                "Recorded {} diagnostics in baseline {}",
+                baseline.len(),
+                path.display()
+            ),
+            Err(error) => error!("Failed to write baseline {}: {error}", path.display()),
+        }
+        return vec![];
+    }
+    if !path.exists() {
+        return diagnostics;
+    }
+    let baseline = match Baseline::load(&path) {
+        Ok(baseline) => baseline,
+        Err(error) => {
+            error!("Failed to read baseline {}: {error}", path.display());
+            return diagnostics;
+        }
+    };
+    let total = diagnostics.len();
+    let (new, fixed) = baseline.filter(diagnostics);
+    info!(
+        "Baseline {} hides {} of {total} diagnostics",
+        path.display(),
+        total - new.len()
+    );
+    if fixed > 0 {
+        info!("{fixed} baselined diagnostics are gone, run with --update-baseline to drop them");
+    }
+    new
+}
+
+/// Only keeps the diagnostics on lines that a change touches, for checking pull requests. The
+/// change is the unified diff in `LIES_DIFF_FILE`, or the difference between the tree in
+/// `LIES_DIFF_BASE_DIR` and the environment directory.
+fn diff_scoped(diagnostics: Vec<Diagnostic>, environment_directory: &Path) -> Vec<Diagnostic> {
+    let scope = if let Ok(path) = env::var("LIES_DIFF_FILE") {
+        fs::read_to_string(&path)
+            .map_err(|error| error.to_string())
+            .and_then(|diff| DiffScope::from_unified_diff(&diff))
+            .map_err(|error| format!("Failed to read diff {path}: {error}"))
+    } else if let Ok(path) = env::var("LIES_DIFF_BASE_DIR") {
+        DiffScope::from_trees(path.as_ref(), environment_directory)
+            .map_err(|error| format!("Failed to compare with {path}: {error}"))
+    } else {
+        return diagnostics;
+    };
+    match scope {
+        Ok(scope) => {
+            let total = diagnostics.len();
+            let diagnostics = scope.filter(diagnostics);
+            info!(
+                "{} of {total} diagnostics are on changed lines",
+                diagnostics.len()
+            );
+            diagnostics
+        }
+        Err(error) => {
+            error!("{error}");
+            diagnostics
+        }
+    }
 }
@@ -1,26 +1,76 @@
-use std::fmt::Display;
+use std::{
+    fmt::{Debug, Display},
+    sync::Arc,
+};
+
+use super::{source_text::SourceText, span::Span};
 
 #[derive(Debug, Clone)]
 pub struct DmToken {
-    value: String,
+    text: TokenText,
     is_in_string: bool,
-    line: Option<usize>,
-    column: Option<usize>,
+    /// A `// lies:` comment the tokenizer kept for the analysis, the preprocessor drops it.
+    is_comment: bool,
+    /// The use of the define this token was copied out of.
+    expansion: Option<Arc<Span>>,
+}
+
+/// Tokens read from a file share its source buffer, only synthesized tokens own their text.
+#[derive(Clone)]
+enum TokenText {
+    Span {
+        source: Arc<SourceText>,
+        start: u32,
+        end: u32,
+    },
+    Owned(Box<str>),
+}
+
+impl Debug for TokenText {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+impl TokenText {
+    fn as_str(&self) -> &str {
+        match self {
+            TokenText::Span { source, start, end } => {
+                &source.text()[*start as usize..*end as usize]
+            }
+            TokenText::Owned(value) => value,
+        }
+    }
 }
 
 impl Display for DmToken {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.value)
+        write!(f, "{}", self.value())
     }
 }
 
 impl DmToken {
     pub fn new(value: String) -> Self {
         Self {
-            value,
+            text: TokenText::Owned(value.into_boxed_str()),
             is_in_string: false,
-            line: None,
-            column: None,
+            is_comment: false,
+            expansion: None,
+        }
+    }
+
+    /// Creates a token referencing `source` from byte `start` to byte `end`.
+    pub fn from_span(source: &Arc<SourceText>, start: usize, end: usize) -> Self {
+        debug_assert!(source.text().get(start..end).is_some());
+        Self {
+            text: TokenText::Span {
+                source: source.clone(),
+                start: start as u32,
+                end: end as u32,
+            },
+            is_in_string: false,
+            is_comment: false,
+            expansion: None,
         }
     }
 
@@ -29,29 +79,80 @@ impl DmToken {
         self
     }
 
+    pub fn with_is_comment(mut self, is_comment: bool) -> Self {
+        self.is_comment = is_comment;
+        self
+    }
+
+    /// Marks the token as copied out of the body of a define used at `call_site`.
+    pub fn with_expansion(mut self, call_site: &Arc<Span>) -> Self {
+        self.expansion = Some(call_site.clone());
+        self
+    }
+
+    /// The span to record as the expansion of the tokens of a define used at this token, which
+    /// is the outermost use when this token was itself expanded.
+    pub fn call_site(&self) -> Option<Arc<Span>> {
+        match &self.expansion {
+            Some(expansion) => Some(expansion.clone()),
+            None => self.source().map(|_| Arc::new(self.span())),
+        }
+    }
+
     pub fn value(&self) -> &str {
-        &self.value
+        self.text.as_str()
     }
 
     pub fn is_only_whitespace(&self, include_newline: bool) -> bool {
         if include_newline {
-            self.value.chars().all(char::is_whitespace)
+            self.value().chars().all(char::is_whitespace)
         } else {
-            self.value.chars().all(|c| c.is_whitespace() && c != '\n')
+            self.value().chars().all(|c| c.is_whitespace() && c != '\n')
+        }
+    }
+
+    /// The source buffer this token was read from, if it was not synthesized.
+    pub fn source(&self) -> Option<&Arc<SourceText>> {
+        match &self.text {
+            TokenText::Span { source, .. } => Some(source),
+            TokenText::Owned(_) => None,
         }
     }
 
-    pub fn set_line(&mut self, line: usize) {
-        self.line = Some(line);
+    /// Where this token was read from. Synthesized tokens have an unknown span.
+    pub fn span(&self) -> Span {
+        match &self.text {
+            TokenText::Span { source, start, end } => {
+                Span::new(source, *start as usize, *end as usize)
+                    .with_expansion(self.expansion.clone())
+            }
+            TokenText::Owned(_) => Span::unknown(),
+        }
+    }
+
+    /// The 1-based line and column this token starts at in its file.
+    pub fn line_column(&self) -> Option<(usize, usize)> {
+        match &self.text {
+            TokenText::Span { source, start, .. } => Some(source.location_of(*start as usize)),
+            TokenText::Owned(_) => None,
+        }
     }
 
-    pub fn set_column(&mut self, column: usize) {
-        self.column = Some(column);
+    pub fn line(&self) -> Option<usize> {
+        self.line_column().map(|(line, _)| line)
+    }
+
+    pub fn column(&self) -> Option<usize> {
+        self.line_column().map(|(_, column)| column)
     }
 
     pub fn is_in_string(&self) -> bool {
         self.is_in_string
     }
+
+    pub fn is_comment(&self) -> bool {
+        self.is_comment
+    }
 }
 
 impl From<&str> for DmToken {
@@ -86,6 +187,6 @@ impl From<&String> for DmToken {
 
 impl PartialEq for DmToken {
     fn eq(&self, other: &Self) -> bool {
-        self.value == other.value
+        self.value() == other.value()
     }
 }
@@ -1,7 +1,9 @@
 mod constants;
 pub mod dm_token;
+pub mod source_text;
+pub mod span;
 mod token_action;
-mod tokenize;
+pub mod tokenize;
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,124 @@
+use std::{
+    fmt::Debug,
+    path::{Path, PathBuf},
+};
+
+use crate::util::condense_lines::condense_lines_numbered;
+
+/// The condensed text of a single file.
+/// Tokens taken from the file reference this buffer by span instead of owning their text.
+#[derive(Default)]
+pub struct SourceText {
+    path: PathBuf,
+    text: String,
+    /// Byte offset of the start of each condensed line.
+    line_starts: Vec<usize>,
+    /// Byte offset of the text of each original line and its 1-based line number, several
+    /// original lines share a condensed line after a `\` continuation.
+    original_lines: Vec<(usize, usize)>,
+}
+
+impl Debug for SourceText {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SourceText({})", self.path.display())
+    }
+}
+
+impl SourceText {
+    /// Every condensed line is followed by a `\n` so that line breaks can be referenced as well.
+    pub fn new(path: impl Into<PathBuf>, lines: &[String]) -> Self {
+        let mut text = String::new();
+        let mut line_starts = vec![];
+        let mut original_lines = vec![];
+        for line in condense_lines_numbered(lines) {
+            let start = text.len();
+            line_starts.push(start);
+            original_lines.extend(
+                line.parts
+                    .iter()
+                    .map(|&(index, offset)| (start + offset, index + 1)),
+            );
+            text.push_str(&line.text);
+            text.push('\n');
+        }
+        Self {
+            path: path.into(),
+            text,
+            line_starts,
+            original_lines,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Returns the byte range of the condensed line, excluding the trailing `\n`.
+    pub fn line_range(&self, line_index: usize) -> (usize, usize) {
+        let start = self.line_starts[line_index];
+        let end = self
+            .line_starts
+            .get(line_index + 1)
+            .copied()
+            .unwrap_or(self.text.len());
+        (start, end - 1)
+    }
+
+    fn line_index_of(&self, offset: usize) -> usize {
+        self.line_starts
+            .partition_point(|start| *start <= offset)
+            .saturating_sub(1)
+    }
+
+    /// Returns the 1-based line and column of a byte offset in the original file.
+    pub fn location_of(&self, offset: usize) -> (usize, usize) {
+        let index = self
+            .original_lines
+            .partition_point(|(start, _)| *start <= offset)
+            .saturating_sub(1);
+        let Some(&(start, line_number)) = self.original_lines.get(index) else {
+            return (1, 1);
+        };
+        let column = self.text[start..offset].chars().count() + 1;
+        (line_number, column)
+    }
+
+    /// Returns the byte range of the condensed line containing the given original line number.
+    pub fn line_range_of(&self, line_number: usize) -> Option<(usize, usize)> {
+        let index = self
+            .original_lines
+            .partition_point(|(_, number)| *number <= line_number)
+            .checked_sub(1)?;
+        let (start, _) = self.original_lines[index];
+        Some(self.line_range(self.line_index_of(start)))
+    }
+
+    /// Returns the text of a line of the original file, without the `\` that continues it.
+    pub fn original_line_text(&self, line_number: usize) -> Option<&str> {
+        let index = self
+            .original_lines
+            .binary_search_by_key(&line_number, |&(_, number)| number)
+            .ok()?;
+        let (start, _) = self.original_lines[index];
+        let (_, line_end) = self.line_range(self.line_index_of(start));
+        let end = match self.original_lines.get(index + 1) {
+            Some(&(next_start, _)) if next_start <= line_end => next_start,
+            _ => line_end,
+        };
+        Some(&self.text[start..end])
+    }
+
+    /// Returns the text of the condensed line containing the given original line number.
+    pub fn line_text(&self, line_number: usize) -> Option<&str> {
+        let (start, end) = self.line_range_of(line_number)?;
+        Some(&self.text[start..end])
+    }
+}
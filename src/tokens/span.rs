@@ -0,0 +1,130 @@
+use std::{
+    fmt::{Debug, Display},
+    path::Path,
+    sync::Arc,
+};
+
+use super::source_text::SourceText;
+
+/// A byte range in a source buffer.
+/// Spans of synthesized tokens have no source and only report an unknown location.
+#[derive(Clone, Default)]
+pub struct Span {
+    source: Option<Arc<SourceText>>,
+    start: usize,
+    end: usize,
+    /// The macro call the code was expanded from, when it comes from the body of a define.
+    expansion: Option<Arc<Span>>,
+}
+
+impl Span {
+    pub fn new(source: &Arc<SourceText>, start: usize, end: usize) -> Self {
+        Self {
+            source: Some(source.clone()),
+            start,
+            end,
+            expansion: None,
+        }
+    }
+
+    pub fn with_expansion(mut self, expansion: Option<Arc<Span>>) -> Self {
+        self.expansion = expansion;
+        self
+    }
+
+    /// Where the define this span is in the body of was used, the outermost use for nested
+    /// defines.
+    pub fn expansion(&self) -> Option<&Span> {
+        self.expansion.as_deref()
+    }
+
+    pub fn unknown() -> Self {
+        Self::default()
+    }
+
+    pub fn is_known(&self) -> bool {
+        self.source.is_some()
+    }
+
+    pub fn source(&self) -> Option<&Arc<SourceText>> {
+        self.source.as_ref()
+    }
+
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.source.as_ref().map(|source| source.path())
+    }
+
+    /// The 1-based line and column this span starts at.
+    pub fn line_column(&self) -> Option<(usize, usize)> {
+        self.source
+            .as_ref()
+            .map(|source| source.location_of(self.start))
+    }
+
+    pub fn line(&self) -> Option<usize> {
+        self.line_column().map(|(line, _)| line)
+    }
+
+    /// The 1-based line this span ends on.
+    pub fn end_line(&self) -> Option<usize> {
+        self.source
+            .as_ref()
+            .map(|source| source.location_of(self.end.max(self.start + 1) - 1).0)
+    }
+
+    /// Returns a span covering both spans if they are in the same source, otherwise `self`.
+    pub fn to(&self, other: &Span) -> Span {
+        match (&self.source, &other.source) {
+            (Some(source), Some(other_source))
+                if Arc::ptr_eq(source, other_source) && other.end >= self.start =>
+            {
+                Span::new(source, self.start, other.end.max(self.end))
+                    .with_expansion(self.expansion.clone())
+            }
+            (None, _) => other.clone(),
+            _ => self.clone(),
+        }
+    }
+
+    pub fn text(&self) -> Option<&str> {
+        self.source
+            .as_ref()
+            .map(|source| &source.text()[self.start..self.end])
+    }
+}
+
+impl PartialEq for Span {
+    fn eq(&self, other: &Self) -> bool {
+        let same_source = match (&self.source, &other.source) {
+            (Some(source), Some(other_source)) => Arc::ptr_eq(source, other_source),
+            (None, None) => true,
+            _ => false,
+        };
+        same_source && self.start == other.start && self.end == other.end
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.path(), self.line_column()) {
+            (Some(path), Some((line, column))) => {
+                write!(f, "{}:{}:{}", path.display(), line, column)
+            }
+            _ => write!(f, "<unknown>"),
+        }
+    }
+}
+
+impl Debug for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Span({})", self)
+    }
+}
@@ -26,3 +26,20 @@ fn test_tokenize_comment() {
     let result = preprocessor.test_tokenize(&lines);
     assert_eq!(result, expected);
 }
+
+#[test]
+fn test_tokenize_directive_comment() {
+    use crate::{dm_preprocessor::lib::DmPreProcessor, tokens::dm_token::DmToken};
+
+    let mut preprocessor = DmPreProcessor::new();
+    let lines = vec![
+        "x = 1 // lies:ignore(unused_var)",
+        "// lies is not a directive",
+    ];
+
+    let result = preprocessor.test_tokenize(&lines);
+    let comments: Vec<&DmToken> = result.iter().filter(|token| token.is_comment()).collect();
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0].value(), "// lies:ignore(unused_var)");
+    assert_eq!(result.last().map(DmToken::value), Some("\n"));
+}
@@ -1,6 +1,6 @@
 use crate::dm_preprocessor::lib::DmPreProcessor;
 
-use super::dm_token::DmToken;
+use super::{dm_token::DmToken, tokenize::DmTokenizer};
 
 mod comment;
 mod comment_multiline;
@@ -14,13 +14,13 @@ mod multi_empty;
 mod multi_line;
 mod quote_interior;
 mod single_line;
+mod spans;
 mod string_interop;
 mod unmatched_quotes;
 
 impl DmPreProcessor {
     pub fn test_tokenize(&mut self, lines: &[&str]) -> Vec<DmToken> {
         let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
-        self.tokenize_state.set_lines(&lines);
-        self.start_tokenizing()
+        DmTokenizer::new(self.get_current_file(), &lines).collect()
     }
 }
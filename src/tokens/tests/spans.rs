@@ -0,0 +1,43 @@
+#[test]
+fn test_spans() {
+    use crate::dm_preprocessor::lib::DmPreProcessor;
+
+    let mut preprocessor = DmPreProcessor::new();
+
+    let lines = vec!["/obj/var/x = \\", "  \"ab[c]\"", "/mob"];
+
+    let result = preprocessor.test_tokenize(&lines);
+    let source = result[0].source().expect("token was not a span").clone();
+    assert!(result.iter().all(|token| token
+        .source()
+        .is_some_and(|other| std::sync::Arc::ptr_eq(other, &source))));
+
+    let located: Vec<(&str, Option<(usize, usize)>)> = result
+        .iter()
+        .map(|token| (token.value(), token.line_column()))
+        .collect();
+    assert_eq!(
+        located,
+        vec![
+            ("/", Some((1, 1))),
+            ("obj", Some((1, 2))),
+            ("/", Some((1, 5))),
+            ("var", Some((1, 6))),
+            ("/", Some((1, 9))),
+            ("x", Some((1, 10))),
+            (" ", Some((1, 11))),
+            ("=", Some((1, 12))),
+            ("   ", Some((1, 13))),
+            ("\"", Some((2, 3))),
+            ("ab", Some((2, 4))),
+            ("[", Some((2, 6))),
+            ("c", Some((2, 7))),
+            ("]", Some((2, 8))),
+            ("\"", Some((2, 9))),
+            ("\n", Some((2, 10))),
+            ("/", Some((3, 1))),
+            ("mob", Some((3, 2))),
+            ("\n", Some((3, 5))),
+        ]
+    );
+}
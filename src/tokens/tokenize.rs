@@ -1,4 +1,4 @@
-use std::char;
+use std::{char, collections::VecDeque, path::Path, sync::Arc};
 
 use log::{error, trace};
 
@@ -21,44 +21,171 @@ use quotes::handle_quotes;
 use star::handle_star;
 
 use crate::{
-    dm_preprocessor::lib::DmPreProcessor, tokens::token_action::TokenAction,
-    util::count_backslashes,
+    dm_preprocessor::tokenize_state::TokenizeState,
+    tokens::token_action::TokenAction,
+    util::{count_backslashes, dm_file::DmFile},
 };
 
-use super::dm_token::DmToken;
+use super::{dm_token::DmToken, source_text::SourceText};
 
-impl DmPreProcessor {
-    /// Tokenizes the current file.
-    pub fn start_tokenizing(&mut self) -> Vec<DmToken> {
-        let mut tokens: Vec<DmToken> = vec![];
+/// Comments starting with this are kept as tokens for the analysis.
+pub const DIRECTIVE_COMMENT_PREFIX: &str = "lies:";
 
-        while self.tokenize_state.next_line() {
-            self.tokenize_state.set_in_preprocessor(false);
-            self.tokenize_state.set_comment_single(false);
+/// Lazily tokenizes a file one line at a time.
+/// Only the tokens of the line currently being handed out are buffered.
+pub struct DmTokenizer {
+    tokenize_state: TokenizeState,
+    line_tokens: VecDeque<DmToken>,
+    finished: bool,
+}
 
-            let token = self.get_token();
+impl DmTokenizer {
+    pub fn new(path: &Path, lines: &[String]) -> Self {
+        Self::from_source(Arc::new(SourceText::new(path, lines)))
+    }
 
-            if !token.is_empty() {
-                self.tokenize_state.add_line_token(token);
-            }
+    pub fn from_file(file: &DmFile) -> Self {
+        Self::new(file.path(), file.lines())
+    }
+
+    pub fn from_source(source: Arc<SourceText>) -> Self {
+        let mut tokenize_state = TokenizeState::default();
+        tokenize_state.set_source(source);
+        Self {
+            tokenize_state,
+            line_tokens: VecDeque::new(),
+            finished: false,
+        }
+    }
 
-            self.tokenize_state.add_line_token("\n");
-            tokens.append(&mut self.tokenize_state.finalize_line_tokens());
-
-            if self.tokenize_state.in_quote().is_some()
-                && !self.tokenize_state.in_preprocessor()
-                && !self.tokenize_state.multiline_string()
-            {
-                error!(
-                    "Unterminated quote `{}` in line `{}`",
-                    self.tokenize_state.in_quote().unwrap(),
-                    self.tokenize_state.current_line()
-                );
-                panic!();
+    pub fn path(&self) -> &Path {
+        self.tokenize_state.source().path()
+    }
+}
+
+/// The token currently being built.
+/// It stays a span into the source unless characters in the middle of it were skipped.
+#[derive(Default)]
+struct PendingToken {
+    start: usize,
+    end: usize,
+    detached: Option<String>,
+}
+
+impl PendingToken {
+    fn starting_at(position: usize, char: char) -> Self {
+        Self {
+            start: position,
+            end: position + char.len_utf8(),
+            detached: None,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.start == self.end && self.detached.is_none()
+    }
+
+    fn push(&mut self, source: &SourceText, position: usize, char: char) {
+        if let Some(detached) = &mut self.detached {
+            detached.push(char);
+        } else if self.start == self.end {
+            *self = Self::starting_at(position, char);
+        } else if position == self.end {
+            self.end += char.len_utf8();
+        } else {
+            let mut detached = self.text(source).to_string();
+            detached.push(char);
+            self.detached = Some(detached);
+        }
+    }
+
+    fn text<'a>(&'a self, source: &'a SourceText) -> &'a str {
+        match &self.detached {
+            Some(detached) => detached,
+            None => &source.text()[self.start..self.end],
+        }
+    }
+
+    fn into_token(self, source: &Arc<SourceText>) -> DmToken {
+        match self.detached {
+            Some(detached) => DmToken::new(detached),
+            None => DmToken::from_span(source, self.start, self.end),
+        }
+    }
+}
+
+impl Iterator for DmTokenizer {
+    type Item = DmToken;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.line_tokens.is_empty() {
+            if self.finished {
+                return None;
             }
+            if !self.tokenize_next_line() {
+                self.finished = true;
+                self.check_end_of_file();
+            }
+        }
+        self.line_tokens.pop_front()
+    }
+}
+
+impl DmTokenizer {
+    /// Tokenizes the next line into the line buffer. Returns false once there are no lines left.
+    fn tokenize_next_line(&mut self) -> bool {
+        if !self.tokenize_state.next_line() {
+            return false;
+        }
+
+        self.tokenize_state.set_in_preprocessor(false);
+        self.tokenize_state.set_comment_single(false);
+
+        let token = self.get_token();
+
+        if !token.is_empty() {
+            let token = token.into_token(self.tokenize_state.source());
+            self.tokenize_state.add_line_token(token);
+        }
+
+        if let Some(comment) = self.directive_comment() {
+            self.tokenize_state.add_line_token(comment);
         }
 
-        let path = self.get_current_file();
+        let line_break = self.tokenize_state.line_break_token();
+        self.tokenize_state.add_line_token(line_break);
+        self.line_tokens
+            .extend(self.tokenize_state.finalize_line_tokens());
+
+        if self.tokenize_state.in_quote().is_some()
+            && !self.tokenize_state.in_preprocessor()
+            && !self.tokenize_state.multiline_string()
+        {
+            error!(
+                "Unterminated quote `{}` in line `{}`",
+                self.tokenize_state.in_quote().unwrap(),
+                self.tokenize_state.current_line()
+            );
+            panic!();
+        }
+
+        true
+    }
+
+    /// The single-line comment of the current line if it is a `// lies:` directive, such as
+    /// `// lies:ignore(unused_var)`. Other comments are dropped.
+    fn directive_comment(&self) -> Option<DmToken> {
+        let (start, end) = self.tokenize_state.comment_single_range()?;
+        let source = self.tokenize_state.source();
+        let text = source.text()[start..end]
+            .trim_start_matches('/')
+            .trim_start();
+        text.starts_with(DIRECTIVE_COMMENT_PREFIX)
+            .then(|| DmToken::from_span(source, start, end).with_is_comment(true))
+    }
+
+    fn check_end_of_file(&self) {
+        let path = self.path();
         if self.tokenize_state.unmatched_brackets() {
             error!("Unmatched brackets in file `{}`", path.display());
             panic!();
@@ -78,58 +205,65 @@ impl DmPreProcessor {
             error!("Unterminated multiline string in file `{}`", path.display());
             panic!();
         }
-
-        tokens
     }
 
     /// Returns the next token in the current line.
-    fn get_token(&mut self) -> String {
-        let mut token = String::new();
+    fn get_token(&mut self) -> PendingToken {
+        let source = self.tokenize_state.source().clone();
+        let mut token = PendingToken::default();
         let mut last_action = TokenAction::None;
 
         while let Some(char) = self.tokenize_state.next_char() {
             trace!("Char: `{}`", char.escape_debug());
+            let position = self.tokenize_state.last_char_position();
 
-            let next_action = self.get_token_action(char, &token);
+            let next_action = self.get_token_action(char, token.text(&source));
             if last_action == TokenAction::DelayTokenDrop && next_action != last_action {
-                token = String::new();
+                token = PendingToken::default();
             }
             last_action = next_action;
 
             match next_action {
                 TokenAction::StartNewToken => {
                     if !token.is_empty() {
-                        self.tokenize_state.add_line_token(token);
+                        self.tokenize_state
+                            .add_line_token(token.into_token(&source));
                     }
-                    token = char.to_string();
+                    token = PendingToken::starting_at(position, char);
                 }
                 TokenAction::ContinueToken => {
-                    token.push(char);
+                    token.push(&source, position, char);
                 }
                 TokenAction::EndToken => {
-                    token.push(char);
-                    self.tokenize_state.add_line_token(token);
-                    token = String::new();
+                    token.push(&source, position, char);
+                    self.tokenize_state
+                        .add_line_token(token.into_token(&source));
+                    token = PendingToken::default();
                 }
                 TokenAction::IsolateToken => {
                     if !token.is_empty() {
-                        self.tokenize_state.add_line_token(token);
+                        self.tokenize_state
+                            .add_line_token(token.into_token(&source));
                     }
-                    self.tokenize_state.add_line_token(char.to_string());
-                    token = String::new();
+                    self.tokenize_state.add_line_token(DmToken::from_span(
+                        &source,
+                        position,
+                        position + char.len_utf8(),
+                    ));
+                    token = PendingToken::default();
                 }
                 TokenAction::DropToken => {
-                    token = String::new();
+                    token = PendingToken::default();
                 }
                 TokenAction::None => {}
                 TokenAction::DelayTokenDrop => {
-                    token.push(char);
+                    token.push(&source, position, char);
                 }
             }
         }
 
         if last_action == TokenAction::DelayTokenDrop {
-            token = String::new();
+            token = PendingToken::default();
         }
         token
     }
@@ -219,10 +353,7 @@ impl DmPreProcessor {
         }
 
         if self.tokenize_state.multiline_string()
-            && !self
-                .tokenize_state
-                .next_char_peek()
-                .is_some_and(|c| *c == '}')
+            && self.tokenize_state.next_char_peek() != Some('}')
         {
             return TokenAction::ContinueToken;
         }
@@ -1,24 +1,51 @@
 // Condenses all lines that end with a backslash into a single line.
 pub fn condense_lines(lines: &[impl Into<String> + Clone]) -> Vec<String> {
-    if lines.is_empty() {
-        return vec![];
+    condense_lines_numbered(lines)
+        .into_iter()
+        .map(|line| line.text)
+        .collect()
+}
+
+// A line made of one or more original lines joined by trailing backslashes.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CondensedLine {
+    pub text: String,
+    // The index of every original line the condensed line is made of and the byte offset in
+    // `text` where its text starts.
+    pub parts: Vec<(usize, usize)>,
+}
+
+impl CondensedLine {
+    // The index of the original line the condensed line starts on.
+    pub fn first_line(&self) -> usize {
+        self.parts[0].0
     }
+}
 
+// Same as `condense_lines`, but keeps where every original line starts in the condensed lines.
+pub fn condense_lines_numbered(lines: &[impl Into<String> + Clone]) -> Vec<CondensedLine> {
     let mut condensed = vec![];
-    let mut current_line = String::new();
+    // the line being continued, which may have no text yet if its first lines were only `\`
+    let mut current: Option<CondensedLine> = None;
 
-    for line in lines {
+    for (index, line) in lines.iter().enumerate() {
         let line = line.clone().into();
-        if line.ends_with('\\') {
-            current_line.push_str(&line[..line.len() - 1]);
-        } else {
-            current_line.push_str(&line);
-            condensed.push(std::mem::take(&mut current_line));
+        let current_line = current.get_or_insert_with(|| CondensedLine {
+            text: String::new(),
+            parts: vec![],
+        });
+        current_line.parts.push((index, current_line.text.len()));
+        match line.strip_suffix('\\') {
+            Some(continued) => current_line.text.push_str(continued),
+            None => {
+                current_line.text.push_str(&line);
+                condensed.extend(current.take());
+            }
         }
     }
 
-    if !current_line.is_empty() {
-        condensed.push(current_line);
+    if let Some(current) = current.filter(|current| !current.text.is_empty()) {
+        condensed.push(current);
     }
 
     condensed
@@ -77,4 +104,34 @@ mod tests {
         let condensed = condense_lines(&lines);
         assert_eq!(condensed, lines);
     }
+
+    #[test]
+    fn condense_lines_numbered_keeps_first_line_index() {
+        let lines = vec!["a\\", "b", "c", "d\\", "e\\", "f"];
+        let condensed: Vec<(usize, String)> = condense_lines_numbered(&lines)
+            .into_iter()
+            .map(|line| (line.first_line(), line.text))
+            .collect();
+        assert_eq!(
+            condensed,
+            vec![
+                (0, "ab".to_string()),
+                (2, "c".to_string()),
+                (3, "def".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn condense_lines_numbered_keeps_empty_continuations() {
+        let lines = vec!["\\", "ab\\", "c"];
+        let condensed = condense_lines_numbered(&lines);
+        assert_eq!(
+            condensed,
+            vec![CondensedLine {
+                text: "abc".to_string(),
+                parts: vec![(0, 0), (1, 0), (2, 2)],
+            }]
+        );
+    }
 }
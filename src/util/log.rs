@@ -141,42 +141,62 @@ impl InternalLogger {
         format!("{}{}{}", color, message, "\x1b[0m")
     }
 
-    fn color_enabled(&self) -> bool {
+    pub fn color_enabled(&self) -> bool {
         stdout().is_terminal()
     }
-}
 
-impl Log for InternalLogger {
-    fn enabled(&self, _metadata: &log::Metadata) -> bool {
-        true
-    }
-
-    fn log(&self, record: &log::Record) {
-        if !self.enabled(record.metadata()) {
+    /// Logs a message that colours itself, such as a rendered diagnostic. `render` is given
+    /// whether to use colour, the log file always gets the plain version.
+    pub fn log_rendered(&self, level: Level, render: impl Fn(bool) -> String) {
+        if level > log::max_level() {
             return;
         }
+        let plain = render(false);
+        let colored = if self.color_enabled() {
+            render(true)
+        } else {
+            plain.clone()
+        };
+        self.write(level, &colored, &plain);
+    }
 
-        let record_body = record.args().to_string();
+    /// Writes a record to stdout and to the log file.
+    fn write(&self, level: Level, stdout_body: &str, file_body: &str) {
         let timestamp = chrono::Local::now().format("%H:%M:%S");
-        let colored = self.color(record.level(), &record_body);
-        let headers = format!("[{}][{}]", timestamp, record.level(),);
+        let headers = format!("[{}][{}]", timestamp, level,);
 
         // Trace output will never go to stdout
-        if record.level() < Level::Trace {
+        if level < Level::Trace {
             let mut stdout = stdout();
             stdout.write_all(headers.as_bytes()).unwrap();
             stdout.write_all(b" ").unwrap();
-            stdout.write_all(colored.as_bytes()).unwrap();
+            stdout.write_all(stdout_body.as_bytes()).unwrap();
             stdout.write_all(b"\n").unwrap();
             stdout.flush().expect("failed to write to stdout");
         }
         let mut log_file = self.log_file.try_clone().unwrap();
         log_file.write_all(headers.as_bytes()).unwrap();
         log_file.write_all(b" ").unwrap();
-        log_file.write_all(record_body.as_bytes()).unwrap();
+        log_file.write_all(file_body.as_bytes()).unwrap();
         log_file.write_all(b"\n").unwrap();
         log_file.flush().expect("failed to write to file");
     }
+}
+
+impl Log for InternalLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let record_body = record.args().to_string();
+        let colored = self.color(record.level(), &record_body);
+        self.write(record.level(), &colored, &record_body);
+    }
 
     fn flush(&self) {
         self.log_file
@@ -9,6 +9,7 @@ pub mod condense_lines;
 pub mod dm_file;
 pub mod exit_codes;
 pub mod log;
+pub mod symbol;
 pub mod whitespace_char;
 
 pub struct ParseError {
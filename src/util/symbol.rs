@@ -0,0 +1,112 @@
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Display},
+    sync::RwLock,
+};
+
+use once_cell::sync::Lazy;
+
+/// An interned string. Comparing and hashing symbols only compares their ids.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Symbol(u32);
+
+#[derive(Default)]
+struct Interner {
+    ids: HashMap<&'static str, Symbol>,
+    strings: Vec<&'static str>,
+}
+
+// Interned strings live for the rest of the program, identifiers are few enough that this is fine.
+static INTERNER: Lazy<RwLock<Interner>> = Lazy::new(Default::default);
+
+impl Symbol {
+    /// Returns the symbol for `string`, interning it if this is the first time it is seen.
+    pub fn intern(string: &str) -> Self {
+        if let Some(symbol) = Self::lookup(string) {
+            return symbol;
+        }
+
+        let mut interner = INTERNER.write().expect("symbol interner poisoned");
+        if let Some(symbol) = interner.ids.get(string) {
+            return *symbol;
+        }
+        let string: &'static str = Box::leak(string.into());
+        let symbol = Symbol(interner.strings.len() as u32);
+        interner.strings.push(string);
+        interner.ids.insert(string, symbol);
+        symbol
+    }
+
+    /// Returns the symbol for `string` if it has already been interned.
+    pub fn lookup(string: &str) -> Option<Self> {
+        INTERNER
+            .read()
+            .expect("symbol interner poisoned")
+            .ids
+            .get(string)
+            .copied()
+    }
+
+    pub fn as_str(self) -> &'static str {
+        INTERNER.read().expect("symbol interner poisoned").strings[self.0 as usize]
+    }
+
+    pub fn id(self) -> u32 {
+        self.0
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl Debug for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Symbol({:?})", self.as_str())
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(value: &str) -> Self {
+        Self::intern(value)
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intern_same_string_same_symbol() {
+        assert_eq!(
+            Symbol::intern("symbol_test_a"),
+            Symbol::intern("symbol_test_a")
+        );
+        assert_ne!(
+            Symbol::intern("symbol_test_a"),
+            Symbol::intern("symbol_test_b")
+        );
+    }
+
+    #[test]
+    fn lookup_does_not_intern() {
+        assert_eq!(Symbol::lookup("symbol_test_never_interned"), None);
+        let symbol = Symbol::intern("symbol_test_c");
+        assert_eq!(Symbol::lookup("symbol_test_c"), Some(symbol));
+        assert_eq!(symbol.as_str(), "symbol_test_c");
+    }
+}